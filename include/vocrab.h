@@ -0,0 +1,139 @@
+#ifndef VOCRAB_H
+#define VOCRAB_H
+
+/* Generated by cbindgen from src/ffi.rs; do not edit by hand. */
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+// Outcome of a call, one per `LemmatizerError` variant plus argument errors
+typedef enum VocrabStatus {
+  VOCRAB_STATUS_OK = 0,
+  VOCRAB_STATUS_NULL_ARGUMENT = 1,
+  VOCRAB_STATUS_INVALID_UTF8 = 2,
+  VOCRAB_STATUS_FILE_IO_FAILED = 3,
+  VOCRAB_STATUS_JSON_PARSE_FAILED = 4,
+  VOCRAB_STATUS_UNKNOWN_FORMAT = 5,
+  VOCRAB_STATUS_MALFORMED_FILE = 6,
+  VOCRAB_STATUS_ANALYZER_FAILED = 7,
+  VOCRAB_STATUS_JSON_SERIALIZE_FAILED = 8,
+} VocrabStatus;
+
+typedef struct VocrabLemmatizer VocrabLemmatizer;
+
+// A list of strings, such as ranked lemmas or the forms of a lemma
+typedef struct VocrabStrings VocrabStrings;
+
+// The sentences a lemma or form is used in
+typedef struct VocrabUsages VocrabUsages;
+
+// One usage, borrowed from the `VocrabUsages` it was read from
+typedef struct VocrabUsage {
+  const char *before;
+  const char *word;
+  const char *after;
+} VocrabUsage;
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+// The message of the last failed call on this thread, or `NULL`. Valid
+// until the next `vocrab_*` call on the thread.
+const char *vocrab_last_error_message(void);
+
+VocrabLemmatizer *vocrab_lemmatizer_new(void);
+
+// # Safety
+// `lemmatizer` is `NULL` or came from `vocrab_lemmatizer_new` and hasn't
+// been freed.
+void vocrab_lemmatizer_free(VocrabLemmatizer *lemmatizer);
+
+// Adds a document written by `tokenizer.py`
+//
+// # Safety
+// `lemmatizer` is a live handle and `path` a NUL-terminated string.
+VocrabStatus vocrab_load_file(VocrabLemmatizer *lemmatizer, const char *path);
+
+// Adds a document from `len` bytes of JSON in the layout `tokenizer.py`
+// writes, which need not be NUL-terminated
+//
+// # Safety
+// `lemmatizer` is a live handle, `name` a NUL-terminated string and `json`
+// points to at least `len` readable bytes.
+VocrabStatus vocrab_load_buffer(VocrabLemmatizer *lemmatizer,
+                                const char *name,
+                                const char *json,
+                                size_t len);
+
+// Number of documents loaded
+//
+// # Safety
+// `lemmatizer` is `NULL` or a live handle.
+size_t vocrab_document_count(const VocrabLemmatizer *lemmatizer);
+
+// Lemmas, most used first, or `NULL` on error
+//
+// # Safety
+// `lemmatizer` is a live handle.
+VocrabStrings *vocrab_get_lemmas(const VocrabLemmatizer *lemmatizer);
+
+// Number of times any form of a lemma is used; 0 on error
+//
+// # Safety
+// `lemmatizer` is a live handle and `lemma` a NUL-terminated string.
+size_t vocrab_get_lemma_count(const VocrabLemmatizer *lemmatizer, const char *lemma);
+
+// Forms of a lemma, most used first, or `NULL` on error. A lemma that
+// isn't in the documents has no forms.
+//
+// # Safety
+// `lemmatizer` is a live handle and `lemma` a NUL-terminated string.
+VocrabStrings *vocrab_get_forms(const VocrabLemmatizer *lemmatizer, const char *lemma);
+
+// # Safety
+// `strings` is `NULL` or a live handle.
+size_t vocrab_strings_len(const VocrabStrings *strings);
+
+// The string at `index`, or `NULL` past the end
+//
+// # Safety
+// `strings` is `NULL` or a live handle.
+const char *vocrab_strings_get(const VocrabStrings *strings, size_t index);
+
+// # Safety
+// `strings` is `NULL` or a live handle, which is invalid afterwards.
+void vocrab_strings_free(VocrabStrings *strings);
+
+// Usages of a form of a lemma in text order, of every form when `form` is
+// `NULL`, or `NULL` on error
+//
+// # Safety
+// `lemmatizer` is a live handle, `lemma` a NUL-terminated string and
+// `form` `NULL` or a NUL-terminated string.
+VocrabUsages *vocrab_get_usages(const VocrabLemmatizer *lemmatizer,
+                                const char *lemma,
+                                const char *form);
+
+// # Safety
+// `usages` is `NULL` or a live handle.
+size_t vocrab_usages_len(const VocrabUsages *usages);
+
+// Reads the usage at `index` into `out`. Returns `false` past the end.
+//
+// # Safety
+// `usages` is `NULL` or a live handle and `out` is `NULL` or writable.
+bool vocrab_usages_get(const VocrabUsages *usages, size_t index, VocrabUsage *out);
+
+// # Safety
+// `usages` is `NULL` or a live handle, which is invalid afterwards.
+void vocrab_usages_free(VocrabUsages *usages);
+
+#ifdef __cplusplus
+} // extern "C"
+#endif // __cplusplus
+
+#endif // VOCRAB_H
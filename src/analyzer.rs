@@ -0,0 +1,142 @@
+use crate::lemmatizer::{LemmatizerError, Token};
+use std::io::Write;
+use std::process::{Command, Stdio};
+use std::thread;
+
+/// How far past the last token found the next one may be, in bytes of
+/// text, so a token the analyzer changed doesn't match much later on
+const MAX_SKIP: usize = 200;
+
+/// Turns raw text into the document JSON that `Lemmatizer::load_json` reads
+pub trait Analyzer {
+    /// `language` is whatever the analyzer understands, e.g. `spanish` or
+    /// `es` for `tokenizer.py`
+    fn analyze(&self, text: &str, language: &str) -> Result<String, LemmatizerError>;
+}
+
+/// Runs an external program with the language as its last argument, the
+/// text on stdin and the JSON on stdout
+#[derive(Clone, Debug)]
+pub struct CommandAnalyzer {
+    program: String,
+    args: Vec<String>,
+}
+
+impl CommandAnalyzer {
+    /// Splits a command line such as `python3 src/tokenizer.py --stdout -`
+    /// on whitespace
+    pub fn new(command: &str) -> Option<CommandAnalyzer> {
+        let mut words = command.split_whitespace().map(str::to_string);
+        Some(CommandAnalyzer {
+            program: words.next()?,
+            args: words.collect(),
+        })
+    }
+}
+
+impl Analyzer for CommandAnalyzer {
+    fn analyze(&self, text: &str, language: &str) -> Result<String, LemmatizerError> {
+        let mut child = Command::new(&self.program)
+            .args(&self.args)
+            .arg(language)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .map_err(|e| LemmatizerError::AnalyzerFailed(format!("{}: {}", self.program, e)))?;
+        // The text is written from another thread while the output is read,
+        // since an analyzer that fills the stdout or stderr pipe stops
+        // reading until they're drained
+        let output = thread::scope(|scope| {
+            if let Some(mut stdin) = child.stdin.take() {
+                scope.spawn(move || {
+                    // The analyzer may exit without reading, e.g. for an
+                    // unknown language; its status says why
+                    let _ = stdin.write_all(text.as_bytes());
+                });
+            }
+            child.wait_with_output()
+        })
+        .map_err(LemmatizerError::FileIOFailed)?;
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            let reason = stderr.lines().last().unwrap_or("").trim();
+            return Err(LemmatizerError::AnalyzerFailed(format!(
+                "{} exited with {}: {}",
+                self.program, output.status, reason
+            )));
+        }
+        String::from_utf8(output.stdout)
+            .map_err(|_| LemmatizerError::AnalyzerFailed("output isn't UTF-8".to_string()))
+    }
+}
+
+/// Where each token of analyzed sentences is in the text they were analyzed
+/// from, as a byte offset. Tokens are found in order, and those the analyzer
+/// changed, like an ellipsis it wrote as a period, are `None`.
+pub(crate) fn token_offsets(text: &str, sentences: &[Vec<Token>]) -> Vec<Vec<Option<usize>>> {
+    let mut cursor = 0;
+    sentences
+        .iter()
+        .map(|sentence| {
+            sentence
+                .iter()
+                .map(|token| {
+                    let offset = text[cursor..]
+                        .find(&token.text)
+                        .filter(|offset| *offset < MAX_SKIP)?;
+                    let offset = cursor + offset;
+                    cursor = offset + token.text.len();
+                    Some(offset)
+                })
+                .collect()
+        })
+        .collect()
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use serde_json::json;
+
+    /// Splits text on whitespace, and into sentences after words ending in a
+    /// period, lemmatizing each word as itself
+    pub(crate) struct SplitAnalyzer;
+
+    impl Analyzer for SplitAnalyzer {
+        fn analyze(&self, text: &str, language: &str) -> Result<String, LemmatizerError> {
+            let mut sentences = vec![Vec::new()];
+            for word in text.split_whitespace() {
+                let sentence = sentences.last_mut().unwrap();
+                sentence.push(json!({ "text": word, "lemma": word, "pos": "X" }));
+                if word.ends_with('.') {
+                    sentences.push(Vec::new());
+                }
+            }
+            sentences.retain(|sentence| !sentence.is_empty());
+            Ok(json!({ "lang": language, "sentences": sentences }).to_string())
+        }
+    }
+
+    #[test]
+    fn tokens_are_found_in_order_within_reach() {
+        let token = |text: &str| Token {
+            text: text.to_string(),
+            lemma: text.to_string(),
+            pos: "X".to_string(),
+        };
+        let text = format!("uno dos … uno{}tres", " ".repeat(MAX_SKIP + 1));
+        let sentences = vec![
+            vec![token("uno"), token("dos"), token("...")],
+            vec![token("uno"), token("tres")],
+        ];
+        assert_eq!(
+            token_offsets(&text, &sentences),
+            vec![
+                vec![Some(0), Some(4), None],
+                // "tres" is too far past "uno" to be the same text
+                vec![Some(12), None],
+            ]
+        );
+    }
+}
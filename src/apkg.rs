@@ -0,0 +1,403 @@
+use crate::export::{escape, Card};
+use crate::zip::write_zip;
+use rusqlite::{params, Connection, DatabaseName};
+use serde_json::json;
+use sha1_smol::Sha1;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Note type of every exported card. Kept fixed so that importing a later
+/// package reuses the note type instead of adding another.
+const MODEL_ID: i64 = 1_634_567_890_123;
+
+/// Fields of the note type, in the order of the TSV export's columns
+const FIELDS: [&str; 7] = [
+    "Sentence",
+    "Lemma",
+    "Form",
+    "Definition",
+    "Gloss",
+    "Note",
+    "Timestamp",
+];
+
+const FRONT: &str = "{{Sentence}}";
+const BACK: &str = "{{FrontSide}}<hr id=answer>{{Lemma}} <i>{{Form}}</i>\
+{{#Definition}}<div>{{Definition}}</div>{{/Definition}}\
+{{#Gloss}}<div>{{Gloss}}</div>{{/Gloss}}\
+{{#Note}}<div>{{Note}}</div>{{/Note}}\
+{{#Timestamp}}<div>{{Timestamp}}</div>{{/Timestamp}}";
+const CSS: &str = ".card { font-family: arial; font-size: 20px; text-align: center; }";
+
+/// Tables of the Anki collection format packages hold (schema 11)
+const SCHEMA: &str = "
+create table col (
+    id integer primary key, crt integer not null, mod integer not null,
+    scm integer not null, ver integer not null, dty integer not null,
+    usn integer not null, ls integer not null, conf text not null,
+    models text not null, decks text not null, dconf text not null,
+    tags text not null
+);
+create table notes (
+    id integer primary key, guid text not null, mid integer not null,
+    mod integer not null, usn integer not null, tags text not null,
+    flds text not null, sfld integer not null, csum integer not null,
+    flags integer not null, data text not null
+);
+create table cards (
+    id integer primary key, nid integer not null, did integer not null,
+    ord integer not null, mod integer not null, usn integer not null,
+    type integer not null, queue integer not null, due integer not null,
+    ivl integer not null, factor integer not null, reps integer not null,
+    lapses integer not null, left integer not null, odue integer not null,
+    odid integer not null, flags integer not null, data text not null
+);
+create table revlog (
+    id integer primary key, cid integer not null, usn integer not null,
+    ease integer not null, ivl integer not null, lastIvl integer not null,
+    factor integer not null, time integer not null, type integer not null
+);
+create table graves (
+    usn integer not null, oid integer not null, type integer not null
+);
+create index ix_notes_usn on notes (usn);
+create index ix_cards_usn on cards (usn);
+create index ix_revlog_usn on revlog (usn);
+create index ix_cards_nid on cards (nid);
+create index ix_cards_sched on cards (did, queue, due);
+create index ix_revlog_cid on revlog (cid);
+create index ix_notes_csum on notes (csum);
+";
+
+/// Writes cards as an Anki package, which Anki's "Import File" adds as a
+/// deck named after the file. Cards exported again from the same sentence
+/// update the notes imported before rather than duplicating them.
+pub fn write_anki_package<P: AsRef<Path>>(cards: &[Card], path: P) -> io::Result<()> {
+    let path = path.as_ref();
+    let deck = path
+        .file_stem()
+        .map_or("vocrab".to_string(), |s| s.to_string_lossy().to_string());
+    let collection = anki_collection(cards, &deck).map_err(|e| io::Error::other(e.to_string()))?;
+    let archive = write_zip(&[
+        ("collection.anki2", &collection),
+        // Exported cards have no pictures or sounds
+        ("media", b"{}"),
+    ])?;
+    fs::write(path, archive)
+}
+
+/// An Anki collection database holding one deck of `cards`
+fn anki_collection(cards: &[Card], deck: &str) -> rusqlite::Result<Vec<u8>> {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_millis() as i64);
+    let seconds = now / 1000;
+    // Kept below 2^53, so JavaScript reads it exactly, as Anki ids are
+    let deck_id = (sha1_prefix(deck) >> 11) as i64;
+
+    let connection = Connection::open_in_memory()?;
+    connection.execute_batch(SCHEMA)?;
+    connection.execute(
+        "insert into col values (1, ?, ?, ?, 11, 0, 0, 0, ?, ?, ?, ?, '{}')",
+        params![
+            seconds,
+            now,
+            now,
+            collection_config(deck_id).to_string(),
+            json!({ MODEL_ID.to_string(): note_type(deck_id, seconds) }).to_string(),
+            json!({
+                "1": deck_json(1, "Default", seconds),
+                deck_id.to_string(): deck_json(deck_id, deck, seconds),
+            })
+            .to_string(),
+            json!({ "1": deck_options() }).to_string(),
+        ],
+    )?;
+
+    for (i, card) in cards.iter().enumerate() {
+        // Ids are creation times in milliseconds, so they must differ
+        let id = now + i as i64;
+        let fields = [
+            format!(
+                "{}<b>{}</b>{}",
+                escape(&card.before),
+                escape(&card.word),
+                escape(&card.after)
+            ),
+            escape(&card.lemma),
+            escape(&card.form),
+            escape(&card.definition),
+            escape(&card.gloss),
+            escape(&card.note),
+            escape(&card.timestamp),
+        ];
+        let sentence = card.sentence();
+        connection.execute(
+            "insert into notes values (?, ?, ?, ?, -1, ' vocrab ', ?, ?, ?, 0, '')",
+            params![
+                id,
+                guid(card),
+                MODEL_ID,
+                seconds,
+                fields.join("\u{1f}"),
+                sentence,
+                // Anki finds duplicates by this checksum of the first field
+                (sha1_prefix(&sentence) >> 32) as i64,
+            ],
+        )?;
+        connection.execute(
+            "insert into cards values (?, ?, ?, 0, ?, -1, 0, 0, ?, 0, 0, 0, 0, 0, 0, 0, 0, '')",
+            params![id, id, deck_id, seconds, i as i64],
+        )?;
+    }
+    Ok(connection.serialize(DatabaseName::Main)?.to_vec())
+}
+
+/// First 8 bytes of the SHA-1 of `text`
+fn sha1_prefix(text: &str) -> u64 {
+    let digest = Sha1::from(text).digest().bytes();
+    let mut bytes = [0; 8];
+    bytes.copy_from_slice(&digest[..8]);
+    u64::from_be_bytes(bytes)
+}
+
+/// Note id Anki matches notes by on import, the same for the same sentence
+/// and lemma
+fn guid(card: &Card) -> String {
+    let key = format!("{}\u{1f}{}", card.lemma, card.sentence());
+    Sha1::from(key).digest().to_string()[..16].to_string()
+}
+
+fn collection_config(deck_id: i64) -> serde_json::Value {
+    json!({
+        "activeDecks": [deck_id],
+        "curDeck": deck_id,
+        "newSpread": 0,
+        "collapseTime": 1200,
+        "timeLim": 0,
+        "estTimes": true,
+        "dueCounts": true,
+        "curModel": MODEL_ID.to_string(),
+        "nextPos": 1,
+        "sortType": "noteFld",
+        "sortBackwards": false,
+        "addToCur": true,
+    })
+}
+
+fn note_type(deck_id: i64, seconds: i64) -> serde_json::Value {
+    let fields: Vec<serde_json::Value> = FIELDS
+        .iter()
+        .enumerate()
+        .map(|(i, name)| {
+            json!({
+                "name": name,
+                "ord": i,
+                "sticky": false,
+                "rtl": false,
+                "font": "Arial",
+                "size": 20,
+                "media": [],
+            })
+        })
+        .collect();
+    json!({
+        "id": MODEL_ID,
+        "name": "vocrab",
+        "type": 0,
+        "mod": seconds,
+        "usn": -1,
+        "sortf": 0,
+        "did": deck_id,
+        "tmpls": [{
+            "name": "Recognition",
+            "ord": 0,
+            "qfmt": FRONT,
+            "afmt": BACK,
+            "did": null,
+            "bqfmt": "",
+            "bafmt": "",
+        }],
+        "flds": fields,
+        "css": CSS,
+        "latexPre": "\\documentclass[12pt]{article}\n\\special{papersize=3in,5in}\n\
+    \\usepackage[utf8]{inputenc}\n\\usepackage{amssymb,amsmath}\n\\pagestyle{empty}\n\
+    \\setlength{\\parindent}{0in}\n\\begin{document}\n",
+        "latexPost": "\\end{document}",
+        "latexsvg": false,
+        "req": [[0, "any", [0]]],
+        "tags": [],
+        "vers": [],
+    })
+}
+
+fn deck_json(id: i64, name: &str, seconds: i64) -> serde_json::Value {
+    json!({
+        "id": id,
+        "name": name,
+        "mod": seconds,
+        "usn": -1,
+        "lrnToday": [0, 0],
+        "revToday": [0, 0],
+        "newToday": [0, 0],
+        "timeToday": [0, 0],
+        "collapsed": false,
+        "browserCollapsed": false,
+        "desc": "",
+        "dyn": 0,
+        "conf": 1,
+        "extendNew": 0,
+        "extendRev": 0,
+    })
+}
+
+/// Anki's default deck options
+fn deck_options() -> serde_json::Value {
+    json!({
+        "id": 1,
+        "name": "Default",
+        "mod": 0,
+        "usn": 0,
+        "maxTaken": 60,
+        "autoplay": true,
+        "timer": 0,
+        "replayq": true,
+        "dyn": false,
+        "new": {
+            "bury": false,
+            "delays": [1.0, 10.0],
+            "initialFactor": 2500,
+            "ints": [1, 4, 0],
+            "order": 1,
+            "perDay": 20,
+        },
+        "lapse": {
+            "delays": [10.0],
+            "leechAction": 1,
+            "leechFails": 8,
+            "minInt": 1,
+            "mult": 0.0,
+        },
+        "rev": {
+            "bury": false,
+            "ease4": 1.3,
+            "ivlFct": 1.0,
+            "maxIvl": 36500,
+            "perDay": 200,
+            "hardFactor": 1.2,
+        },
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::zip::ZipArchive;
+    use std::path::PathBuf;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("vocrab-apkg-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn cards() -> Vec<Card> {
+        let usage = |before: &str, word: &str, after: &str| {
+            (before.to_string(), word.to_string(), after.to_string())
+        };
+        let mut cat = Card::new("gato", "gatos", &usage("Los ", "gatos", " <comen>."));
+        cat.definition = "cat".to_string();
+        cat.gloss = "feline".to_string();
+        let dog = Card::new("perro", "perro", &usage("El ", "perro", " duerme."));
+        vec![cat, dog]
+    }
+
+    /// The collection inside a package, opened from a copy next to it
+    fn open_package(path: &Path) -> Connection {
+        let archive = ZipArchive::new(fs::read(path).unwrap()).unwrap();
+        assert_eq!(archive.read("media").unwrap(), b"{}");
+        let collection = path.with_extension("anki2");
+        fs::write(&collection, archive.read("collection.anki2").unwrap()).unwrap();
+        Connection::open(collection).unwrap()
+    }
+
+    fn guids(connection: &Connection) -> Vec<String> {
+        let mut statement = connection
+            .prepare("select guid from notes order by id")
+            .unwrap();
+        let guids = statement.query_map([], |row| row.get(0)).unwrap();
+        guids.map(Result::unwrap).collect()
+    }
+
+    #[test]
+    fn notes_hold_every_field() {
+        let dir = temp_dir("fields");
+        let path = dir.join("Spanish words.apkg");
+        write_anki_package(&cards(), &path).unwrap();
+        let connection = open_package(&path);
+
+        let mut statement = connection
+            .prepare("select flds, sfld, mid from notes order by id")
+            .unwrap();
+        let notes: Vec<(String, String, i64)> = statement
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))
+            .unwrap()
+            .map(Result::unwrap)
+            .collect();
+        assert_eq!(
+            notes[0].0.split('\u{1f}').collect::<Vec<&str>>(),
+            vec![
+                "Los <b>gatos</b> &lt;comen&gt;.",
+                "gato",
+                "gatos",
+                "cat",
+                "feline",
+                "",
+                ""
+            ]
+        );
+        assert_eq!(notes[0].1, "Los gatos <comen>.");
+        assert_eq!(notes[1].1, "El perro duerme.");
+        assert!(notes.iter().all(|note| note.2 == MODEL_ID));
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn cards_go_in_a_deck_named_after_the_file() {
+        let dir = temp_dir("deck");
+        let path = dir.join("Spanish words.apkg");
+        write_anki_package(&cards(), &path).unwrap();
+        let connection = open_package(&path);
+
+        let decks: String = connection
+            .query_row("select decks from col", [], |row| row.get(0))
+            .unwrap();
+        let decks: serde_json::Value = serde_json::from_str(&decks).unwrap();
+        let deck_ids: Vec<i64> = {
+            let mut statement = connection.prepare("select did from cards").unwrap();
+            let ids = statement.query_map([], |row| row.get(0)).unwrap();
+            ids.map(Result::unwrap).collect()
+        };
+        assert_eq!(deck_ids.len(), 2);
+        let deck = &decks[deck_ids[0].to_string()];
+        assert_eq!(deck["name"], "Spanish words");
+        assert!(deck_ids.iter().all(|id| *id == deck_ids[0]));
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn exporting_again_keeps_the_guids() {
+        let dir = temp_dir("guids");
+        let (first, second) = (dir.join("first.apkg"), dir.join("second.apkg"));
+        write_anki_package(&cards(), &first).unwrap();
+        write_anki_package(&cards()[1..], &second).unwrap();
+        let (first, second) = (guids(&open_package(&first)), guids(&open_package(&second)));
+        assert_eq!(first.len(), 2);
+        assert_ne!(first[0], first[1]);
+        assert_eq!(second, vec![first[1].clone()]);
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,319 @@
+use std::fs;
+use std::ops::RangeInclusive;
+use std::path::Path;
+use thiserror::Error;
+
+/// Command names and how they're used, for completion and error messages
+const COMMANDS: [(&str, &str); 7] = [
+    ("compare", "compare PATH"),
+    ("export", "export anki PATH"),
+    ("filter", "filter [pos=TAG[,TAG]] [known=yes|no]"),
+    ("goto", "goto RANK|LEMMA"),
+    ("known", "known RANKS, e.g. 1-500,600"),
+    ("load", "load PATH"),
+    ("sort", "sort freq|alpha|forms|adjusted"),
+];
+
+const SORT_KEYS: [(&str, SortKey); 4] = [
+    ("freq", SortKey::Frequency),
+    ("alpha", SortKey::Alphabetical),
+    ("forms", SortKey::Forms),
+    ("adjusted", SortKey::Adjusted),
+];
+
+const EXPORT_FORMATS: [&str; 1] = ["anki"];
+
+const FILTERS: [&str; 3] = ["pos=", "known=yes", "known=no"];
+
+/// Order of the lemma list
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SortKey {
+    /// Most used first
+    Frequency,
+    Alphabetical,
+    /// Most forms first
+    Forms,
+    /// Most used first, discounted by how unevenly the lemma is spread
+    /// through the paragraphs
+    Adjusted,
+}
+
+/// A condition lemmas must meet to stay in the lemma list
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Filter {
+    /// The lemma's most common part of speech is one of these
+    Pos(Vec<String>),
+    Known(bool),
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Target {
+    /// Position in the list, from 1
+    Rank(usize),
+    Lemma(String),
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Command {
+    /// Adds a document to the library
+    Load(String),
+    /// Adds a document to the texts the library is compared with
+    Compare(String),
+    Sort(SortKey),
+    /// Replaces the lemma filters; none shows every lemma
+    Filter(Vec<Filter>),
+    /// Writes the queued cards as an Anki package if the path ends in
+    /// .apkg, or else as an Anki TSV file
+    Export(String),
+    Goto(Target),
+    /// Marks lemmas known by their rank in the lemma list
+    Known(Vec<RangeInclusive<usize>>),
+}
+
+#[derive(Error, Debug)]
+pub enum CommandError {
+    #[error("unknown command :{0}")]
+    Unknown(String),
+    #[error(":{0} could be any of {1}")]
+    Ambiguous(String, String),
+    #[error("usage: :{0}")]
+    Usage(&'static str),
+    #[error("unknown sort order \"{0}\", expected freq, alpha, forms or adjusted")]
+    SortKey(String),
+    #[error("unknown filter \"{0}\", expected pos=TAG or known=yes|no")]
+    Filter(String),
+    #[error("unknown export format \"{0}\", expected anki")]
+    Format(String),
+    #[error("\"{0}\" isn't a rank or a range of ranks like 1-500")]
+    Ranks(String),
+}
+
+/// The text a tab completes to, and every candidate when there is more than
+/// one
+pub struct Completion {
+    pub text: String,
+    pub candidates: Vec<String>,
+}
+
+/// Parses a command line, without its `:`. Commands can be shortened to any
+/// unambiguous prefix.
+pub fn parse(line: &str) -> Result<Command, CommandError> {
+    let line = line.trim();
+    let (name, args) = match line.split_once(char::is_whitespace) {
+        Some((name, args)) => (name, args.trim()),
+        None => (line, ""),
+    };
+    let (name, usage) = find_command(name)?;
+    let words: Vec<&str> = args.split_whitespace().collect();
+    let command = match name {
+        "load" if !args.is_empty() => Command::Load(args.to_string()),
+        "compare" if !args.is_empty() => Command::Compare(args.to_string()),
+        "sort" => match words[..] {
+            [key] => SORT_KEYS
+                .iter()
+                .find(|(name, _)| *name == key)
+                .map(|(_, key)| Command::Sort(*key))
+                .ok_or_else(|| CommandError::SortKey(key.to_string()))?,
+            _ => return Err(CommandError::Usage(usage)),
+        },
+        "filter" => Command::Filter(
+            words
+                .iter()
+                .map(|word| parse_filter(word))
+                .collect::<Result<Vec<Filter>, CommandError>>()?,
+        ),
+        "export" => match args.split_once(char::is_whitespace) {
+            Some(("anki", path)) => Command::Export(path.trim().to_string()),
+            Some((format, _)) => return Err(CommandError::Format(format.to_string())),
+            None => return Err(CommandError::Usage(usage)),
+        },
+        "goto" => match words[..] {
+            [target] => Command::Goto(match target.parse::<usize>() {
+                Ok(rank) => Target::Rank(rank),
+                Err(_) => Target::Lemma(target.to_lowercase()),
+            }),
+            _ => return Err(CommandError::Usage(usage)),
+        },
+        "known" if !words.is_empty() => Command::Known(
+            words
+                .join(",")
+                .split(',')
+                .filter(|range| !range.is_empty())
+                .map(parse_ranks)
+                .collect::<Result<Vec<RangeInclusive<usize>>, CommandError>>()?,
+        ),
+        _ => return Err(CommandError::Usage(usage)),
+    };
+    Ok(command)
+}
+
+/// Completes the word being typed at the end of a command line: a command
+/// name, a fixed argument or a file path.
+pub fn complete(line: &str) -> Completion {
+    let (name, args) = match line.split_once(char::is_whitespace) {
+        Some((name, args)) => (name, args.trim_start()),
+        None => {
+            let names: Vec<String> = COMMANDS
+                .iter()
+                .map(|(name, _)| name.to_string())
+                .filter(|name| name.starts_with(line))
+                .collect();
+            let mut completion = complete_from(line, names);
+            if completion.candidates.len() == 1 {
+                completion.text.push(' ');
+            }
+            return completion;
+        }
+    };
+    let name = match find_command(name) {
+        Ok((name, _)) => name,
+        Err(_) => {
+            return Completion {
+                text: line.to_string(),
+                candidates: Vec::new(),
+            }
+        }
+    };
+    // Everything up to the word being completed is kept as typed
+    let args_start = line.len() - args.len();
+    let (word_start, candidates) = match name {
+        "load" | "compare" => (args_start, path_candidates(args)),
+        "export" => match args.split_once(char::is_whitespace) {
+            Some((_, path)) => {
+                let path = path.trim_start();
+                (line.len() - path.len(), path_candidates(path))
+            }
+            None => (args_start, fixed_candidates(args, &EXPORT_FORMATS)),
+        },
+        _ => {
+            let word = line.rsplit(char::is_whitespace).next().unwrap_or("");
+            let candidates = match name {
+                "sort" => {
+                    let keys: Vec<&str> = SORT_KEYS.iter().map(|(name, _)| *name).collect();
+                    fixed_candidates(word, &keys)
+                }
+                "filter" => fixed_candidates(word, &FILTERS),
+                _ => Vec::new(),
+            };
+            (line.len() - word.len(), candidates)
+        }
+    };
+    let (head, word) = line.split_at(word_start);
+    let completion = complete_from(word, candidates);
+    Completion {
+        text: format!("{}{}", head, completion.text),
+        candidates: completion.candidates,
+    }
+}
+
+fn find_command(name: &str) -> Result<(&'static str, &'static str), CommandError> {
+    let matches: Vec<&(&str, &str)> = COMMANDS
+        .iter()
+        .filter(|(command, _)| command.starts_with(name))
+        .collect();
+    match matches[..] {
+        _ if name.is_empty() => Err(CommandError::Unknown(String::new())),
+        [command] => Ok(*command),
+        [] => Err(CommandError::Unknown(name.to_string())),
+        _ => match matches.iter().find(|(command, _)| *command == name) {
+            Some(command) => Ok(**command),
+            None => Err(CommandError::Ambiguous(
+                name.to_string(),
+                matches
+                    .iter()
+                    .map(|(command, _)| *command)
+                    .collect::<Vec<&str>>()
+                    .join(", "),
+            )),
+        },
+    }
+}
+
+fn parse_filter(word: &str) -> Result<Filter, CommandError> {
+    match word.split_once('=') {
+        Some(("pos", tags)) if !tags.is_empty() => Ok(Filter::Pos(
+            tags.split(',').map(|tag| tag.to_uppercase()).collect(),
+        )),
+        Some(("known", "yes")) => Ok(Filter::Known(true)),
+        Some(("known", "no")) => Ok(Filter::Known(false)),
+        _ => Err(CommandError::Filter(word.to_string())),
+    }
+}
+
+/// A rank such as `7`, or a range of ranks such as `1-500`
+fn parse_ranks(text: &str) -> Result<RangeInclusive<usize>, CommandError> {
+    let error = || CommandError::Ranks(text.to_string());
+    let rank = |s: &str| match s.trim().parse::<usize>() {
+        Ok(0) | Err(_) => Err(error()),
+        Ok(rank) => Ok(rank),
+    };
+    let (first, last) = match text.split_once('-') {
+        Some((first, last)) => (rank(first)?, rank(last)?),
+        None => (rank(text)?, rank(text)?),
+    };
+    match first <= last {
+        true => Ok(first..=last),
+        false => Err(error()),
+    }
+}
+
+fn fixed_candidates(word: &str, options: &[&str]) -> Vec<String> {
+    options
+        .iter()
+        .filter(|option| option.starts_with(word))
+        .map(|option| option.to_string())
+        .collect()
+}
+
+/// Files and directories starting with the last part of a path. Directories
+/// end in `/` so completion can carry on into them.
+fn path_candidates(word: &str) -> Vec<String> {
+    let (dir, prefix) = match word.rfind('/') {
+        Some(i) => word.split_at(i + 1),
+        None => ("", word),
+    };
+    let entries = match fs::read_dir(if dir.is_empty() {
+        Path::new(".")
+    } else {
+        Path::new(dir)
+    }) {
+        Ok(entries) => entries,
+        Err(_) => return Vec::new(),
+    };
+    let mut candidates: Vec<String> = entries
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| {
+            let name = entry.file_name().to_string_lossy().to_string();
+            // Hidden files only when asked for
+            if !name.starts_with(prefix) || (name.starts_with('.') && !prefix.starts_with('.')) {
+                return None;
+            }
+            let slash = match entry.path().is_dir() {
+                true => "/",
+                false => "",
+            };
+            Some(format!("{}{}{}", dir, name, slash))
+        })
+        .collect();
+    candidates.sort();
+    candidates
+}
+
+/// Completes a word to the longest prefix shared by the candidates
+fn complete_from(word: &str, candidates: Vec<String>) -> Completion {
+    let text = match candidates.split_first() {
+        Some((first, rest)) => {
+            let mut prefix = first.as_str();
+            for candidate in rest {
+                while !candidate.starts_with(prefix) {
+                    let mut chars = prefix.chars();
+                    chars.next_back();
+                    prefix = chars.as_str();
+                }
+            }
+            prefix.to_string()
+        }
+        None => word.to_string(),
+    };
+    Completion { text, candidates }
+}
@@ -0,0 +1,441 @@
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use std::collections::HashMap;
+use std::fs;
+use std::io::{self, ErrorKind};
+use std::path::Path;
+use thiserror::Error;
+
+/// Something a key sequence can be bound to
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Action {
+    Quit,
+    Back,
+    Enter,
+    MoveDown,
+    MoveUp,
+    PageDown,
+    PageUp,
+    /// First item, or the item numbered by the count
+    First,
+    /// Last item, or the item numbered by the count
+    Last,
+    Mining,
+    Reader,
+    Plan,
+    Review,
+    ToggleKnown,
+    QueueCard,
+    ToggleLearning,
+    EditGloss,
+    EditNote,
+    /// Open the `:` command line
+    Command,
+    /// Switch to the tab at this index
+    Tab(usize),
+    Reveal,
+    GradeAgain,
+    GradeHard,
+    GradeGood,
+    GradeEasy,
+}
+
+/// Action names as written in the keymap file
+const ACTION_NAMES: [(&str, Action); 31] = [
+    ("quit", Action::Quit),
+    ("back", Action::Back),
+    ("enter", Action::Enter),
+    ("move_down", Action::MoveDown),
+    ("move_up", Action::MoveUp),
+    ("page_down", Action::PageDown),
+    ("page_up", Action::PageUp),
+    ("first", Action::First),
+    ("last", Action::Last),
+    ("mining", Action::Mining),
+    ("reader", Action::Reader),
+    ("plan", Action::Plan),
+    ("review", Action::Review),
+    ("toggle_known", Action::ToggleKnown),
+    ("queue_card", Action::QueueCard),
+    ("toggle_learning", Action::ToggleLearning),
+    ("edit_gloss", Action::EditGloss),
+    ("edit_note", Action::EditNote),
+    ("command", Action::Command),
+    ("tab_1", Action::Tab(0)),
+    ("tab_2", Action::Tab(1)),
+    ("tab_3", Action::Tab(2)),
+    ("tab_4", Action::Tab(3)),
+    ("tab_5", Action::Tab(4)),
+    ("tab_6", Action::Tab(5)),
+    ("tab_7", Action::Tab(6)),
+    ("reveal", Action::Reveal),
+    ("grade_again", Action::GradeAgain),
+    ("grade_hard", Action::GradeHard),
+    ("grade_good", Action::GradeGood),
+    ("grade_easy", Action::GradeEasy),
+];
+
+const DEFAULT_BINDINGS: &[(&str, &str)] = &[
+    ("q", "quit"),
+    ("h", "back"),
+    ("<Left>", "back"),
+    ("l", "enter"),
+    ("<Right>", "enter"),
+    ("<Enter>", "enter"),
+    ("j", "move_down"),
+    ("<Down>", "move_down"),
+    ("k", "move_up"),
+    ("<Up>", "move_up"),
+    ("<PageDown>", "page_down"),
+    ("<C-d>", "page_down"),
+    ("<PageUp>", "page_up"),
+    ("<C-u>", "page_up"),
+    ("gg", "first"),
+    ("<Home>", "first"),
+    ("G", "last"),
+    ("<End>", "last"),
+    ("i", "mining"),
+    ("r", "reader"),
+    ("p", "plan"),
+    ("v", "review"),
+    ("m", "toggle_known"),
+    ("a", "queue_card"),
+    ("n", "toggle_learning"),
+    ("d", "edit_gloss"),
+    ("e", "edit_note"),
+    (":", "command"),
+    // These digits no longer start counts; binding them to none in the
+    // keymap file brings back counts such as 12j
+    ("1", "tab_1"),
+    ("2", "tab_2"),
+    ("3", "tab_3"),
+    ("4", "tab_4"),
+    ("5", "tab_5"),
+    ("6", "tab_6"),
+    ("7", "tab_7"),
+    ("<F1>", "tab_1"),
+    ("<F2>", "tab_2"),
+    ("<F3>", "tab_3"),
+    ("<F4>", "tab_4"),
+    ("<F5>", "tab_5"),
+    ("<F6>", "tab_6"),
+    ("<F7>", "tab_7"),
+];
+
+const DEFAULT_REVIEW_BINDINGS: &[(&str, &str)] = &[
+    ("<Space>", "reveal"),
+    ("<Enter>", "reveal"),
+    ("1", "grade_again"),
+    ("2", "grade_hard"),
+    ("3", "grade_good"),
+    ("4", "grade_easy"),
+];
+
+/// Names of the keys that can't be written as a single character
+const KEY_NAMES: [(&str, KeyCode); 16] = [
+    ("enter", KeyCode::Enter),
+    ("esc", KeyCode::Esc),
+    ("space", KeyCode::Char(' ')),
+    ("tab", KeyCode::Tab),
+    ("backspace", KeyCode::Backspace),
+    ("delete", KeyCode::Delete),
+    ("insert", KeyCode::Insert),
+    ("up", KeyCode::Up),
+    ("down", KeyCode::Down),
+    ("left", KeyCode::Left),
+    ("right", KeyCode::Right),
+    ("pageup", KeyCode::PageUp),
+    ("pagedown", KeyCode::PageDown),
+    ("home", KeyCode::Home),
+    ("end", KeyCode::End),
+    ("lt", KeyCode::Char('<')),
+];
+
+#[derive(Error, Debug)]
+pub enum KeymapError {
+    #[error("failed to read the keymap file: {0}")]
+    FileIOFailed(#[from] io::Error),
+    #[error("line {line}: expected a key sequence and an action")]
+    Malformed { line: usize },
+    #[error("line {line}: unknown key \"{key}\"")]
+    UnknownKey { line: usize, key: String },
+    #[error("line {line}: unknown action \"{action}\"")]
+    UnknownAction { line: usize, action: String },
+    #[error("line {line}: unknown section [{section}], expected [normal] or [review]")]
+    UnknownSection { line: usize, section: String },
+    #[error("line {line}: \"{keys}\" is already bound to {action} on line {previous}")]
+    Rebound {
+        line: usize,
+        keys: String,
+        action: String,
+        previous: usize,
+    },
+    #[error(
+        "\"{prefix}\" ({prefix_action}) is the start of \"{keys}\" ({action}), so \"{keys}\" \
+         could never be typed; bind one of them to none"
+    )]
+    Shadowed {
+        prefix: String,
+        prefix_action: String,
+        keys: String,
+        action: String,
+    },
+}
+
+/// Result of looking up the keys typed so far
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Lookup {
+    Action(Action),
+    /// The keys start a longer binding
+    Pending,
+    Unbound,
+}
+
+/// Key sequences and the actions they run. Review mode has its own bindings
+/// laid over the normal ones.
+#[derive(Debug)]
+pub struct Keymap {
+    normal: HashMap<Vec<KeyEvent>, Action>,
+    review: HashMap<Vec<KeyEvent>, Action>,
+}
+
+impl Keymap {
+    /// Reads overrides for the default bindings. Each line is a key sequence
+    /// and an action, e.g. `<C-n> move_down`; `none` unbinds the keys, and a
+    /// `[review]` line starts the bindings used while reviewing. A missing
+    /// file keeps the defaults.
+    pub fn load_file<P: AsRef<Path>>(path: P) -> Result<Keymap, KeymapError> {
+        match fs::read_to_string(path) {
+            Ok(text) => Keymap::parse(&text),
+            Err(e) if e.kind() == ErrorKind::NotFound => Keymap::parse(""),
+            Err(e) => Err(KeymapError::FileIOFailed(e)),
+        }
+    }
+
+    pub fn parse(text: &str) -> Result<Keymap, KeymapError> {
+        let mut normal = defaults(DEFAULT_BINDINGS);
+        let mut review = defaults(DEFAULT_REVIEW_BINDINGS);
+
+        let mut in_review = false;
+        // Where each sequence was bound in this file, to report rebinding
+        let mut seen: HashMap<(bool, Vec<KeyEvent>), (usize, Option<Action>)> = HashMap::new();
+        for (i, line) in text.lines().enumerate() {
+            let line_no = i + 1;
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            if let Some(section) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+                in_review = match section.trim() {
+                    "normal" => false,
+                    "review" => true,
+                    section => {
+                        return Err(KeymapError::UnknownSection {
+                            line: line_no,
+                            section: section.to_string(),
+                        })
+                    }
+                };
+                continue;
+            }
+
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            let (keys, action) = match fields[..] {
+                [keys, action] => (keys, action),
+                _ => return Err(KeymapError::Malformed { line: line_no }),
+            };
+            let keys =
+                parse_keys(keys).map_err(|key| KeymapError::UnknownKey { line: line_no, key })?;
+            let action = match action {
+                "none" => None,
+                name => Some(
+                    action_by_name(name).ok_or_else(|| KeymapError::UnknownAction {
+                        line: line_no,
+                        action: name.to_string(),
+                    })?,
+                ),
+            };
+            match seen.get(&(in_review, keys.clone())) {
+                Some((previous, previous_action)) if *previous_action != action => {
+                    return Err(KeymapError::Rebound {
+                        line: line_no,
+                        keys: format_keys(&keys),
+                        action: previous_action.map_or("none", action_name).to_string(),
+                        previous: *previous,
+                    });
+                }
+                _ => {
+                    seen.insert((in_review, keys.clone()), (line_no, action));
+                }
+            }
+
+            let bindings = if in_review { &mut review } else { &mut normal };
+            match action {
+                Some(action) => bindings.insert(keys, action),
+                None => bindings.remove(&keys),
+            };
+        }
+
+        // Review bindings win over normal ones for the same keys
+        let mut merged = normal.clone();
+        merged.extend(review);
+        check_shadowing(&normal)?;
+        check_shadowing(&merged)?;
+        Ok(Keymap {
+            normal,
+            review: merged,
+        })
+    }
+
+    pub fn lookup(&self, review: bool, keys: &[KeyEvent]) -> Lookup {
+        let bindings = if review { &self.review } else { &self.normal };
+        if let Some(action) = bindings.get(keys) {
+            return Lookup::Action(*action);
+        }
+        if bindings
+            .keys()
+            .any(|bound| bound.len() > keys.len() && bound.starts_with(keys))
+        {
+            Lookup::Pending
+        } else {
+            Lookup::Unbound
+        }
+    }
+}
+
+/// Strips the shift from typed characters, since `G` already says it
+pub fn normalize(key: KeyEvent) -> KeyEvent {
+    match key.code {
+        KeyCode::Char(_) => KeyEvent::new(key.code, key.modifiers - KeyModifiers::SHIFT),
+        _ => key,
+    }
+}
+
+/// Whether a key continues a count prefix
+pub fn is_count(key: &KeyEvent) -> bool {
+    matches!(key.code, KeyCode::Char('0'..='9')) && key.modifiers.is_empty()
+}
+
+fn defaults(bindings: &[(&str, &str)]) -> HashMap<Vec<KeyEvent>, Action> {
+    bindings
+        .iter()
+        .map(|(keys, action)| {
+            (
+                parse_keys(keys).expect("default key sequence"),
+                action_by_name(action).expect("default action"),
+            )
+        })
+        .collect()
+}
+
+fn check_shadowing(bindings: &HashMap<Vec<KeyEvent>, Action>) -> Result<(), KeymapError> {
+    let mut sequences: Vec<&Vec<KeyEvent>> = bindings.keys().collect();
+    sequences.sort_by_key(|keys| format_keys(keys));
+    for keys in &sequences {
+        for prefix in &sequences {
+            if prefix.len() < keys.len() && keys.starts_with(prefix) {
+                return Err(KeymapError::Shadowed {
+                    prefix: format_keys(prefix),
+                    prefix_action: action_name(bindings[*prefix]).to_string(),
+                    keys: format_keys(keys),
+                    action: action_name(bindings[*keys]).to_string(),
+                });
+            }
+        }
+    }
+    Ok(())
+}
+
+fn action_by_name(name: &str) -> Option<Action> {
+    ACTION_NAMES
+        .iter()
+        .find(|(n, _)| *n == name)
+        .map(|(_, action)| *action)
+}
+
+fn action_name(action: Action) -> &'static str {
+    ACTION_NAMES
+        .iter()
+        .find(|(_, a)| *a == action)
+        .map_or("none", |(name, _)| name)
+}
+
+/// Parses a vim-style key sequence such as `gg`, `<C-d>` or `<PageDown>`.
+/// Returns the part that isn't a key on failure.
+fn parse_keys(text: &str) -> Result<Vec<KeyEvent>, String> {
+    let mut keys = Vec::new();
+    let mut rest = text;
+    while let Some(c) = rest.chars().next() {
+        if c == '<' {
+            let end = rest.find('>').ok_or_else(|| rest.to_string())?;
+            keys.push(parse_named_key(&rest[1..end]).ok_or_else(|| rest[..=end].to_string())?);
+            rest = &rest[end + 1..];
+        } else {
+            keys.push(KeyEvent::new(KeyCode::Char(c), KeyModifiers::NONE));
+            rest = &rest[c.len_utf8()..];
+        }
+    }
+    match keys.is_empty() {
+        true => Err(text.to_string()),
+        false => Ok(keys),
+    }
+}
+
+/// The inside of `<...>`: a key name or character, after optional `C-`,
+/// `A-` and `S-` modifiers
+fn parse_named_key(name: &str) -> Option<KeyEvent> {
+    let mut modifiers = KeyModifiers::NONE;
+    let mut name = name;
+    loop {
+        let modifier = match name.get(..2).map(str::to_ascii_uppercase).as_deref() {
+            Some("C-") => KeyModifiers::CONTROL,
+            Some("A-") | Some("M-") => KeyModifiers::ALT,
+            Some("S-") => KeyModifiers::SHIFT,
+            _ => break,
+        };
+        if name.len() == 2 {
+            break;
+        }
+        modifiers |= modifier;
+        name = &name[2..];
+    }
+
+    let lower = name.to_ascii_lowercase();
+    let code = if let Some((_, code)) = KEY_NAMES.iter().find(|(n, _)| *n == lower) {
+        *code
+    } else if let Some(n) = lower.strip_prefix('f').and_then(|n| n.parse::<u8>().ok()) {
+        KeyCode::F(n)
+    } else {
+        let mut chars = name.chars();
+        match (chars.next(), chars.next()) {
+            (Some(c), None) => KeyCode::Char(c),
+            _ => return None,
+        }
+    };
+    Some(normalize(KeyEvent::new(code, modifiers)))
+}
+
+pub fn format_keys(keys: &[KeyEvent]) -> String {
+    keys.iter().map(format_key).collect()
+}
+
+fn format_key(key: &KeyEvent) -> String {
+    let name = match key.code {
+        KeyCode::Char('<') => "lt".to_string(),
+        KeyCode::Char(' ') => "Space".to_string(),
+        KeyCode::Char(c) if key.modifiers.is_empty() => return c.to_string(),
+        KeyCode::Char(c) => c.to_string(),
+        KeyCode::F(n) => format!("F{}", n),
+        code => format!("{:?}", code),
+    };
+    let mut prefix = String::new();
+    for (modifier, text) in [
+        (KeyModifiers::CONTROL, "C-"),
+        (KeyModifiers::ALT, "A-"),
+        (KeyModifiers::SHIFT, "S-"),
+    ] {
+        if key.modifiers.contains(modifier) {
+            prefix.push_str(text);
+        }
+    }
+    format!("<{}{}>", prefix, name)
+}
@@ -0,0 +1,2362 @@
+//use fltk::{app, button::Button, frame::Frame, prelude::*, window::Window};
+extern crate vocrab;
+
+mod command;
+mod keymap;
+
+use command::{Command, Filter, SortKey, Target};
+use crossterm::{
+    event::{
+        self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode, KeyEvent, MouseButton,
+        MouseEvent, MouseEventKind,
+    },
+    execute,
+    terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
+};
+use keymap::{Action, Keymap, Lookup};
+use std::fs::File;
+use std::time::{Duration, Instant};
+use std::{error::Error, io};
+use structopt::StructOpt;
+use textwrap::core::{display_width, Fragment, Word};
+use textwrap::word_separators::{UnicodeBreakProperties, WordSeparator};
+use tui::{
+    backend::{Backend, CrosstermBackend},
+    layout::{Alignment, Constraint, Direction, Layout, Margin, Rect},
+    style::{Color, Modifier, Style},
+    symbols,
+    text::{Span, Spans},
+    widgets::{
+        Axis, BarChart, Block, BorderType, Borders, Chart, Dataset, GraphType, List, ListItem,
+        ListState, Paragraph, Sparkline, Tabs,
+    },
+    Frame, Terminal,
+};
+use vocrab::apkg::write_anki_package;
+use vocrab::compare::Comparison;
+use vocrab::coverage::{Difficulty, EASY_COVERAGE};
+use vocrab::dictionary::Dictionary;
+use vocrab::dispersion::Segments;
+use vocrab::export::{append_anki_tsv, write_anki_tsv, Card};
+use vocrab::known::KnownWords;
+use vocrab::lemmatizer::Lemmatizer;
+use vocrab::mining::{MinedLemma, MinedSentence};
+use vocrab::notes::Annotations;
+use vocrab::plan::{LearningPlan, PlanOptions, PlanStep};
+use vocrab::review::{Grade, ReviewDeck, SystemClock};
+use vocrab::subtitles::format_timestamp;
+
+/// Longest gap between two clicks that still makes a double-click
+const DOUBLE_CLICK_TIME: Duration = Duration::from_millis(400);
+
+#[derive(StructOpt)]
+struct Opt {
+    // The json file to read from
+    file: String,
+    /// File of known lemmas, one per line
+    #[structopt(long, default_value = "known.txt")]
+    known: String,
+    /// Anki TSV file that queued flashcards are appended to
+    #[structopt(long, default_value = "cards.tsv")]
+    cards: String,
+    /// Coverage the learning plan aims for, between 0 and 1
+    #[structopt(long, default_value = "0.95")]
+    target: f32,
+    /// Part of speech to leave out of the learning plan, e.g. PROPN
+    #[structopt(long)]
+    exclude_pos: Vec<String>,
+    /// File the spaced-repetition deck and review history are kept in
+    #[structopt(long, default_value = "reviews.json")]
+    reviews: String,
+    /// Dictionary to look lemmas up in: a StarDict .ifo, dictd .index or
+    /// Wiktextract .jsonl file
+    #[structopt(long)]
+    dict: Vec<String>,
+    /// File your glosses and sentence notes are kept in
+    #[structopt(long, default_value = "notes.json")]
+    notes: String,
+    /// Key bindings to use instead of the defaults, one `keys action` per line
+    #[structopt(long, default_value = "keys.conf")]
+    keymap: String,
+    /// Json files read before, that the Compare tab compares this one with
+    #[structopt(long)]
+    compare: Vec<String>,
+    /// Count sentences that repeat earlier ones, like a restarted opening,
+    /// only once
+    #[structopt(long)]
+    skip_duplicates: bool,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum AppMode {
+    /// Browse lemmas, their forms and usages
+    Browse,
+    /// Browse sentences with a single unknown lemma
+    Mining,
+    /// Read the text, colored by how much of each sentence is known
+    Reader,
+    /// Lemmas to learn, in order, to reach the target coverage
+    Plan,
+    /// Spaced-repetition review of the lemmas being learnt
+    Review,
+    /// Charts of the library's vocabulary
+    Stats,
+    /// The library's vocabulary side by side with that of texts read before
+    Compare,
+}
+
+/// Modes in the order of the tab bar
+const TABS: [(AppMode, &str); 7] = [
+    (AppMode::Browse, "Browse"),
+    (AppMode::Stats, "Stats"),
+    (AppMode::Mining, "Mining"),
+    (AppMode::Reader, "Reader"),
+    (AppMode::Plan, "Plan"),
+    (AppMode::Review, "Review"),
+    (AppMode::Compare, "Compare"),
+];
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum AppColumn {
+    Lemmas,
+    Forms,
+    Usage,
+}
+
+impl AppColumn {
+    fn next(self) -> Self {
+        match self {
+            AppColumn::Lemmas => AppColumn::Forms,
+            AppColumn::Forms => AppColumn::Usage,
+            AppColumn::Usage => AppColumn::Usage,
+        }
+    }
+
+    fn prev(self) -> Self {
+        match self {
+            AppColumn::Lemmas => AppColumn::Lemmas,
+            AppColumn::Forms => AppColumn::Lemmas,
+            AppColumn::Usage => AppColumn::Forms,
+        }
+    }
+}
+
+/// A way of moving the selection in a list
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Motion {
+    /// One item on, wrapping around at the end
+    Next,
+    Prev,
+    Down(usize),
+    Up(usize),
+    PageDown,
+    PageUp,
+    To(usize),
+    Last,
+}
+
+struct StatefulList<T> {
+    state: ListState,
+    items: Vec<T>,
+    /// Where the list was last drawn, for mouse hits
+    area: Rect,
+    /// Height of each item when last drawn
+    heights: Vec<usize>,
+    /// First visible item, mirroring the private offset of `ListState`
+    offset: usize,
+}
+
+impl<T> StatefulList<T> {
+    fn with_items(items: Vec<T>) -> StatefulList<T> {
+        StatefulList {
+            state: ListState::default(),
+            items,
+            area: Rect::default(),
+            heights: Vec::new(),
+            offset: 0,
+        }
+    }
+
+    /// Records how the list is about to be drawn. Scrolls the same way
+    /// `List` does, so `offset` matches what ends up on screen.
+    fn rendered(&mut self, area: Rect, heights: Vec<usize>) {
+        let max_height = area.height.saturating_sub(2) as usize;
+        let mut start = self.offset.min(heights.len().saturating_sub(1));
+        let mut end = start;
+        let mut height = 0;
+        for item_height in heights.iter().skip(start) {
+            if height + item_height > max_height {
+                break;
+            }
+            height += item_height;
+            end += 1;
+        }
+        if !heights.is_empty() {
+            let selected = self.state.selected().unwrap_or(0).min(heights.len() - 1);
+            while selected >= end {
+                height += heights[end];
+                end += 1;
+                while height > max_height {
+                    height -= heights[start];
+                    start += 1;
+                }
+            }
+            while selected < start {
+                start -= 1;
+                height += heights[start];
+                while height > max_height {
+                    end -= 1;
+                    height -= heights[end];
+                }
+            }
+        }
+        self.area = area;
+        self.heights = heights;
+        self.offset = start;
+    }
+
+    /// The item drawn at a screen position, if any
+    fn item_at(&self, column: u16, row: u16) -> Option<usize> {
+        let inner = self.area.inner(&Margin {
+            vertical: 1,
+            horizontal: 1,
+        });
+        if column < inner.x || column >= inner.right() || row < inner.y || row >= inner.bottom() {
+            return None;
+        }
+        let mut y = inner.y as usize;
+        for (i, height) in self.heights.iter().enumerate().skip(self.offset) {
+            y += height;
+            if (row as usize) < y {
+                return Some(i);
+            }
+        }
+        None
+    }
+
+    fn contains(&self, column: u16, row: u16) -> bool {
+        let area = self.area;
+        column >= area.x && column < area.right() && row >= area.y && row < area.bottom()
+    }
+
+    fn selected(&self) -> Option<&T> {
+        self.state.selected().and_then(|i| self.items.get(i))
+    }
+
+    /// Number of items that fit in the list from the first one shown
+    fn page_size(&self) -> usize {
+        let max_height = self.area.height.saturating_sub(2) as usize;
+        let mut height = 0;
+        let count = self
+            .heights
+            .iter()
+            .skip(self.offset)
+            .take_while(|item_height| {
+                height += *item_height;
+                height <= max_height
+            })
+            .count();
+        count.max(1)
+    }
+
+    fn apply(&mut self, motion: Motion) {
+        if self.items.is_empty() {
+            return;
+        }
+        let last = self.items.len() - 1;
+        let i = self.state.selected().unwrap_or(0);
+        let i = match motion {
+            Motion::Next => return self.next(),
+            Motion::Prev => return self.prev(),
+            Motion::Down(n) => i.saturating_add(n),
+            Motion::Up(n) => i.saturating_sub(n),
+            Motion::PageDown => i + self.page_size(),
+            Motion::PageUp => i.saturating_sub(self.page_size()),
+            Motion::To(i) => i,
+            Motion::Last => last,
+        };
+        self.state.select(Some(i.min(last)));
+    }
+
+    fn next(&mut self) {
+        if self.items.is_empty() {
+            return;
+        }
+        let i = match self.state.selected() {
+            Some(i) => {
+                if i >= self.items.len() - 1 {
+                    0
+                } else {
+                    i + 1
+                }
+            }
+            None => 0,
+        };
+        self.state.select(Some(i));
+    }
+
+    fn prev(&mut self) {
+        if self.items.is_empty() {
+            return;
+        }
+        let i = match self.state.selected() {
+            Some(i) => {
+                if i == 0 {
+                    self.items.len() - 1
+                } else {
+                    i - 1
+                }
+            }
+            None => 0,
+        };
+        self.state.select(Some(i));
+    }
+}
+
+// Application state
+struct App {
+    lemmatizer: Lemmatizer,
+    lemma_vec: StatefulList<String>,
+    curr_lemma: Option<String>,
+    form_vec: Option<StatefulList<String>>,
+    curr_form: Option<String>,
+    usage_vec: Option<StatefulList<UsageItem>>,
+    column: AppColumn,
+    mode: AppMode,
+    known: KnownWords,
+    mined_vec: StatefulList<MinedLemma>,
+    mined_sentences: Option<StatefulList<MinedSentence>>,
+    card_queue: Vec<Card>,
+    reader_vec: StatefulList<usize>,
+    plan_options: PlanOptions,
+    plan: Option<LearningPlan>,
+    plan_vec: StatefulList<PlanStep>,
+    deck: ReviewDeck,
+    review: Option<ReviewState>,
+    dictionary: Dictionary,
+    annotations: Annotations,
+    input: Option<TextInput>,
+    /// Time, column and item of the last click, to spot double-clicks
+    last_click: Option<(Instant, AppColumn, usize)>,
+    keymap: Keymap,
+    /// Keys typed so far of a binding longer than one key
+    pending_keys: Vec<KeyEvent>,
+    /// Count typed before a motion, as in `5j`
+    count: Option<usize>,
+    lemma_sort: SortKey,
+    lemma_filters: Vec<Filter>,
+    /// Shown in place of the status bar until the next key
+    message: Option<StatusMessage>,
+    /// Texts read before, that the library is compared with
+    compared: Lemmatizer,
+    comparison: Option<Comparison>,
+    /// Columns of the Compare tab: lemmas new in the library, shared ones
+    /// and ones only the compared texts use
+    compare_new: StatefulList<(String, usize)>,
+    compare_shared: StatefulList<(String, usize, usize)>,
+    compare_old: StatefulList<(String, usize)>,
+}
+
+/// Feedback from a command, or an error
+struct StatusMessage {
+    text: String,
+    is_error: bool,
+}
+
+impl StatusMessage {
+    fn info(text: String) -> StatusMessage {
+        StatusMessage {
+            text,
+            is_error: false,
+        }
+    }
+
+    fn error(text: String) -> StatusMessage {
+        StatusMessage {
+            text,
+            is_error: true,
+        }
+    }
+}
+
+/// A usage sentence and the sentence index it was taken from
+struct UsageItem {
+    sentence_i: usize,
+    usage: (String, String, String),
+}
+
+/// What the text being typed in is for
+enum InputTarget {
+    Gloss(String),
+    Note(usize),
+    Command,
+}
+
+/// A one-line text field shown in place of the status bar
+struct TextInput {
+    prompt: String,
+    text: String,
+    /// Cursor position, in chars
+    cursor: usize,
+    target: InputTarget,
+}
+
+impl TextInput {
+    fn new(prompt: String, text: &str, target: InputTarget) -> TextInput {
+        TextInput {
+            prompt,
+            text: text.to_string(),
+            cursor: text.chars().count(),
+            target,
+        }
+    }
+
+    fn byte_index(&self) -> usize {
+        self.text
+            .char_indices()
+            .nth(self.cursor)
+            .map_or(self.text.len(), |(i, _)| i)
+    }
+
+    /// Edits the text; returns false for keys the field doesn't use
+    fn handle_key(&mut self, code: KeyCode) -> bool {
+        match code {
+            KeyCode::Char(c) => {
+                let i = self.byte_index();
+                self.text.insert(i, c);
+                self.cursor += 1;
+            }
+            KeyCode::Backspace if self.cursor > 0 => {
+                self.cursor -= 1;
+                let i = self.byte_index();
+                self.text.remove(i);
+            }
+            KeyCode::Delete if self.cursor < self.text.chars().count() => {
+                let i = self.byte_index();
+                self.text.remove(i);
+            }
+            KeyCode::Left => self.cursor = self.cursor.saturating_sub(1),
+            KeyCode::Right => self.cursor = (self.cursor + 1).min(self.text.chars().count()),
+            KeyCode::Home => self.cursor = 0,
+            KeyCode::End => self.cursor = self.text.chars().count(),
+            _ => return false,
+        }
+        true
+    }
+}
+
+/// The card being reviewed
+struct ReviewState {
+    lemma: String,
+    usage: Option<(String, String, String)>,
+    revealed: bool,
+}
+
+fn main() -> Result<(), Box<dyn Error>> {
+    // Load lemmatization
+    let opt = Opt::from_args();
+    // Bad bindings are reported before anything else is loaded
+    let keymap = match Keymap::load_file(&opt.keymap) {
+        Ok(keymap) => keymap,
+        Err(e) => {
+            eprintln!("{}: {}", opt.keymap, e);
+            std::process::exit(2);
+        }
+    };
+    let filepath = opt.file; //= "data/emos-vs-punks.json";
+
+    let mut lemmatizer = Lemmatizer::new();
+    lemmatizer.skip_duplicates(opt.skip_duplicates);
+    lemmatizer.load_file(&filepath).unwrap();
+    let lemma_vec = lemmatizer.get_lemmas();
+    let known = KnownWords::load_file(&opt.known)?;
+    let deck = ReviewDeck::load_file(&opt.reviews)?;
+    let annotations = Annotations::load_file(&opt.notes)?;
+    let mut dictionary = Dictionary::new();
+    for path in &opt.dict {
+        dictionary.load_file(path)?;
+    }
+    let mut compared = Lemmatizer::new();
+    compared.skip_duplicates(opt.skip_duplicates);
+    for path in &opt.compare {
+        compared.load_file(path)?;
+    }
+
+    /*
+    let token_array = tokens_from_file(filepath).unwrap();
+    let lemma_map = map_from_array(&token_array);
+    let mut lemma_vec: LemmaVec = lemma_map.iter().collect();
+    lemma_vec.sort_by(|a, b| b.1.word_count().cmp(&a.1.word_count()));
+    */
+
+    // setup terminal
+    enable_raw_mode()?;
+    let mut stdout = io::stdout();
+    execute!(stdout, EnterAlternateScreen, EnableMouseCapture)?;
+    let backend = CrosstermBackend::new(stdout);
+    let mut terminal = Terminal::new(backend)?;
+
+    // create app and run it
+    let mut app = App {
+        lemmatizer,
+        lemma_vec: StatefulList::with_items(lemma_vec),
+        curr_lemma: None,
+        form_vec: None,
+        curr_form: None,
+        usage_vec: None,
+        column: AppColumn::Lemmas,
+        mode: AppMode::Browse,
+        known,
+        mined_vec: StatefulList::with_items(Vec::new()),
+        mined_sentences: None,
+        card_queue: Vec::new(),
+        reader_vec: StatefulList::with_items(Vec::new()),
+        plan_options: PlanOptions {
+            target: opt.target,
+            exclude_pos: opt.exclude_pos.clone(),
+            ..PlanOptions::default()
+        },
+        plan: None,
+        plan_vec: StatefulList::with_items(Vec::new()),
+        deck,
+        review: None,
+        dictionary,
+        annotations,
+        input: None,
+        last_click: None,
+        keymap,
+        pending_keys: Vec::new(),
+        count: None,
+        lemma_sort: SortKey::Frequency,
+        lemma_filters: Vec::new(),
+        message: None,
+        compared,
+        comparison: None,
+        compare_new: StatefulList::with_items(Vec::new()),
+        compare_shared: StatefulList::with_items(Vec::new()),
+        compare_old: StatefulList::with_items(Vec::new()),
+    };
+
+    let res = run_app(&mut terminal, &mut app);
+
+    // restore terminal
+    disable_raw_mode()?;
+    execute!(
+        terminal.backend_mut(),
+        LeaveAlternateScreen,
+        DisableMouseCapture,
+    )?;
+    terminal.show_cursor()?;
+
+    if let Err(err) = res {
+        println!("{:?}", err)
+    }
+
+    app.known.save_file(&opt.known)?;
+    app.deck.save_file(&opt.reviews)?;
+    app.annotations.save_file(&opt.notes)?;
+    if !app.card_queue.is_empty() {
+        append_anki_tsv(&app.card_queue, &opt.cards)?;
+        println!("{} cards written to {}", app.card_queue.len(), opt.cards);
+    }
+
+    Ok(())
+}
+
+fn update_form(app: &mut App) {
+    if app.lemma_vec.items.is_empty() {
+        // Nothing passes the filters
+        app.curr_lemma = None;
+        app.form_vec = None;
+        app.curr_form = None;
+        app.usage_vec = None;
+        return;
+    }
+    let lemma = match app.lemma_vec.state.selected() {
+        Some(i) => &app.lemma_vec.items[i],
+        None => {
+            app.lemma_vec.state.select(Some(0));
+            &app.lemma_vec.items[0]
+        }
+    };
+
+    match &app.curr_lemma {
+        Some(curr_lemma) if lemma.eq(curr_lemma) => {}
+        _ => {
+            let form_vec = app.lemmatizer.get_forms(lemma);
+            app.form_vec = Some(StatefulList::with_items(form_vec));
+            app.curr_lemma = Some(lemma.to_string());
+            if let Some(form_vec) = &mut app.form_vec {
+                form_vec.state.select(Some(0));
+            }
+        }
+    };
+
+    update_usage(app);
+}
+
+fn update_usage(app: &mut App) {
+    let form = match &mut app.form_vec {
+        Some(form_vec) if !form_vec.items.is_empty() => match form_vec.state.selected() {
+            Some(i) => &form_vec.items[i],
+            None => {
+                form_vec.state.select(Some(0));
+                &form_vec.items[0]
+            }
+        },
+        _ => {
+            app.curr_form = None;
+            app.usage_vec = None;
+            if app.curr_lemma.is_some() {
+                app.message = Some(StatusMessage::error(
+                    "Unable to find forms of the selected lemma".to_string(),
+                ));
+            }
+            return;
+        }
+    };
+
+    match &app.curr_form {
+        Some(curr_form) if form.eq(curr_form) => {}
+        _ => {
+            if let Some(lemma) = &app.curr_lemma {
+                let lemmatizer = &app.lemmatizer;
+                let usage_vec = lemmatizer
+                    .get_ranked_usages(lemma, Some(form), &app.known)
+                    .into_iter()
+                    .map(|(sentence_i, token_i)| UsageItem {
+                        sentence_i,
+                        usage: lemmatizer.get_usage(sentence_i, token_i),
+                    })
+                    .collect();
+                app.usage_vec = Some(StatefulList::with_items(usage_vec));
+                app.curr_form = Some(form.to_string());
+                if let Some(usage_vec) = &mut app.usage_vec {
+                    usage_vec.state.select(Some(0));
+                }
+            }
+        }
+    };
+}
+
+fn update_mined(app: &mut App) {
+    let selected = app.mined_vec.selected().map(|mined| mined.lemma.clone());
+    app.mined_vec = StatefulList::with_items(app.lemmatizer.get_mined_sentences(&app.known));
+    // Stay near the previous selection after marking a lemma known
+    let i = match selected {
+        Some(lemma) => app
+            .mined_vec
+            .items
+            .iter()
+            .position(|mined| mined.lemma >= lemma)
+            .unwrap_or(0),
+        None => 0,
+    };
+    app.mined_vec.state.select(Some(i));
+    update_mined_sentences(app);
+}
+
+fn update_mined_sentences(app: &mut App) {
+    app.mined_sentences = app.mined_vec.selected().map(|mined| {
+        let mut sentences = StatefulList::with_items(mined.sentences.clone());
+        sentences.state.select(Some(0));
+        sentences
+    });
+}
+
+fn update_plan(app: &mut App) {
+    let selected = app.plan_vec.state.selected().unwrap_or(0);
+    let plan = app
+        .lemmatizer
+        .get_learning_plan(&app.known, &app.plan_options);
+    let steps = plan
+        .as_ref()
+        .map_or_else(Vec::new, |plan| plan.steps.clone());
+    let last = steps.len().saturating_sub(1);
+    app.plan_vec = StatefulList::with_items(steps);
+    app.plan_vec.state.select(Some(selected.min(last)));
+    app.plan = plan;
+}
+
+/// A list of `items`, with the selection kept at `selected` or the last item
+fn reselected<T>(items: Vec<T>, selected: Option<usize>) -> StatefulList<T> {
+    let mut list = StatefulList::with_items(items);
+    if !list.items.is_empty() {
+        let last = list.items.len() - 1;
+        list.state.select(Some(selected.unwrap_or(0).min(last)));
+    }
+    list
+}
+
+fn update_comparison(app: &mut App) {
+    let comparison = app.compared.compare(&app.lemmatizer);
+    app.compare_new = reselected(comparison.only_b.clone(), app.compare_new.state.selected());
+    app.compare_shared = reselected(
+        comparison.shared.clone(),
+        app.compare_shared.state.selected(),
+    );
+    app.compare_old = reselected(comparison.only_a.clone(), app.compare_old.state.selected());
+    app.comparison = Some(comparison);
+}
+
+fn enter_behavior(app: &mut App) {
+    match app.column {
+        _ if app.mode == AppMode::Compare => {}
+        AppColumn::Lemmas if app.mode == AppMode::Mining => {
+            app.column = AppColumn::Usage;
+            return;
+        }
+        AppColumn::Lemmas => update_form(app),
+        AppColumn::Forms => update_usage(app),
+        AppColumn::Usage => {}
+    }
+    app.column = app.column.next();
+}
+
+fn back_behavior(app: &mut App) {
+    app.column = match app.mode {
+        AppMode::Browse | AppMode::Compare => app.column.prev(),
+        _ => AppColumn::Lemmas,
+    };
+}
+
+/// Switches to `mode`, or back to browsing if already there
+fn toggle_mode(app: &mut App, mode: AppMode) {
+    match app.mode == mode {
+        true => enter_mode(app, AppMode::Browse),
+        false => enter_mode(app, mode),
+    }
+}
+
+fn enter_mode(app: &mut App, mode: AppMode) {
+    if app.mode != mode {
+        match mode {
+            AppMode::Browse | AppMode::Stats => {}
+            AppMode::Mining => update_mined(app),
+            AppMode::Reader if app.reader_vec.items.is_empty() => {
+                app.reader_vec =
+                    StatefulList::with_items((0..app.lemmatizer.sentence_count()).collect());
+                app.reader_vec.state.select(Some(0));
+            }
+            AppMode::Reader => {}
+            AppMode::Plan => update_plan(app),
+            AppMode::Review => next_review(app),
+            AppMode::Compare => update_comparison(app),
+        }
+    }
+    app.mode = mode;
+    app.column = AppColumn::Lemmas;
+}
+
+/// The lemma under the cursor in the current mode
+fn selected_lemma(app: &App) -> Option<String> {
+    match app.mode {
+        AppMode::Browse | AppMode::Stats => app.curr_lemma.clone(),
+        AppMode::Mining => app.mined_vec.selected().map(|m| m.lemma.clone()),
+        AppMode::Reader => None,
+        AppMode::Plan => app.plan_vec.selected().map(|step| step.lemma.clone()),
+        AppMode::Review => app.review.as_ref().map(|review| review.lemma.clone()),
+        AppMode::Compare => match app.column {
+            AppColumn::Lemmas => app.compare_new.selected().map(|(lemma, _)| lemma.clone()),
+            AppColumn::Forms => app
+                .compare_shared
+                .selected()
+                .map(|(lemma, ..)| lemma.clone()),
+            AppColumn::Usage => app.compare_old.selected().map(|(lemma, _)| lemma.clone()),
+        },
+    }
+}
+
+/// Marks the selected lemma known, or unknown again if it already was
+fn toggle_known(app: &mut App) {
+    if let Some(lemma) = selected_lemma(app) {
+        app.known.toggle(&lemma);
+        match app.mode {
+            AppMode::Mining => update_mined(app),
+            AppMode::Plan => update_plan(app),
+            _ => {}
+        }
+    }
+}
+
+/// Starts learning the selected lemma in the review deck, or stops if it
+/// already was
+fn toggle_learning(app: &mut App) {
+    if let Some(lemma) = selected_lemma(app) {
+        if !app.deck.remove(&lemma) {
+            app.deck.add(&lemma, &SystemClock);
+        }
+    }
+}
+
+/// Shows the most overdue card, in one of the sentences the lemma is used in
+fn next_review(app: &mut App) {
+    let card = app
+        .deck
+        .due(&SystemClock)
+        .first()
+        .map(|card| (*card).clone());
+    app.review = card.map(|card| {
+        let usages = app
+            .lemmatizer
+            .get_ranked_usages(&card.lemma, None, &app.known);
+        // Start from the best example and move to the next each time the
+        // card is passed
+        let usage = match usages.len() {
+            0 => None,
+            n => {
+                let (sentence_i, token_i) = usages[card.repetitions as usize % n];
+                Some(app.lemmatizer.get_usage(sentence_i, token_i))
+            }
+        };
+        ReviewState {
+            lemma: card.lemma,
+            usage,
+            revealed: false,
+        }
+    });
+}
+
+fn grade_review(app: &mut App, grade: Grade) {
+    if let Some(review) = &app.review {
+        if review.revealed {
+            app.deck.grade(&review.lemma, grade, &SystemClock);
+            next_review(app);
+        }
+    }
+}
+
+/// Queues the selected usage sentence and its word for flashcard export. In
+/// the plan, queues a card for every planned lemma.
+fn queue_card(app: &mut App) {
+    // Each card with the sentence it was taken from
+    let cards: Vec<(Card, usize)> = match app.mode {
+        AppMode::Browse => match (&app.curr_lemma, &app.curr_form, &app.usage_vec) {
+            (Some(lemma), Some(form), Some(usage_vec)) => usage_vec
+                .selected()
+                .map(|item| (Card::new(lemma, form, &item.usage), item.sentence_i))
+                .into_iter()
+                .collect(),
+            _ => Vec::new(),
+        },
+        AppMode::Mining => match (app.mined_vec.selected(), &app.mined_sentences) {
+            (Some(mined), Some(sentences)) => sentences
+                .selected()
+                .map(|sentence| {
+                    let card = Card::new(&mined.lemma, &sentence.form, &sentence.usage);
+                    (card, sentence.sentence_i)
+                })
+                .into_iter()
+                .collect(),
+            _ => Vec::new(),
+        },
+        AppMode::Reader | AppMode::Review | AppMode::Stats | AppMode::Compare => Vec::new(),
+        AppMode::Plan => match &app.plan {
+            Some(plan) => app
+                .lemmatizer
+                .get_plan_cards(plan)
+                .into_iter()
+                .zip(plan.steps.iter().map(|step| step.example.0))
+                .collect(),
+            None => Vec::new(),
+        },
+    };
+    for (card, sentence_i) in cards {
+        let definition = app.dictionary.gloss(&card.lemma, &card.form);
+        let card = app.annotations.annotate(
+            card.with_definition(definition)
+                .with_timing(app.lemmatizer.timing_of(sentence_i)),
+            &app.lemmatizer,
+            sentence_i,
+        );
+        if !app.card_queue.contains(&card) {
+            app.card_queue.push(card);
+        }
+    }
+}
+
+/// Opens the text field on the selected lemma's gloss
+fn edit_gloss(app: &mut App) {
+    if let Some(lemma) = selected_lemma(app) {
+        let gloss = app.annotations.gloss(&lemma).unwrap_or("");
+        let prompt = format!("Gloss for {}: ", lemma);
+        app.input = Some(TextInput::new(prompt, gloss, InputTarget::Gloss(lemma)));
+    }
+}
+
+/// Opens the text field on the note of the selected usage sentence
+fn edit_note(app: &mut App) {
+    let sentence_i = match app.mode {
+        AppMode::Browse => app
+            .usage_vec
+            .as_ref()
+            .and_then(|usage_vec| usage_vec.selected())
+            .map(|item| item.sentence_i),
+        AppMode::Mining => app
+            .mined_sentences
+            .as_ref()
+            .and_then(|sentences| sentences.selected())
+            .map(|sentence| sentence.sentence_i),
+        AppMode::Reader => app.reader_vec.selected().copied(),
+        AppMode::Plan | AppMode::Review | AppMode::Stats | AppMode::Compare => None,
+    };
+    if let Some(sentence_i) = sentence_i {
+        let note = app
+            .annotations
+            .sentence_note(&app.lemmatizer, sentence_i)
+            .unwrap_or("");
+        let prompt = "Note: ".to_string();
+        app.input = Some(TextInput::new(prompt, note, InputTarget::Note(sentence_i)));
+    }
+}
+
+/// Rebuilds the lemma list with the current sort order and filters,
+/// keeping the selected lemma if it's still there
+fn update_lemmas(app: &mut App) {
+    let lemmatizer = &app.lemmatizer;
+    let known = &app.known;
+    let filters = &app.lemma_filters;
+    let lemmas = match app.lemma_sort {
+        SortKey::Adjusted => lemmatizer.get_adjusted_lemmas(Segments::Paragraphs),
+        _ => lemmatizer.get_lemmas(),
+    };
+    let mut lemmas: Vec<String> = lemmas
+        .into_iter()
+        .filter(|lemma| {
+            filters.iter().all(|filter| match filter {
+                Filter::Pos(tags) => lemmatizer
+                    .get_lemma_pos(lemma)
+                    .first()
+                    .is_some_and(|(pos, _)| tags.contains(pos)),
+                Filter::Known(is_known) => known.contains(lemma) == *is_known,
+            })
+        })
+        .collect();
+    match app.lemma_sort {
+        SortKey::Frequency | SortKey::Adjusted => {}
+        SortKey::Alphabetical => lemmas.sort(),
+        SortKey::Forms => {
+            lemmas.sort_by_key(|lemma| std::cmp::Reverse(lemmatizer.get_forms(lemma).len()))
+        }
+    }
+
+    let selected = app.curr_lemma.take();
+    let i = selected
+        .and_then(|lemma| lemmas.iter().position(|l| *l == lemma))
+        .unwrap_or(0);
+    app.lemma_vec = StatefulList::with_items(lemmas);
+    if !app.lemma_vec.items.is_empty() {
+        app.lemma_vec.state.select(Some(i));
+    }
+    app.curr_form = None;
+    update_form(app);
+}
+
+fn open_command(app: &mut App) {
+    app.input = Some(TextInput::new(":".to_string(), "", InputTarget::Command));
+}
+
+/// Runs a `:` command line. Returns what to tell the user on success.
+fn run_command(app: &mut App, line: &str) -> Result<Option<String>, String> {
+    let command = command::parse(line).map_err(|e| e.to_string())?;
+    match command {
+        Command::Load(path) => {
+            app.lemmatizer
+                .load_file(&path)
+                .map_err(|e| format!("{}: {}", path, e))?;
+            if !app.reader_vec.items.is_empty() {
+                app.reader_vec.items = (0..app.lemmatizer.sentence_count()).collect();
+            }
+            match app.mode {
+                AppMode::Mining => update_mined(app),
+                AppMode::Plan => update_plan(app),
+                AppMode::Compare => update_comparison(app),
+                _ => {}
+            }
+            update_lemmas(app);
+            Ok(Some(format!(
+                "Loaded {}, {} lemmas in the library",
+                path,
+                app.lemmatizer.get_lemmas().len()
+            )))
+        }
+        Command::Compare(path) => {
+            app.compared
+                .load_file(&path)
+                .map_err(|e| format!("{}: {}", path, e))?;
+            match app.mode {
+                AppMode::Compare => update_comparison(app),
+                _ => enter_mode(app, AppMode::Compare),
+            }
+            Ok(Some(format!(
+                "Comparing with {}, {} lemmas in the compared texts",
+                path,
+                app.compared.get_lemmas().len()
+            )))
+        }
+        Command::Sort(key) => {
+            app.lemma_sort = key;
+            update_lemmas(app);
+            Ok(None)
+        }
+        Command::Filter(filters) => {
+            app.lemma_filters = filters;
+            update_lemmas(app);
+            Ok(Some(format!("{} lemmas", app.lemma_vec.items.len())))
+        }
+        Command::Export(path) => {
+            if app.card_queue.is_empty() {
+                return Err("No cards queued".to_string());
+            }
+            if path.ends_with(".apkg") {
+                write_anki_package(&app.card_queue, &path)
+            } else {
+                File::create(&path).and_then(|mut file| write_anki_tsv(&app.card_queue, &mut file))
+            }
+            .map_err(|e| format!("{}: {}", path, e))?;
+            let count = app.card_queue.len();
+            app.card_queue.clear();
+            Ok(Some(format!("{} cards written to {}", count, path)))
+        }
+        Command::Goto(Target::Rank(0)) => Err("Ranks start at 1".to_string()),
+        Command::Goto(Target::Rank(rank)) => {
+            move_selection(app, Motion::To(rank - 1));
+            Ok(None)
+        }
+        Command::Goto(Target::Lemma(lemma)) => {
+            let i = app
+                .lemma_vec
+                .items
+                .iter()
+                .position(|l| *l == lemma)
+                .ok_or_else(|| format!("{} isn't in the lemma list", lemma))?;
+            app.mode = AppMode::Browse;
+            app.column = AppColumn::Lemmas;
+            app.lemma_vec.state.select(Some(i));
+            update_form(app);
+            Ok(None)
+        }
+        Command::Known(ranges) => {
+            let mut count = 0;
+            let len = app.lemma_vec.items.len();
+            for range in ranges {
+                // Ranks past the end of the list would only be skipped, and
+                // there may be billions of them
+                let (start, end) = ((*range.start()).max(1), (*range.end()).min(len));
+                for lemma in app.lemma_vec.items.get(start - 1..end).unwrap_or(&[]) {
+                    if app.known.insert(lemma) {
+                        count += 1;
+                    }
+                }
+            }
+            match app.mode {
+                AppMode::Mining => update_mined(app),
+                AppMode::Plan => update_plan(app),
+                _ => {}
+            }
+            if !app.lemma_filters.is_empty() {
+                update_lemmas(app);
+            }
+            Ok(Some(format!("{} lemmas marked known", count)))
+        }
+    }
+}
+
+fn input_behavior(app: &mut App, code: KeyCode) {
+    let input = match &mut app.input {
+        Some(input) => input,
+        None => return,
+    };
+    match code {
+        KeyCode::Esc => app.input = None,
+        KeyCode::Enter => {
+            if let Some(input) = app.input.take() {
+                match input.target {
+                    InputTarget::Gloss(lemma) => app.annotations.set_gloss(&lemma, &input.text),
+                    InputTarget::Note(sentence_i) => {
+                        app.annotations
+                            .set_sentence_note(&app.lemmatizer, sentence_i, &input.text)
+                    }
+                    InputTarget::Command => {
+                        app.message = match run_command(app, &input.text) {
+                            Ok(message) => message.map(StatusMessage::info),
+                            Err(error) => Some(StatusMessage::error(error)),
+                        }
+                    }
+                }
+            }
+        }
+        KeyCode::Tab if matches!(input.target, InputTarget::Command) => {
+            let completion = command::complete(&input.text);
+            input.text = completion.text;
+            input.cursor = input.text.chars().count();
+            app.message = match completion.candidates.len() {
+                0 | 1 => None,
+                _ => Some(StatusMessage::info(completion.candidates.join("  "))),
+            };
+        }
+        code => {
+            input.handle_key(code);
+        }
+    }
+}
+
+/// The browse column drawn at a screen position
+fn column_at(app: &App, column: u16, row: u16) -> Option<AppColumn> {
+    if app.lemma_vec.contains(column, row) {
+        Some(AppColumn::Lemmas)
+    } else if app
+        .form_vec
+        .as_ref()
+        .is_some_and(|l| l.contains(column, row))
+    {
+        Some(AppColumn::Forms)
+    } else if app
+        .usage_vec
+        .as_ref()
+        .is_some_and(|l| l.contains(column, row))
+    {
+        Some(AppColumn::Usage)
+    } else {
+        None
+    }
+}
+
+/// Clicks select and focus, double-clicks act like Enter and the wheel
+/// scrolls the column under the pointer
+fn mouse_behavior(app: &mut App, mouse: MouseEvent) {
+    if app.mode != AppMode::Browse || app.input.is_some() {
+        return;
+    }
+    let column = match column_at(app, mouse.column, mouse.row) {
+        Some(column) => column,
+        None => return,
+    };
+    match mouse.kind {
+        MouseEventKind::Down(MouseButton::Left) => {
+            let item = match column {
+                AppColumn::Lemmas => app.lemma_vec.item_at(mouse.column, mouse.row),
+                AppColumn::Forms => app
+                    .form_vec
+                    .as_ref()
+                    .and_then(|l| l.item_at(mouse.column, mouse.row)),
+                AppColumn::Usage => app
+                    .usage_vec
+                    .as_ref()
+                    .and_then(|l| l.item_at(mouse.column, mouse.row)),
+            };
+            let i = match item {
+                Some(i) => i,
+                None => return,
+            };
+            app.column = column;
+            match column {
+                AppColumn::Lemmas => {
+                    app.lemma_vec.state.select(Some(i));
+                    update_form(app);
+                }
+                AppColumn::Forms => {
+                    if let Some(form_vec) = &mut app.form_vec {
+                        form_vec.state.select(Some(i));
+                        update_usage(app);
+                    }
+                }
+                AppColumn::Usage => {
+                    if let Some(usage_vec) = &mut app.usage_vec {
+                        usage_vec.state.select(Some(i));
+                    }
+                }
+            }
+            let now = Instant::now();
+            match app.last_click {
+                Some((time, c, item))
+                    if c == column && item == i && now - time < DOUBLE_CLICK_TIME =>
+                {
+                    app.last_click = None;
+                    enter_behavior(app);
+                }
+                _ => app.last_click = Some((now, column, i)),
+            }
+        }
+        MouseEventKind::ScrollDown | MouseEventKind::ScrollUp => {
+            let motion = match mouse.kind {
+                MouseEventKind::ScrollDown => Motion::Down(1),
+                _ => Motion::Up(1),
+            };
+            let focus = app.column;
+            app.column = column;
+            move_selection(app, motion);
+            app.column = focus;
+        }
+        _ => {}
+    }
+}
+
+fn move_selection(app: &mut App, motion: Motion) {
+    match (app.mode, app.column) {
+        (AppMode::Browse, AppColumn::Lemmas) => {
+            app.lemma_vec.apply(motion);
+            update_form(app);
+        }
+        (AppMode::Browse, AppColumn::Forms) => {
+            if let Some(vec) = &mut app.form_vec {
+                vec.apply(motion);
+                update_usage(app);
+            }
+        }
+        (AppMode::Browse, AppColumn::Usage) => {
+            if let Some(usage) = &mut app.usage_vec {
+                usage.apply(motion);
+            }
+        }
+        (AppMode::Mining, AppColumn::Usage) => {
+            if let Some(sentences) = &mut app.mined_sentences {
+                sentences.apply(motion);
+            }
+        }
+        (AppMode::Mining, _) => {
+            app.mined_vec.apply(motion);
+            update_mined_sentences(app);
+        }
+        (AppMode::Reader, _) => app.reader_vec.apply(motion),
+        (AppMode::Plan, _) => app.plan_vec.apply(motion),
+        (AppMode::Stats, _) => {
+            app.lemma_vec.apply(motion);
+            update_form(app);
+        }
+        (AppMode::Review, _) => {}
+        (AppMode::Compare, AppColumn::Lemmas) => app.compare_new.apply(motion),
+        (AppMode::Compare, AppColumn::Forms) => app.compare_shared.apply(motion),
+        (AppMode::Compare, AppColumn::Usage) => app.compare_old.apply(motion),
+    }
+}
+
+fn reveal_review(app: &mut App) {
+    if let Some(review) = &mut app.review {
+        review.revealed = true;
+    }
+}
+
+/// Runs a bound action, `count` times where that makes sense. Returns false
+/// to quit.
+fn action_behavior(app: &mut App, action: Action, count: Option<usize>) -> bool {
+    match action {
+        Action::Quit => return false,
+        Action::Back => back_behavior(app),
+        Action::Enter => enter_behavior(app),
+        Action::MoveDown => move_selection(app, count.map_or(Motion::Next, Motion::Down)),
+        Action::MoveUp => move_selection(app, count.map_or(Motion::Prev, Motion::Up)),
+        Action::PageDown | Action::PageUp => {
+            let motion = match action {
+                Action::PageDown => Motion::PageDown,
+                _ => Motion::PageUp,
+            };
+            for _ in 0..count.unwrap_or(1) {
+                move_selection(app, motion);
+            }
+        }
+        // Counts are numbered from 1, like lines in vim
+        Action::First => move_selection(app, Motion::To(count.unwrap_or(1).saturating_sub(1))),
+        Action::Last => move_selection(app, count.map_or(Motion::Last, |n| Motion::To(n - 1))),
+        Action::Mining => toggle_mode(app, AppMode::Mining),
+        Action::Reader => toggle_mode(app, AppMode::Reader),
+        Action::Plan => toggle_mode(app, AppMode::Plan),
+        Action::Review => toggle_mode(app, AppMode::Review),
+        Action::ToggleKnown => toggle_known(app),
+        Action::QueueCard => queue_card(app),
+        Action::ToggleLearning => toggle_learning(app),
+        Action::EditGloss => edit_gloss(app),
+        Action::EditNote => edit_note(app),
+        Action::Command => open_command(app),
+        Action::Tab(i) => {
+            if let Some((mode, _)) = TABS.get(i) {
+                enter_mode(app, *mode);
+            }
+        }
+        Action::Reveal => reveal_review(app),
+        Action::GradeAgain => grade_review(app, Grade::Again),
+        Action::GradeHard => grade_review(app, Grade::Hard),
+        Action::GradeGood => grade_review(app, Grade::Good),
+        Action::GradeEasy => grade_review(app, Grade::Easy),
+    }
+    true
+}
+
+/// Feeds a key to the keymap, collecting counts and multi-key bindings.
+/// Returns false to quit.
+fn key_behavior(app: &mut App, key: KeyEvent) -> bool {
+    let key = keymap::normalize(key);
+    let review = app.mode == AppMode::Review;
+    if !review && app.pending_keys.is_empty() && keymap::is_count(&key) {
+        if let KeyCode::Char(c) = key.code {
+            let digit = c.to_digit(10).unwrap_or(0) as usize;
+            // Bound digits, like the tab keys, run their action unless a
+            // count is already pending, and a leading zero is never a count
+            let starts_count = digit > 0 && app.keymap.lookup(review, &[key]) == Lookup::Unbound;
+            if starts_count || app.count.is_some() {
+                let count = app.count.unwrap_or(0);
+                app.count = Some(count.saturating_mul(10).saturating_add(digit));
+                return true;
+            }
+        }
+    }
+    app.pending_keys.push(key);
+    match app.keymap.lookup(review, &app.pending_keys) {
+        Lookup::Pending => true,
+        Lookup::Action(action) => {
+            app.pending_keys.clear();
+            let count = app.count.take();
+            action_behavior(app, action, count)
+        }
+        Lookup::Unbound => {
+            app.pending_keys.clear();
+            app.count = None;
+            true
+        }
+    }
+}
+
+fn run_app<B: Backend>(terminal: &mut Terminal<B>, app: &mut App) -> io::Result<()> {
+    update_form(app);
+    loop {
+        terminal.draw(|f| ui(f, app))?;
+
+        match event::read()? {
+            Event::Key(key) if app.input.is_some() => {
+                app.message = None;
+                input_behavior(app, key.code);
+            }
+            Event::Key(key) => {
+                app.message = None;
+                if !key_behavior(app, key) {
+                    return Ok(());
+                }
+            }
+            Event::Mouse(mouse) => mouse_behavior(app, mouse),
+            Event::Resize(_, _) => {}
+        }
+    }
+}
+
+fn ui<B: Backend>(f: &mut Frame<B>, app: &mut App) {
+    let size = f.size();
+
+    let selected_style = Style::default()
+        .fg(Color::Yellow)
+        .add_modifier(Modifier::BOLD);
+
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .title("📚🦀 Vocrab 🦀📚")
+        .title_alignment(Alignment::Center)
+        .border_type(BorderType::Rounded);
+    f.render_widget(block, size);
+
+    let rows = Layout::default()
+        .direction(Direction::Vertical)
+        .margin(1)
+        .constraints(
+            [
+                Constraint::Length(1),
+                Constraint::Min(0),
+                Constraint::Length(status_height(app)),
+            ]
+            .as_ref(),
+        )
+        .split(size);
+    ui_tabs(f, app, rows[0], selected_style);
+    ui_status(f, app, rows[2]);
+    let rows = &rows[1..];
+
+    match app.mode {
+        AppMode::Browse => {}
+        AppMode::Mining => return ui_mining(f, app, rows[0], selected_style),
+        AppMode::Reader => return ui_reader(f, app, rows[0], selected_style),
+        AppMode::Plan => return ui_plan(f, app, rows[0], selected_style),
+        AppMode::Review => return ui_review(f, app, rows[0], selected_style),
+        AppMode::Stats => return ui_stats(f, app, rows[0], selected_style),
+        AppMode::Compare => return ui_compare(f, app, rows[0], selected_style),
+    }
+
+    let constraints = match app.dictionary.is_empty() {
+        true => vec![
+            Constraint::Percentage(30),
+            Constraint::Percentage(30),
+            Constraint::Percentage(40),
+        ],
+        false => vec![
+            Constraint::Percentage(25),
+            Constraint::Percentage(20),
+            Constraint::Percentage(25),
+            Constraint::Percentage(30),
+        ],
+    };
+    let chunks = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints(constraints)
+        .split(rows[0]);
+    let usage_area = chunks[chunks.len() - 1];
+
+    let title_style = match &app.column {
+        AppColumn::Lemmas => selected_style,
+        _ => Style::default(),
+    };
+
+    let lemmas: Vec<ListItem> = app
+        .lemma_vec
+        .items
+        .iter()
+        .enumerate()
+        .map(|(i, lemma)| {
+            let content = vec![Spans::from(Span::raw(format!("{:4}: {}", i + 1, lemma)))];
+            let style = match app.known.contains(lemma) {
+                true => Style::default().fg(Color::DarkGray),
+                false => Style::default(),
+            };
+            ListItem::new(content).style(style)
+        })
+        .collect();
+    app.lemma_vec
+        .rendered(chunks[0], lemmas.iter().map(ListItem::height).collect());
+    let lemmas = List::new(lemmas)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title(Span::styled("Lemmas", title_style))
+                .title_alignment(Alignment::Left)
+                .border_type(BorderType::Rounded),
+        )
+        .highlight_style(title_style.add_modifier(Modifier::BOLD))
+        .highlight_symbol("> ");
+    f.render_stateful_widget(lemmas, chunks[0], &mut app.lemma_vec.state);
+
+    let title_style = match &app.column {
+        AppColumn::Forms => selected_style,
+        _ => Style::default(),
+    };
+
+    match &mut app.form_vec {
+        Some(form_vec) => {
+            let forms: Vec<ListItem> = form_vec
+                .items
+                .iter()
+                .enumerate()
+                .map(|(i, form)| {
+                    let content = vec![Spans::from(Span::raw(format!("{:4}: {}", i + 1, form)))];
+                    ListItem::new(content)
+                })
+                .collect();
+            let lemma: String = match &app.curr_lemma {
+                Some(lemma) => match app.annotations.gloss(lemma) {
+                    Some(gloss) => format!("{} — {}", lemma, gloss),
+                    None => lemma.to_string(),
+                },
+                None => "".to_string(),
+            };
+            form_vec.rendered(chunks[1], forms.iter().map(ListItem::height).collect());
+            let forms = List::new(forms)
+                .block(
+                    Block::default()
+                        .borders(Borders::ALL)
+                        .title(Span::styled(format!("Forms: {}", lemma), title_style))
+                        .title_alignment(Alignment::Left)
+                        .border_type(BorderType::Rounded),
+                )
+                .highlight_style(title_style.add_modifier(Modifier::BOLD))
+                .highlight_symbol("> ");
+            f.render_stateful_widget(forms, chunks[1], &mut form_vec.state);
+        }
+        _ => {
+            let block = Block::default()
+                .borders(Borders::ALL)
+                .title(Span::styled("Forms", title_style))
+                .title_alignment(Alignment::Left)
+                .border_type(BorderType::Rounded);
+            f.render_widget(block, chunks[1]);
+        }
+    }
+
+    if !app.dictionary.is_empty() {
+        ui_definitions(f, app, chunks[2]);
+    }
+
+    let title_style = match &app.column {
+        AppColumn::Usage => selected_style,
+        _ => Style::default(),
+    };
+
+    let (annotations, lemmatizer) = (&app.annotations, &app.lemmatizer);
+    match &mut app.usage_vec {
+        Some(usage_vec) => {
+            // Borders and highlight symbol take two columns each
+            let para_width = (usage_area.width as usize).saturating_sub(4);
+            let word_style = Style::default().fg(Color::Red);
+            let usage: Vec<ListItem> = usage_vec
+                .items
+                .iter()
+                .map(|item| {
+                    let (before, word, after) = &item.usage;
+                    let mut lines = wrap_usage(before, word, after, word_style, para_width);
+                    // When and by whom it's said, in subtitles and transcripts
+                    let timestamp = lemmatizer
+                        .timing_of(item.sentence_i)
+                        .map(|timing| format_timestamp(timing.start));
+                    let speaker = lemmatizer.speaker_of(item.sentence_i).map(str::to_string);
+                    let header: Vec<String> = timestamp.into_iter().chain(speaker).collect();
+                    if !header.is_empty() {
+                        let header_style = Style::default().fg(Color::DarkGray);
+                        let header = format!("{}:", header.join(" "));
+                        lines.splice(0..0, wrap_usage("", &header, "", header_style, para_width));
+                    }
+                    if let Some(note) = annotations.sentence_note(lemmatizer, item.sentence_i) {
+                        let note_style = Style::default().fg(Color::Cyan);
+                        lines.append(&mut wrap_usage("", note, "", note_style, para_width));
+                    }
+                    ListItem::new(lines)
+                })
+                .collect();
+            let form: String = match &app.curr_form {
+                Some(form) => form.to_string(),
+                None => "".to_string(),
+            };
+            usage_vec.rendered(usage_area, usage.iter().map(ListItem::height).collect());
+            let usage = List::new(usage)
+                .block(
+                    Block::default()
+                        .borders(Borders::ALL)
+                        .title(Span::styled(format!("Usage: {}", form), title_style))
+                        .title_alignment(Alignment::Left)
+                        .border_type(BorderType::Rounded),
+                )
+                .highlight_style(title_style.add_modifier(Modifier::BOLD))
+                .highlight_symbol("> ");
+            f.render_stateful_widget(usage, usage_area, &mut usage_vec.state);
+        }
+        _ => {
+            let block = Block::default()
+                .borders(Borders::ALL)
+                .title(Span::styled("Usage", title_style))
+                .title_alignment(Alignment::Left)
+                .border_type(BorderType::Rounded);
+            f.render_widget(block, usage_area);
+        }
+    };
+
+    /*
+    let title_style = match &app.column {
+        AppColumn::Usage => selected_style,
+        _ => Style::default(),
+    };
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .title(Span::styled("Usage", title_style))
+        .title_alignment(Alignment::Left)
+        .border_type(BorderType::Rounded);
+    f.render_widget(block, chunks[2]);
+    */
+
+    /*
+    let block = Block::default()
+        .title(vec![
+            Span::styled("With", Style::default().fg(Color::Yellow)),
+            Span::from(" background"),
+        ])
+        .title_alignment(Alignment::Center)
+        .style(Style::default().bg(Color::Green));
+    f.render_widget(block, top_chunks[0]);
+
+    let block = Block::default()
+        .title(Span::styled(
+            "Styled title",
+            Style::default()
+                .fg(Color::White)
+                .bg(Color::Red)
+                .add_modifier(Modifier::BOLD),
+        ))
+        .title_alignment(Alignment::Right);
+    f.render_widget(block, top_chunks[1]);
+    */
+}
+
+fn ui_definitions<B: Backend>(f: &mut Frame<B>, app: &App, area: Rect) {
+    let lemma = app.curr_lemma.as_deref().unwrap_or("");
+    let form = app.curr_form.as_deref().unwrap_or("");
+    let width = (area.width as usize).saturating_sub(2);
+
+    let (title, text) = match app.dictionary.lookup_usage(lemma, form) {
+        Some((headword, definitions)) => {
+            let mut text: Vec<Spans> = Vec::new();
+            for definition in definitions {
+                text.push(Spans::from(Span::styled(
+                    definition.source,
+                    Style::default().add_modifier(Modifier::ITALIC),
+                )));
+                for line in definition.text.lines() {
+                    text.append(&mut wrap_usage(line, "", "", Style::default(), width));
+                }
+                text.push(Spans::from(""));
+            }
+            (format!("Definition: {}", headword), text)
+        }
+        None => (
+            "Definition".to_string(),
+            vec![Spans::from(Span::styled(
+                "Not found",
+                Style::default().fg(Color::DarkGray),
+            ))],
+        ),
+    };
+    let definitions = Paragraph::new(text).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .title(title)
+            .title_alignment(Alignment::Left)
+            .border_type(BorderType::Rounded),
+    );
+    f.render_widget(definitions, area);
+}
+
+fn ui_tabs<B: Backend>(f: &mut Frame<B>, app: &App, area: Rect, selected_style: Style) {
+    let titles: Vec<Spans> = TABS
+        .iter()
+        .enumerate()
+        .map(|(i, (_, name))| Spans::from(format!("{} {}", i + 1, name)))
+        .collect();
+    let selected = TABS.iter().position(|(mode, _)| *mode == app.mode);
+    let tabs = Tabs::new(titles)
+        .select(selected.unwrap_or(0))
+        .highlight_style(selected_style);
+    f.render_widget(tabs, area);
+}
+
+/// Coverage curve, parts of speech, vocabulary counts, and where the
+/// selected lemma is used
+fn ui_stats<B: Backend>(f: &mut Frame<B>, app: &App, area: Rect, selected_style: Style) {
+    let rows = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints(
+            [
+                Constraint::Percentage(55),
+                Constraint::Min(0),
+                Constraint::Length(5),
+            ]
+            .as_ref(),
+        )
+        .split(area);
+    let top = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Length(34), Constraint::Min(0)].as_ref())
+        .split(rows[0]);
+    let block = |title: String| {
+        Block::default()
+            .borders(Borders::ALL)
+            .title(Span::styled(title, selected_style))
+            .title_alignment(Alignment::Left)
+            .border_type(BorderType::Rounded)
+    };
+
+    let metrics = app.lemmatizer.get_metrics();
+    let stats = metrics.stats;
+    let coverage = app.lemmatizer.get_coverage(&app.known);
+    let counts = vec![
+        ("Documents", stats.documents.to_string()),
+        ("Sentences", stats.sentences.to_string()),
+        ("Tokens", stats.tokens.to_string()),
+        ("Lemmas", stats.types.to_string()),
+        ("Forms", stats.forms.to_string()),
+        ("Hapax legomena", stats.hapax.to_string()),
+        ("Dis legomena", metrics.dis_legomena.to_string()),
+        (
+            "Type/token ratio",
+            format!("{:.3}", stats.type_token_ratio()),
+        ),
+        ("MATTR", format!("{:.3}", metrics.mattr)),
+        ("MTLD", format!("{:.1}", metrics.mtld)),
+        (
+            "Heaps' K, β",
+            metrics.heaps.map_or(String::new(), |heaps| {
+                format!("{:.2}, {:.3}", heaps.k, heaps.beta)
+            }),
+        ),
+        (
+            "Zipf exponent",
+            metrics
+                .zipf_exponent
+                .map_or(String::new(), |s| format!("{:.3}", s)),
+        ),
+        ("Known lemmas", app.known.len().to_string()),
+        ("Known tokens", format!("{:.1}%", 100.0 * coverage.ratio())),
+    ];
+    let counts: Vec<Spans> = counts
+        .into_iter()
+        .map(|(name, value)| Spans::from(format!("{:<18}{:>12}", name, value)))
+        .collect();
+    f.render_widget(
+        Paragraph::new(counts).block(block("Vocabulary".to_string())),
+        top[0],
+    );
+
+    // Coverage against how many of the most frequent lemmas are learnt
+    let curve: Vec<(f64, f64)> = app
+        .lemmatizer
+        .get_coverage_curve()
+        .iter()
+        .enumerate()
+        .map(|(i, coverage)| ((i + 1) as f64, 100.0 * coverage.ratio() as f64))
+        .collect();
+    let lemmas = curve.len().max(1) as f64;
+    let target: Vec<(f64, f64)> = vec![
+        (0.0, 100.0 * EASY_COVERAGE as f64),
+        (lemmas, 100.0 * EASY_COVERAGE as f64),
+    ];
+    let datasets = vec![
+        Dataset::default()
+            .name("coverage")
+            .marker(symbols::Marker::Braille)
+            .graph_type(GraphType::Line)
+            .style(Style::default().fg(Color::Cyan))
+            .data(&curve),
+        Dataset::default()
+            .name(format!("{:.0}%", 100.0 * EASY_COVERAGE))
+            .marker(symbols::Marker::Braille)
+            .graph_type(GraphType::Line)
+            .style(Style::default().fg(Color::DarkGray))
+            .data(&target),
+    ];
+    let chart = Chart::new(datasets)
+        .block(block(
+            "Coverage by lemmas learnt, most frequent first".to_string(),
+        ))
+        .x_axis(
+            Axis::default()
+                .title("lemmas")
+                .bounds([0.0, lemmas])
+                .labels(
+                    [
+                        "0".to_string(),
+                        format!("{:.0}", lemmas / 2.0),
+                        format!("{:.0}", lemmas),
+                    ]
+                    .iter()
+                    .cloned()
+                    .map(Span::from)
+                    .collect(),
+                ),
+        )
+        .y_axis(
+            Axis::default()
+                .title("%")
+                .bounds([0.0, 100.0])
+                .labels(["0", "50", "100"].iter().cloned().map(Span::from).collect()),
+        );
+    f.render_widget(chart, top[1]);
+
+    let pos_counts = app.lemmatizer.get_pos_counts();
+    let bars: Vec<(&str, u64)> = pos_counts
+        .iter()
+        .map(|(pos, count)| (pos.as_str(), *count as u64))
+        .collect();
+    let pos_chart = BarChart::default()
+        .block(block("Tokens by part of speech".to_string()))
+        .data(&bars)
+        .bar_width(6)
+        .bar_gap(1)
+        .bar_style(Style::default().fg(Color::Cyan))
+        .value_style(Style::default().fg(Color::Black).bg(Color::Cyan));
+    f.render_widget(pos_chart, rows[1]);
+
+    let lemma = app.curr_lemma.as_deref().unwrap_or("");
+    let bins = rows[2].width.saturating_sub(2) as usize;
+    let distribution = app.lemmatizer.get_lemma_distribution(lemma, bins);
+    let dispersion = app
+        .lemmatizer
+        .get_dispersion(lemma, Segments::Paragraphs)
+        .map_or(String::new(), |d| {
+            format!(", Juilland's D {:.2}, DP {:.2}", d.juilland_d, d.gries_dp)
+        });
+    let sparkline = Sparkline::default()
+        .block(block(format!(
+            "Where \"{}\" is used ({}×{}), start to end of the text",
+            lemma,
+            app.lemmatizer.get_lemma_count(lemma),
+            dispersion
+        )))
+        .data(&distribution)
+        .style(Style::default().fg(Color::Yellow));
+    f.render_widget(sparkline, rows[2]);
+}
+
+/// Jaccard overlap and coverage above three columns: lemmas new in the
+/// library, shared ones, and ones only the compared texts use
+fn ui_compare<B: Backend>(f: &mut Frame<B>, app: &mut App, area: Rect, selected_style: Style) {
+    let rows = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Length(3), Constraint::Min(0)].as_ref())
+        .split(area);
+    let columns = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints(
+            [
+                Constraint::Percentage(34),
+                Constraint::Percentage(33),
+                Constraint::Percentage(33),
+            ]
+            .as_ref(),
+        )
+        .split(rows[1]);
+
+    let summary = match &app.comparison {
+        Some(_) if app.compared.documents().is_empty() => {
+            "Nothing to compare with: start with --compare FILE or run :compare PATH".to_string()
+        }
+        Some(comparison) => format!(
+            "{} documents compared, Jaccard overlap {:.3}, {:.1}% of the library's tokens met before",
+            app.compared.documents().len(),
+            comparison.jaccard(),
+            100.0 * comparison.coverage.ratio()
+        ),
+        None => String::new(),
+    };
+    let summary = Paragraph::new(summary).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .title("Compared with texts read before")
+            .title_alignment(Alignment::Left)
+            .border_type(BorderType::Rounded),
+    );
+    f.render_widget(summary, rows[0]);
+
+    let column_style = |column: AppColumn| match app.column == column {
+        true => selected_style,
+        false => Style::default(),
+    };
+    let styles = [
+        column_style(AppColumn::Lemmas),
+        column_style(AppColumn::Forms),
+        column_style(AppColumn::Usage),
+    ];
+
+    let items: Vec<ListItem> = app
+        .compare_new
+        .items
+        .iter()
+        .enumerate()
+        .map(|(i, (lemma, count))| ListItem::new(format!("{:4}: {} ({}×)", i + 1, lemma, count)))
+        .collect();
+    let title = format!("New in the library ({})", items.len());
+    ui_compare_column(f, &mut app.compare_new, items, title, columns[0], styles[0]);
+
+    let items: Vec<ListItem> = app
+        .compare_shared
+        .items
+        .iter()
+        .enumerate()
+        .map(|(i, (lemma, before, now))| {
+            ListItem::new(format!("{:4}: {} ({}× / {}×)", i + 1, lemma, now, before))
+        })
+        .collect();
+    let title = format!("Shared, here / before ({})", items.len());
+    ui_compare_column(
+        f,
+        &mut app.compare_shared,
+        items,
+        title,
+        columns[1],
+        styles[1],
+    );
+
+    let items: Vec<ListItem> = app
+        .compare_old
+        .items
+        .iter()
+        .enumerate()
+        .map(|(i, (lemma, count))| ListItem::new(format!("{:4}: {} ({}×)", i + 1, lemma, count)))
+        .collect();
+    let title = format!("Only met before ({})", items.len());
+    ui_compare_column(f, &mut app.compare_old, items, title, columns[2], styles[2]);
+}
+
+fn ui_compare_column<B: Backend, T>(
+    f: &mut Frame<B>,
+    list: &mut StatefulList<T>,
+    items: Vec<ListItem>,
+    title: String,
+    area: Rect,
+    title_style: Style,
+) {
+    list.rendered(area, items.iter().map(ListItem::height).collect());
+    let items = List::new(items)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title(Span::styled(title, title_style))
+                .title_alignment(Alignment::Left)
+                .border_type(BorderType::Rounded),
+        )
+        .highlight_style(title_style.add_modifier(Modifier::BOLD))
+        .highlight_symbol("> ");
+    f.render_stateful_widget(items, area, &mut list.state);
+}
+
+/// Lines the status bar needs: one, plus one for a message shown above the
+/// text field
+fn status_height(app: &App) -> u16 {
+    match (&app.input, &app.message) {
+        (Some(_), Some(_)) => 2,
+        _ => 1,
+    }
+}
+
+fn ui_status<B: Backend>(f: &mut Frame<B>, app: &App, area: Rect) {
+    if let Some(message) = &app.message {
+        let style = match message.is_error {
+            true => Style::default().fg(Color::Red),
+            false => Style::default(),
+        };
+        let line = Span::styled(format!(" {}", message.text), style);
+        f.render_widget(
+            Paragraph::new(Spans::from(line)),
+            Rect { height: 1, ..area },
+        );
+        if app.input.is_none() {
+            return;
+        }
+    }
+    if let Some(input) = &app.input {
+        let area = Rect {
+            y: area.bottom().saturating_sub(1),
+            height: 1,
+            ..area
+        };
+        let prompt = Span::styled(input.prompt.clone(), Style::default().fg(Color::Yellow));
+        let before_cursor: String = input.text.chars().take(input.cursor).collect();
+        let cursor_x = display_width(&input.prompt) + display_width(&before_cursor);
+        let line = Spans::from(vec![prompt, Span::raw(input.text.clone())]);
+        f.render_widget(Paragraph::new(line), area);
+        f.set_cursor(area.x + cursor_x as u16, area.y);
+        return;
+    }
+
+    let coverage = app.lemmatizer.get_coverage(&app.known);
+    let mut status = vec![
+        Span::styled(
+            format!(" {:.1}% known", 100.0 * coverage.ratio()),
+            difficulty_style(coverage.difficulty()).add_modifier(Modifier::BOLD),
+        ),
+        Span::raw(format!(
+            " ({}/{} tokens, {} lemmas known)",
+            coverage.known,
+            coverage.total,
+            app.known.len()
+        )),
+    ];
+    if !app.card_queue.is_empty() {
+        status.push(Span::raw(format!(
+            " · {} cards queued",
+            app.card_queue.len()
+        )));
+    }
+    if app.count.is_some() || !app.pending_keys.is_empty() {
+        let count = app.count.map_or(String::new(), |n| n.to_string());
+        status.push(Span::styled(
+            format!(" · {}{}", count, keymap::format_keys(&app.pending_keys)),
+            Style::default().fg(Color::Yellow),
+        ));
+    }
+    f.render_widget(Paragraph::new(Spans::from(status)), area);
+}
+
+fn difficulty_style(difficulty: Difficulty) -> Style {
+    match difficulty {
+        Difficulty::Easy => Style::default().fg(Color::Green),
+        Difficulty::Comfortable => Style::default().fg(Color::Yellow),
+        Difficulty::Hard => Style::default().fg(Color::Red),
+    }
+}
+
+fn ui_reader<B: Backend>(f: &mut Frame<B>, app: &mut App, area: Rect, selected_style: Style) {
+    let chunks = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Percentage(70), Constraint::Percentage(30)].as_ref())
+        .split(area);
+
+    let documents = app.lemmatizer.documents();
+    let para_width = (chunks[0].width as usize).saturating_sub(4);
+    // Leave a blank line above each paragraph but the first
+    let paragraph_starts: Vec<usize> = documents
+        .iter()
+        .flat_map(|doc| doc.paragraphs.iter().map(|p| p.start))
+        .filter(|start| *start != 0)
+        .collect();
+    let sentences: Vec<ListItem> = app
+        .reader_vec
+        .items
+        .iter()
+        .map(|sentence_i| {
+            let text = app.lemmatizer.get_sentence(*sentence_i);
+            let coverage = app
+                .lemmatizer
+                .get_sentence_coverage(*sentence_i, &app.known);
+            let style = difficulty_style(coverage.difficulty());
+            let mut lines = wrap_usage("", &text, "", style, para_width);
+            if paragraph_starts.contains(sentence_i) {
+                lines.insert(0, Spans::from(""));
+            }
+            ListItem::new(lines)
+        })
+        .collect();
+    app.reader_vec
+        .rendered(chunks[0], sentences.iter().map(ListItem::height).collect());
+
+    let document_i = app
+        .reader_vec
+        .selected()
+        .and_then(|sentence_i| {
+            documents
+                .iter()
+                .position(|doc| doc.sentences.contains(sentence_i))
+        })
+        .unwrap_or(0);
+    let section_title = app
+        .reader_vec
+        .selected()
+        .and_then(|sentence_i| app.lemmatizer.section_of(*sentence_i))
+        .and_then(|section| section.title.as_deref());
+    let reader_title = match (documents.get(document_i), section_title) {
+        (Some(document), Some(section)) => format!(
+            "Reader: {} — {}",
+            document.title.as_deref().unwrap_or(&document.name),
+            section
+        ),
+        (Some(document), None) => format!(
+            "Reader: {}",
+            document.title.as_deref().unwrap_or(&document.name)
+        ),
+        (None, _) => "Reader".to_string(),
+    };
+    let sentences = List::new(sentences)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title(Span::styled(reader_title, selected_style))
+                .title_alignment(Alignment::Left)
+                .border_type(BorderType::Rounded),
+        )
+        .highlight_style(Style::default().add_modifier(Modifier::BOLD))
+        .highlight_symbol("> ");
+    f.render_stateful_widget(sentences, chunks[0], &mut app.reader_vec.state);
+
+    // What to learn next for the document being read
+    let (title, items) = match app
+        .lemmatizer
+        .get_coverage_report(document_i, &app.known, 50)
+    {
+        None => ("Learn next".to_string(), Vec::new()),
+        Some(report) => {
+            let items: Vec<ListItem> = report
+                .top_unknown
+                .iter()
+                .enumerate()
+                .map(|(i, (lemma, count))| {
+                    ListItem::new(format!("{:4}: {} × {}", i + 1, lemma, count))
+                })
+                .collect();
+            let title = format!("Learn next: {:.1}% known", 100.0 * report.document.ratio());
+            (title, items)
+        }
+    };
+    let learn_next = List::new(items).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .title(title)
+            .title_alignment(Alignment::Left)
+            .border_type(BorderType::Rounded),
+    );
+    f.render_widget(learn_next, chunks[1]);
+}
+
+fn ui_review<B: Backend>(f: &mut Frame<B>, app: &App, area: Rect, selected_style: Style) {
+    let due = app.deck.due(&SystemClock).len();
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .title(Span::styled(
+            format!("Review: {} due, {} learning", due, app.deck.cards().len()),
+            selected_style,
+        ))
+        .title_alignment(Alignment::Left)
+        .border_type(BorderType::Rounded);
+    let width = (area.width as usize).saturating_sub(2);
+
+    let mut text: Vec<Spans> = Vec::new();
+    match &app.review {
+        Some(review) => {
+            let word_style = Style::default().fg(Color::Red);
+            match &review.usage {
+                Some((before, word, after)) if review.revealed => {
+                    text.append(&mut wrap_usage(before, word, after, word_style, width));
+                }
+                Some((before, word, after)) => {
+                    let cloze = "_".repeat(display_width(word).max(3));
+                    text.append(&mut wrap_usage(before, &cloze, after, word_style, width));
+                }
+                None => text.push(Spans::from(review.lemma.clone())),
+            }
+            text.push(Spans::from(""));
+            if review.revealed {
+                text.push(Spans::from(vec![
+                    Span::styled(
+                        review.lemma.clone(),
+                        Style::default().add_modifier(Modifier::BOLD),
+                    ),
+                    Span::raw("   1: again  2: hard  3: good  4: easy"),
+                ]));
+            } else {
+                text.push(Spans::from("Space: show answer"));
+            }
+        }
+        None => text.push(Spans::from(
+            "Nothing due. Press n on a lemma to start learning it.",
+        )),
+    }
+    f.render_widget(Paragraph::new(text).block(block), area);
+}
+
+fn ui_plan<B: Backend>(f: &mut Frame<B>, app: &mut App, area: Rect, selected_style: Style) {
+    let chunks = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Percentage(40), Constraint::Percentage(60)].as_ref())
+        .split(area);
+
+    let plan = match &app.plan {
+        Some(plan) => plan,
+        None => return,
+    };
+    let steps: Vec<ListItem> = plan
+        .steps
+        .iter()
+        .enumerate()
+        .map(|(i, step)| {
+            ListItem::new(format!(
+                "{:4}: {} × {} → {:.1}% (+{:.2})",
+                i + 1,
+                step.lemma,
+                step.count,
+                100.0 * step.coverage.ratio(),
+                100.0 * plan.gain(i)
+            ))
+        })
+        .collect();
+    let title = format!(
+        "Plan: {:.1}% → {:.1}% in {} lemmas{} (a: queue all)",
+        100.0 * plan.start.ratio(),
+        100.0 * plan.target,
+        plan.steps.len(),
+        if plan.reaches_target() {
+            ""
+        } else {
+            ", not reachable"
+        }
+    );
+    app.plan_vec
+        .rendered(chunks[0], steps.iter().map(ListItem::height).collect());
+    let steps = List::new(steps)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title(Span::styled(title, selected_style))
+                .title_alignment(Alignment::Left)
+                .border_type(BorderType::Rounded),
+        )
+        .highlight_style(selected_style)
+        .highlight_symbol("> ");
+    f.render_stateful_widget(steps, chunks[0], &mut app.plan_vec.state);
+
+    let example = match app.plan_vec.selected() {
+        Some(step) => {
+            let (before, word, after) = app.lemmatizer.get_usage(step.example.0, step.example.1);
+            let para_width = (chunks[1].width as usize).saturating_sub(2);
+            let word_style = Style::default().fg(Color::Red);
+            wrap_usage(&before, &word, &after, word_style, para_width)
+        }
+        None => Vec::new(),
+    };
+    let example = Paragraph::new(example).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .title("Example")
+            .title_alignment(Alignment::Left)
+            .border_type(BorderType::Rounded),
+    );
+    f.render_widget(example, chunks[1]);
+}
+
+fn ui_mining<B: Backend>(f: &mut Frame<B>, app: &mut App, area: Rect, selected_style: Style) {
+    let chunks = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Percentage(30), Constraint::Percentage(70)].as_ref())
+        .split(area);
+
+    let title_style = match &app.column {
+        AppColumn::Lemmas => selected_style,
+        _ => Style::default(),
+    };
+
+    let lemmas: Vec<ListItem> = app
+        .mined_vec
+        .items
+        .iter()
+        .enumerate()
+        .map(|(i, mined)| {
+            let content = format!(
+                "{:4}: {} ({}×, {} i+1)",
+                i + 1,
+                mined.lemma,
+                mined.frequency,
+                mined.sentences.len()
+            );
+            ListItem::new(vec![Spans::from(Span::raw(content))])
+        })
+        .collect();
+    app.mined_vec
+        .rendered(chunks[0], lemmas.iter().map(ListItem::height).collect());
+    let lemmas = List::new(lemmas)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title(Span::styled("Unknown lemmas", title_style))
+                .title_alignment(Alignment::Left)
+                .border_type(BorderType::Rounded),
+        )
+        .highlight_style(title_style.add_modifier(Modifier::BOLD))
+        .highlight_symbol("> ");
+    f.render_stateful_widget(lemmas, chunks[0], &mut app.mined_vec.state);
+
+    let title_style = match &app.column {
+        AppColumn::Lemmas => Style::default(),
+        _ => selected_style,
+    };
+
+    match &mut app.mined_sentences {
+        Some(sentences) => {
+            let para_width = (chunks[1].width as usize).saturating_sub(4);
+            let word_style = Style::default().fg(Color::Red);
+            let items: Vec<ListItem> = sentences
+                .items
+                .iter()
+                .map(|sentence| {
+                    let (before, word, after) = &sentence.usage;
+                    ListItem::new(wrap_usage(before, word, after, word_style, para_width))
+                })
+                .collect();
+            sentences.rendered(chunks[1], items.iter().map(ListItem::height).collect());
+            let list = List::new(items)
+                .block(
+                    Block::default()
+                        .borders(Borders::ALL)
+                        .title(Span::styled("i+1 sentences (a: queue card)", title_style))
+                        .title_alignment(Alignment::Left)
+                        .border_type(BorderType::Rounded),
+                )
+                .highlight_style(title_style.add_modifier(Modifier::BOLD))
+                .highlight_symbol("> ");
+            f.render_stateful_widget(list, chunks[1], &mut sentences.state);
+        }
+        None => {
+            let block = Block::default()
+                .borders(Borders::ALL)
+                .title(Span::styled("i+1 sentences", title_style))
+                .title_alignment(Alignment::Left)
+                .border_type(BorderType::Rounded);
+            f.render_widget(block, chunks[1]);
+        }
+    }
+}
+
+/// Wraps a usage sentence to `width` terminal columns, keeping the used word
+/// styled. Widths are measured in display columns, so wide CJK characters
+/// take two, and unspaced scripts break between characters.
+fn wrap_usage(
+    before: &str,
+    word: &str,
+    after: &str,
+    word_style: Style,
+    width: usize,
+) -> Vec<Spans<'static>> {
+    let width = width.max(1);
+    let mut lines: Vec<Spans> = Vec::new();
+    let mut line: Vec<Span> = Vec::new();
+    let mut line_width = 0;
+    // Trailing whitespace of the previous word, only emitted if the line goes on
+    let mut pending: Option<Span> = None;
+
+    for (text, style) in [
+        (before, Style::default()),
+        (word, word_style),
+        (after, Style::default()),
+    ] {
+        let words: Vec<Word> = UnicodeBreakProperties.find_words(text).collect();
+        for word in textwrap::core::break_words(words, width) {
+            let pending_width = pending.as_ref().map_or(0, |s| display_width(&s.content));
+            if line_width > 0 && line_width + pending_width + word.width() > width {
+                lines.push(Spans::from(std::mem::take(&mut line)));
+                line_width = 0;
+                pending = None;
+            }
+            if let Some(space) = pending.take() {
+                line_width += display_width(&space.content);
+                line.push(space);
+            }
+            line.push(Span::styled(word.word.to_string(), style));
+            line_width += word.width();
+            if !word.whitespace.is_empty() {
+                pending = Some(Span::styled(word.whitespace.to_string(), style));
+            }
+        }
+    }
+    if !line.is_empty() {
+        lines.push(Spans::from(line));
+    }
+    lines
+}
+
+// DEPRECATED
+/*
+fn cli() {
+    let opt = Opt::from_args();
+    let filepath = opt.file; //= "data/emos-vs-punks.json";
+    println!();
+    println!("Reading from {}.", filepath);
+    let token_array = tokens_from_file(filepath).unwrap();
+    let lemma_map = map_from_array(&token_array);
+
+    let mut lemma_vec: LemmaVec = lemma_map.iter().collect();
+    lemma_vec.sort_by(|a, b| b.1.word_count().cmp(&a.1.word_count()));
+
+    let word_count = lemma_map.word_count();
+    println!("Word count: {}", word_count);
+
+    let mut count: usize = 0;
+    for (i, (lemma, form_map)) in lemma_vec.into_iter().enumerate() {
+        count += form_map.word_count();
+        let percentage = 100.0 * count as f32 / word_count as f32;
+        println!(
+            "[{:5.1}%] {:4}: {} × {}",
+            percentage,
+            i + 1,
+            lemma,
+            form_map.word_count()
+        );
+
+        let mut form_vec: FormVec = form_map.iter().collect();
+        form_vec.sort_by(|a, b| b.1.len().cmp(&a.1.len()));
+
+        for (j, (form, poss)) in form_vec.into_iter().enumerate() {
+            println!("               {:3}: {} × {}", j + 1, form, poss.len());
+            print!("                      \t");
+            println!(
+                "{}",
+                get_sentence_bolded(&token_array, poss[0].0, poss[0].1)
+            );
+        }
+    }
+}*/
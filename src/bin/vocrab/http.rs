@@ -0,0 +1,206 @@
+use std::collections::HashMap;
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::net::TcpStream;
+use std::time::Duration;
+
+/// Largest request body accepted, enough for a long analyzed document
+pub const MAX_BODY: usize = 64 * 1024 * 1024;
+const MAX_HEADER_LINE: usize = 8 * 1024;
+const READ_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// An HTTP/1.1 request with its path split into decoded segments
+pub struct Request {
+    pub method: String,
+    pub segments: Vec<String>,
+    pub query: HashMap<String, String>,
+    /// Header names are lowercase
+    pub headers: HashMap<String, String>,
+    pub body: Vec<u8>,
+}
+
+impl Request {
+    /// The media type of the body, without parameters such as `charset`
+    pub fn content_type(&self) -> &str {
+        self.headers
+            .get("content-type")
+            .map_or("", |value| value.split(';').next().unwrap_or("").trim())
+    }
+}
+
+pub struct Response {
+    pub status: u16,
+    pub content_type: &'static str,
+    pub body: Vec<u8>,
+}
+
+impl Response {
+    pub fn json<T: serde::Serialize>(status: u16, value: &T) -> Response {
+        Response {
+            status,
+            content_type: "application/json",
+            body: serde_json::to_vec(value).unwrap_or_default(),
+        }
+    }
+
+    /// A JSON `{"error": message}` body
+    pub fn error(status: u16, message: &str) -> Response {
+        Response::json(status, &serde_json::json!({ "error": message }))
+    }
+
+    pub fn empty(status: u16) -> Response {
+        Response {
+            status,
+            content_type: "application/json",
+            body: Vec::new(),
+        }
+    }
+}
+
+/// Reads one request from a connection. Errors are answered with the
+/// response to send back.
+pub fn read_request(stream: &TcpStream) -> Result<Request, Response> {
+    let _ = stream.set_read_timeout(Some(READ_TIMEOUT));
+    let mut reader = BufReader::new(stream);
+    let request_line = read_line(&mut reader)?;
+    let mut parts = request_line.split_whitespace();
+    let (method, target) = match (parts.next(), parts.next(), parts.next()) {
+        (Some(method), Some(target), Some(version)) if version.starts_with("HTTP/1.") => {
+            (method.to_string(), target.to_string())
+        }
+        _ => return Err(Response::error(400, "malformed request line")),
+    };
+
+    let mut headers = HashMap::new();
+    loop {
+        let line = read_line(&mut reader)?;
+        if line.is_empty() {
+            break;
+        }
+        let (name, value) = line
+            .split_once(':')
+            .ok_or_else(|| Response::error(400, "malformed header"))?;
+        headers.insert(name.trim().to_lowercase(), value.trim().to_string());
+    }
+
+    if headers.contains_key("transfer-encoding") {
+        return Err(Response::error(411, "chunked bodies aren't supported"));
+    }
+    let length = match headers.get("content-length") {
+        Some(value) => value
+            .parse::<usize>()
+            .map_err(|_| Response::error(400, "malformed Content-Length"))?,
+        None => 0,
+    };
+    if length > MAX_BODY {
+        return Err(Response::error(413, "body too large"));
+    }
+    let mut body = vec![0; length];
+    reader
+        .read_exact(&mut body)
+        .map_err(|_| Response::error(400, "body shorter than Content-Length"))?;
+
+    let (path, query) = target.split_once('?').unwrap_or((&target, ""));
+    let segments = path
+        .split('/')
+        .filter(|s| !s.is_empty())
+        .map(percent_decode)
+        .collect::<Option<Vec<String>>>()
+        .ok_or_else(|| Response::error(400, "malformed path"))?;
+    let query = parse_query(query).ok_or_else(|| Response::error(400, "malformed query"))?;
+
+    Ok(Request {
+        method,
+        segments,
+        query,
+        headers,
+        body,
+    })
+}
+
+pub fn write_response(mut stream: &TcpStream, response: &Response) -> io::Result<()> {
+    let mut head = format!(
+        "HTTP/1.1 {} {}\r\nContent-Length: {}\r\nConnection: close\r\n\
+         Access-Control-Allow-Origin: *\r\n",
+        response.status,
+        reason(response.status),
+        response.body.len()
+    );
+    if !response.body.is_empty() {
+        head.push_str(&format!("Content-Type: {}\r\n", response.content_type));
+    }
+    if response.status == 204 {
+        // Answers CORS preflight requests from pages served elsewhere
+        head.push_str(
+            "Access-Control-Allow-Methods: GET, POST, PUT, DELETE, OPTIONS\r\n\
+             Access-Control-Allow-Headers: Content-Type\r\n",
+        );
+    }
+    head.push_str("\r\n");
+    stream.write_all(head.as_bytes())?;
+    stream.write_all(&response.body)?;
+    stream.flush()
+}
+
+fn read_line<R: BufRead>(reader: &mut R) -> Result<String, Response> {
+    let mut line = Vec::new();
+    reader
+        .take(MAX_HEADER_LINE as u64)
+        .read_until(b'\n', &mut line)
+        .map_err(|_| Response::error(400, "connection closed"))?;
+    if line.last() != Some(&b'\n') {
+        return Err(Response::error(431, "header line too long"));
+    }
+    String::from_utf8(line)
+        .map(|line| line.trim_end_matches(&['\r', '\n'][..]).to_string())
+        .map_err(|_| Response::error(400, "header isn't UTF-8"))
+}
+
+fn parse_query(query: &str) -> Option<HashMap<String, String>> {
+    query
+        .split('&')
+        .filter(|pair| !pair.is_empty())
+        .map(|pair| {
+            let (name, value) = pair.split_once('=').unwrap_or((pair, ""));
+            Some((
+                percent_decode(&name.replace('+', " "))?,
+                percent_decode(&value.replace('+', " "))?,
+            ))
+        })
+        .collect()
+}
+
+/// Decodes `%XX` escapes, failing on bad escapes or invalid UTF-8
+pub fn percent_decode(text: &str) -> Option<String> {
+    let bytes = text.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' {
+            let hex = std::str::from_utf8(bytes.get(i + 1..i + 3)?).ok()?;
+            decoded.push(u8::from_str_radix(hex, 16).ok()?);
+            i += 3;
+        } else {
+            decoded.push(bytes[i]);
+            i += 1;
+        }
+    }
+    String::from_utf8(decoded).ok()
+}
+
+fn reason(status: u16) -> &'static str {
+    match status {
+        200 => "OK",
+        201 => "Created",
+        204 => "No Content",
+        400 => "Bad Request",
+        404 => "Not Found",
+        405 => "Method Not Allowed",
+        411 => "Length Required",
+        413 => "Payload Too Large",
+        415 => "Unsupported Media Type",
+        422 => "Unprocessable Entity",
+        431 => "Request Header Fields Too Large",
+        502 => "Bad Gateway",
+        _ => "Internal Server Error",
+    }
+}
@@ -0,0 +1,412 @@
+use serde_json::{json, Value};
+use std::collections::{BTreeSet, HashMap};
+use std::error::Error;
+use std::io::{self, BufRead, Write};
+use vocrab::analyzer::{Analyzer, CommandAnalyzer};
+use vocrab::dictionary::Dictionary;
+use vocrab::known::KnownWords;
+use vocrab::lemmatizer::Lemmatizer;
+use vocrab::notes::Annotations;
+use vocrab::spans::WordSpan;
+
+/// Command behind the "mark as known" code action
+pub const MARK_KNOWN: &str = "vocrab.markKnown";
+
+const PARSE_ERROR: i64 = -32700;
+const INVALID_REQUEST: i64 = -32600;
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+const INTERNAL_ERROR: i64 = -32603;
+
+/// Diagnostic severity shown as a hint rather than a problem
+const INFORMATION: u8 = 3;
+/// `window/showMessage` type for errors
+const MESSAGE_ERROR: u8 = 1;
+
+/// A document open in the editor and its analysis
+struct OpenDocument {
+    text: String,
+    lemmatizer: Lemmatizer,
+    spans: Vec<WordSpan>,
+}
+
+/// Marks unknown words in open documents over the Language Server Protocol,
+/// speaking JSON-RPC on standard input and output
+pub struct LanguageServer {
+    pub analyzer: CommandAnalyzer,
+    /// Language given to the analyzer, since editors only know file types
+    pub lang: String,
+    pub known: KnownWords,
+    /// Where known words are saved after each change
+    pub known_path: Option<String>,
+    pub dictionary: Dictionary,
+    pub annotations: Annotations,
+    /// Frequency rank of each lemma in the documents given on the command
+    /// line
+    pub library_ranks: HashMap<String, usize>,
+    documents: HashMap<String, OpenDocument>,
+    shutdown: bool,
+}
+
+enum Flow {
+    Continue,
+    /// The client sent `exit`; the status is non-zero without a `shutdown`
+    Exit(i32),
+}
+
+impl LanguageServer {
+    pub fn new(analyzer: CommandAnalyzer, lang: String) -> LanguageServer {
+        LanguageServer {
+            analyzer,
+            lang,
+            known: KnownWords::new(),
+            known_path: None,
+            dictionary: Dictionary::new(),
+            annotations: Annotations::new(),
+            library_ranks: HashMap::new(),
+            documents: HashMap::new(),
+            shutdown: false,
+        }
+    }
+
+    /// Serves messages until the client exits or closes the input. Returns
+    /// the status the process should exit with.
+    pub fn run<R: BufRead, W: Write>(
+        mut self,
+        mut input: R,
+        mut output: W,
+    ) -> Result<i32, Box<dyn Error>> {
+        loop {
+            let message = match read_message(&mut input)? {
+                Some(Ok(message)) => message,
+                Some(Err(e)) => {
+                    let error = error_response(Value::Null, PARSE_ERROR, &e.to_string());
+                    write_message(&mut output, &error)?;
+                    continue;
+                }
+                None => return Ok(1),
+            };
+            let mut outgoing = Vec::new();
+            let flow = self.handle(&message, &mut outgoing);
+            for message in &outgoing {
+                write_message(&mut output, message)?;
+            }
+            if let Flow::Exit(status) = flow {
+                return Ok(status);
+            }
+        }
+    }
+
+    fn handle(&mut self, message: &Value, outgoing: &mut Vec<Value>) -> Flow {
+        let method = message["method"].as_str().unwrap_or("");
+        let params = &message["params"];
+        let id = match message.get("id") {
+            Some(id) => id.clone(),
+            None => {
+                return self.notify(method, params, outgoing);
+            }
+        };
+        if self.shutdown {
+            outgoing.push(error_response(id, INVALID_REQUEST, "server is shut down"));
+            return Flow::Continue;
+        }
+        let result = match method {
+            "initialize" => Ok(json!({
+                "capabilities": {
+                    "textDocumentSync": { "openClose": true, "change": 1 },
+                    "hoverProvider": true,
+                    "codeActionProvider": true,
+                    "executeCommandProvider": { "commands": [MARK_KNOWN] },
+                },
+                "serverInfo": { "name": "vocrab", "version": env!("CARGO_PKG_VERSION") },
+            })),
+            "shutdown" => {
+                self.shutdown = true;
+                Ok(Value::Null)
+            }
+            "textDocument/hover" => Ok(self.hover(params)),
+            "textDocument/codeAction" => Ok(self.code_actions(params)),
+            "workspace/executeCommand" => self.execute_command(params, outgoing),
+            _ => Err((METHOD_NOT_FOUND, format!("unknown method {}", method))),
+        };
+        outgoing.push(match result {
+            Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+            Err((code, message)) => error_response(id, code, &message),
+        });
+        Flow::Continue
+    }
+
+    fn notify(&mut self, method: &str, params: &Value, outgoing: &mut Vec<Value>) -> Flow {
+        let uri = params["textDocument"]["uri"]
+            .as_str()
+            .unwrap_or("")
+            .to_string();
+        match method {
+            "exit" => return Flow::Exit(if self.shutdown { 0 } else { 1 }),
+            "textDocument/didOpen" => {
+                let text = params["textDocument"]["text"].as_str().unwrap_or("");
+                self.open(&uri, text, outgoing);
+            }
+            "textDocument/didChange" => {
+                // Changes are always the whole text, as asked for in
+                // `initialize`
+                let changes = params["contentChanges"].as_array();
+                if let Some(text) = changes
+                    .and_then(|c| c.last())
+                    .and_then(|c| c["text"].as_str())
+                {
+                    self.open(&uri, text, outgoing);
+                }
+            }
+            "textDocument/didClose" => {
+                self.documents.remove(&uri);
+                outgoing.push(publish_diagnostics(&uri, Vec::new()));
+            }
+            _ => {}
+        }
+        Flow::Continue
+    }
+
+    /// Analyzes a document's text and publishes its unknown words
+    fn open(&mut self, uri: &str, text: &str, outgoing: &mut Vec<Value>) {
+        let mut lemmatizer = Lemmatizer::new();
+        let analyzed = self
+            .analyzer
+            .analyze(text, &self.lang)
+            .and_then(|json| lemmatizer.load_json(uri, &json).map(|_| ()));
+        let spans = match analyzed {
+            Ok(()) => lemmatizer.get_word_spans(0, text).unwrap_or_default(),
+            Err(e) => {
+                outgoing.push(json!({
+                    "jsonrpc": "2.0",
+                    "method": "window/showMessage",
+                    "params": { "type": MESSAGE_ERROR, "message": format!("vocrab: {}", e) },
+                }));
+                Vec::new()
+            }
+        };
+        let document = OpenDocument {
+            text: text.to_string(),
+            lemmatizer,
+            spans,
+        };
+        outgoing.push(publish_diagnostics(uri, self.diagnostics(&document)));
+        self.documents.insert(uri.to_string(), document);
+    }
+
+    fn diagnostics(&self, document: &OpenDocument) -> Vec<Value> {
+        document
+            .spans
+            .iter()
+            .filter(|span| !span.is_known(&self.known))
+            .map(|span| {
+                let word = if span.form == span.lemma {
+                    span.lemma.clone()
+                } else {
+                    format!("{} ({})", span.form, span.lemma)
+                };
+                json!({
+                    "range": range_of(&document.text, span),
+                    "severity": INFORMATION,
+                    "source": "vocrab",
+                    "code": "unknown-word",
+                    "message": format!("Unknown word: {}", word),
+                    "data": { "lemma": span.lemma },
+                })
+            })
+            .collect()
+    }
+
+    fn hover(&self, params: &Value) -> Value {
+        let (document, offset) = match self.locate(params) {
+            Some(found) => found,
+            None => return Value::Null,
+        };
+        let span = match document
+            .spans
+            .iter()
+            .find(|span| span.range.start <= offset && offset < span.range.end)
+        {
+            Some(span) => span,
+            None => return Value::Null,
+        };
+
+        let mut lines = vec![format!("**{}** · {}", span.lemma, span.pos)];
+        let mut counts = vec![
+            format!("form *{}*", span.form),
+            format!(
+                "{}× in this document",
+                document.lemmatizer.get_lemma_count(&span.lemma)
+            ),
+        ];
+        if let Some(rank) = self.library_ranks.get(&span.lemma) {
+            counts.push(format!("#{} in the library", rank));
+        }
+        lines.push(counts.join(" · "));
+        lines.push(if span.is_known(&self.known) {
+            "known".to_string()
+        } else {
+            "unknown".to_string()
+        });
+        let gloss = match self.annotations.gloss(&span.lemma) {
+            Some(gloss) => gloss.to_string(),
+            None => self.dictionary.gloss(&span.lemma, &span.form),
+        };
+        if !gloss.is_empty() {
+            lines.push(gloss);
+        }
+        json!({
+            "contents": { "kind": "markdown", "value": lines.join("\n\n") },
+            "range": range_of(&document.text, span),
+        })
+    }
+
+    /// One "mark as known" action per unknown lemma in the requested range
+    fn code_actions(&self, params: &Value) -> Value {
+        let uri = params["textDocument"]["uri"].as_str().unwrap_or("");
+        let document = match self.documents.get(uri) {
+            Some(document) => document,
+            None => return json!([]),
+        };
+        let start = offset_of(&document.text, &params["range"]["start"]);
+        let end = offset_of(&document.text, &params["range"]["end"]);
+        let (start, end) = match (start, end) {
+            (Some(start), Some(end)) => (start, end),
+            _ => return json!([]),
+        };
+        let lemmas: BTreeSet<&str> = document
+            .spans
+            .iter()
+            .filter(|span| span.range.start <= end && start <= span.range.end)
+            .filter(|span| !span.is_known(&self.known))
+            .map(|span| span.lemma.as_str())
+            .collect();
+        let actions: Vec<Value> = lemmas
+            .into_iter()
+            .map(|lemma| {
+                let title = format!("Mark \"{}\" as known", lemma);
+                json!({
+                    "title": title,
+                    "kind": "quickfix",
+                    "command": { "title": title, "command": MARK_KNOWN, "arguments": [lemma] },
+                })
+            })
+            .collect();
+        json!(actions)
+    }
+
+    fn execute_command(
+        &mut self,
+        params: &Value,
+        outgoing: &mut Vec<Value>,
+    ) -> Result<Value, (i64, String)> {
+        let lemma = match (params["command"].as_str(), params["arguments"][0].as_str()) {
+            (Some(MARK_KNOWN), Some(lemma)) => lemma,
+            _ => return Err((INVALID_PARAMS, "unknown command".to_string())),
+        };
+        if self.known.insert(lemma) {
+            if let Some(path) = &self.known_path {
+                self.known
+                    .save_file(path)
+                    .map_err(|e| (INTERNAL_ERROR, format!("{}: {}", path, e)))?;
+            }
+            let mut uris: Vec<&String> = self.documents.keys().collect();
+            uris.sort();
+            for uri in uris {
+                outgoing.push(publish_diagnostics(
+                    uri,
+                    self.diagnostics(&self.documents[uri]),
+                ));
+            }
+        }
+        Ok(Value::Null)
+    }
+
+    /// The open document and byte offset a position request points at
+    fn locate(&self, params: &Value) -> Option<(&OpenDocument, usize)> {
+        let uri = params["textDocument"]["uri"].as_str()?;
+        let document = self.documents.get(uri)?;
+        let offset = offset_of(&document.text, &params["position"])?;
+        Some((document, offset))
+    }
+}
+
+/// Reads one `Content-Length` framed message. `None` means the input is
+/// closed; a body that isn't JSON is returned as an error to report.
+fn read_message<R: BufRead>(input: &mut R) -> io::Result<Option<serde_json::Result<Value>>> {
+    let mut length = None;
+    loop {
+        let mut line = String::new();
+        if input.read_line(&mut line)? == 0 {
+            return Ok(None);
+        }
+        let line = line.trim_end();
+        if line.is_empty() {
+            if length.is_some() {
+                break;
+            }
+            continue;
+        }
+        if let Some((name, value)) = line.split_once(':') {
+            if name.eq_ignore_ascii_case("content-length") {
+                length = value.trim().parse::<usize>().ok();
+            }
+        }
+    }
+    let mut body = vec![0; length.unwrap_or(0)];
+    input.read_exact(&mut body)?;
+    Ok(Some(serde_json::from_slice(&body)))
+}
+
+fn write_message<W: Write>(output: &mut W, message: &Value) -> io::Result<()> {
+    let body = message.to_string();
+    write!(output, "Content-Length: {}\r\n\r\n{}", body.len(), body)?;
+    output.flush()
+}
+
+fn error_response(id: Value, code: i64, message: &str) -> Value {
+    json!({ "jsonrpc": "2.0", "id": id, "error": { "code": code, "message": message } })
+}
+
+fn publish_diagnostics(uri: &str, diagnostics: Vec<Value>) -> Value {
+    json!({
+        "jsonrpc": "2.0",
+        "method": "textDocument/publishDiagnostics",
+        "params": { "uri": uri, "diagnostics": diagnostics },
+    })
+}
+
+fn range_of(text: &str, span: &WordSpan) -> Value {
+    json!({
+        "start": position_of(text, span.range.start),
+        "end": position_of(text, span.range.end),
+    })
+}
+
+/// An LSP position, whose character is counted in UTF-16 code units
+fn position_of(text: &str, offset: usize) -> Value {
+    let before = &text[..offset];
+    let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+    let line = before.matches('\n').count();
+    let character: usize = before[line_start..].chars().map(char::len_utf16).sum();
+    json!({ "line": line, "character": character })
+}
+
+/// The byte offset of an LSP position, clamped to the end of its line
+fn offset_of(text: &str, position: &Value) -> Option<usize> {
+    let line = position["line"].as_u64()? as usize;
+    let character = position["character"].as_u64()? as usize;
+    let line_start = if line == 0 {
+        0
+    } else {
+        text.match_indices('\n').nth(line - 1)?.0 + 1
+    };
+    let mut units = 0;
+    for (i, c) in text[line_start..].char_indices() {
+        if units >= character || c == '\n' {
+            return Some(line_start + i);
+        }
+        units += c.len_utf16();
+    }
+    Some(text.len())
+}
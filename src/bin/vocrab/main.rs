@@ -0,0 +1,1274 @@
+extern crate vocrab;
+
+mod http;
+mod lsp;
+mod output;
+mod serve;
+
+use lsp::LanguageServer;
+use output::{truncate_end, truncate_start, write_record, write_rows, Align, Format, Row};
+use serde::Serialize;
+use serve::Server;
+use std::error::Error;
+use std::fs::{self, File};
+use std::io::{self, BufWriter, ErrorKind, Write};
+use std::path::Path;
+use std::str::FromStr;
+use structopt::StructOpt;
+use vocrab::analyzer::CommandAnalyzer;
+use vocrab::apkg::write_anki_package;
+use vocrab::dictionary::Dictionary;
+use vocrab::dispersion::Segments;
+use vocrab::epub::{analyze_book, read_epub};
+use vocrab::export::{write_anki_tsv, Card};
+use vocrab::html::{analyze_article, read_article};
+use vocrab::known::KnownWords;
+use vocrab::lemmatizer::{Lemmatizer, LemmatizerError};
+use vocrab::metrics::LexicalMetrics;
+use vocrab::notes::Annotations;
+use vocrab::plan::PlanOptions;
+use vocrab::subtitles::{analyze_subtitles, format_timestamp, read_subtitles};
+
+/// Turns raw text posted to `serve`, or imported, into document JSON
+const DEFAULT_ANALYZER: &str = "python3 src/tokenizer.py --stdout -";
+
+/// Context shown on each side of a usage in text output
+const CONTEXT_WIDTH: usize = 40;
+
+/// Vocabulary analysis of tokenized documents, for scripts and pipelines
+#[derive(StructOpt)]
+#[structopt(name = "vocrab")]
+struct Opt {
+    #[structopt(subcommand)]
+    command: Subcommand,
+}
+
+/// Documents to read and how to print results, shared by every subcommand
+#[derive(StructOpt)]
+struct Input {
+    /// Tokenized documents, as written by tokenizer.py
+    #[structopt(required = true)]
+    files: Vec<String>,
+    /// File of known lemmas, one per line
+    #[structopt(long)]
+    known: Option<String>,
+    /// Output format: text, json or tsv
+    #[structopt(long, default_value = "text")]
+    format: Format,
+    /// Count sentences that repeat earlier ones, like a restarted opening,
+    /// only once
+    #[structopt(long)]
+    skip_duplicates: bool,
+}
+
+#[derive(StructOpt)]
+enum Subcommand {
+    /// Counts of documents, tokens and lemmas, lexical richness, and
+    /// known-word coverage
+    Stats {
+        /// One row of lexical richness metrics per document instead
+        #[structopt(long)]
+        documents: bool,
+        #[structopt(flatten)]
+        input: Input,
+    },
+    /// Lemmas ranked by how often they're used, with how evenly they're
+    /// spread through the text
+    Lemmas {
+        /// Print at most this many lemmas
+        #[structopt(long)]
+        limit: Option<usize>,
+        /// Ranking: freq, or adjusted to rank lemmas used in only a few
+        /// places lower
+        #[structopt(long, default_value = "freq")]
+        sort: LemmaSort,
+        /// Runs of text dispersion is measured over: paragraphs or sentences
+        #[structopt(long, default_value = "paragraphs")]
+        segments: Segments,
+        /// Only lemmas mostly used as this part of speech, e.g. NOUN
+        #[structopt(long)]
+        pos: Vec<String>,
+        /// Leave out known lemmas
+        #[structopt(long)]
+        unknown: bool,
+        #[structopt(flatten)]
+        input: Input,
+    },
+    /// Forms of a lemma and how often each is used
+    Forms {
+        lemma: String,
+        #[structopt(flatten)]
+        input: Input,
+    },
+    /// Sentences a lemma is used in
+    Usages {
+        lemma: String,
+        /// Only usages of this form
+        #[structopt(long)]
+        form: Option<String>,
+        /// Only usages said by this speaker of a transcript
+        #[structopt(long)]
+        speaker: Option<String>,
+        #[structopt(long)]
+        limit: Option<usize>,
+        #[structopt(flatten)]
+        input: Input,
+    },
+    /// Speakers of transcripts and how many sentences each says
+    Speakers {
+        #[structopt(flatten)]
+        input: Input,
+    },
+    /// Chapters and other sections of documents, with the lemmas each is
+    /// the first to use
+    Sections {
+        /// List the lemmas first used in this section, counting from 1,
+        /// instead
+        #[structopt(long)]
+        new: Option<usize>,
+        /// Document the section is in, by name; defaults to the first
+        #[structopt(long)]
+        document: Option<String>,
+        #[structopt(long)]
+        limit: Option<usize>,
+        #[structopt(flatten)]
+        input: Input,
+    },
+    /// Sentences that repeat an earlier one exactly or nearly, within or
+    /// across documents
+    Duplicates {
+        #[structopt(flatten)]
+        input: Input,
+    },
+    /// Flashcards for the lemmas of a learning plan. tsv is Anki's import
+    /// format.
+    Export {
+        /// Coverage the plan aims for, between 0 and 1
+        #[structopt(long, default_value = "0.95")]
+        target: f32,
+        /// Write at most this many cards
+        #[structopt(long)]
+        limit: Option<usize>,
+        /// Part of speech to leave out, e.g. PROPN
+        #[structopt(long)]
+        exclude_pos: Vec<String>,
+        /// Dictionary to take definitions from: a StarDict .ifo, dictd
+        /// .index or Wiktextract .jsonl file
+        #[structopt(long)]
+        dict: Vec<String>,
+        /// File of glosses and sentence notes to add to the cards
+        #[structopt(long)]
+        notes: Option<String>,
+        /// Write to this file instead of standard output; an Anki package
+        /// if it ends in .apkg
+        #[structopt(long, short)]
+        output: Option<String>,
+        #[structopt(flatten)]
+        input: Input,
+    },
+    /// Analyzes a subtitle file (.srt or .vtt), keeping when each sentence
+    /// is said, the main article of a saved web page (.html) or a book
+    /// (.epub), by chapter, into a tokenized document
+    Import {
+        file: String,
+        /// Language of the text, e.g. es
+        #[structopt(long)]
+        lang: String,
+        /// Where to write the document; defaults to the file name with a
+        /// .json extension
+        #[structopt(long, short)]
+        output: Option<String>,
+        /// Command that reads text on standard input and writes document
+        /// JSON, given the language as its last argument
+        #[structopt(long, default_value = DEFAULT_ANALYZER)]
+        analyzer: String,
+    },
+    /// Serve lemmas, usages, coverage and known words as JSON over HTTP
+    Serve {
+        /// Tokenized documents to start with
+        files: Vec<String>,
+        /// File of known lemmas, saved after every change
+        #[structopt(long)]
+        known: Option<String>,
+        #[structopt(long, default_value = "127.0.0.1")]
+        host: String,
+        /// Port to listen on; 0 picks a free one
+        #[structopt(long, default_value = "8080")]
+        port: u16,
+        /// Command that reads text on standard input and writes document
+        /// JSON, given the language as its last argument
+        #[structopt(long, default_value = DEFAULT_ANALYZER)]
+        analyzer: String,
+        /// Count sentences that repeat earlier ones only once
+        #[structopt(long)]
+        skip_duplicates: bool,
+    },
+    /// Lemmas two sets of documents share and those only one of them uses
+    Compare {
+        /// Documents already read, side A
+        #[structopt(required = true)]
+        files: Vec<String>,
+        /// Documents to compare with them, side B
+        #[structopt(long, required = true, min_values = 1)]
+        to: Vec<String>,
+        /// List lemmas instead of a summary: shared, a (only in A) or b
+        /// (only in B)
+        #[structopt(long)]
+        list: Option<Side>,
+        #[structopt(long)]
+        limit: Option<usize>,
+        /// Output format: text, json or tsv
+        #[structopt(long, default_value = "text")]
+        format: Format,
+    },
+    /// Language server that marks unknown words in editors, over standard
+    /// input and output
+    Lsp {
+        /// Tokenized documents to rank lemmas by in hovers
+        files: Vec<String>,
+        /// File of known lemmas, saved when a word is marked known
+        #[structopt(long)]
+        known: Option<String>,
+        /// Language of the documents being edited, e.g. es
+        #[structopt(long, default_value = "en")]
+        lang: String,
+        /// Dictionary to show definitions from in hovers
+        #[structopt(long)]
+        dict: Vec<String>,
+        /// File of glosses, preferred over dictionary definitions
+        #[structopt(long)]
+        notes: Option<String>,
+        #[structopt(long, default_value = DEFAULT_ANALYZER)]
+        analyzer: String,
+    },
+}
+
+/// Which lemmas of a comparison to list
+#[derive(Clone, Copy)]
+enum Side {
+    Shared,
+    OnlyA,
+    OnlyB,
+}
+
+impl FromStr for Side {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Side, String> {
+        match s {
+            "shared" => Ok(Side::Shared),
+            "a" => Ok(Side::OnlyA),
+            "b" => Ok(Side::OnlyB),
+            _ => Err(format!("unknown side \"{}\", expected shared, a or b", s)),
+        }
+    }
+}
+
+/// Order of the lemma list
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum LemmaSort {
+    /// Most used first
+    Frequency,
+    /// Highest frequency times Juilland's D first
+    Adjusted,
+}
+
+impl FromStr for LemmaSort {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<LemmaSort, String> {
+        match s {
+            "freq" => Ok(LemmaSort::Frequency),
+            "adjusted" => Ok(LemmaSort::Adjusted),
+            _ => Err(format!(
+                "unknown sort order \"{}\", expected freq or adjusted",
+                s
+            )),
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct StatsRecord {
+    documents: usize,
+    sentences: usize,
+    tokens: usize,
+    lemmas: usize,
+    forms: usize,
+    hapax: usize,
+    dis_legomena: usize,
+    type_token_ratio: f32,
+    mattr: f32,
+    mtld: f32,
+    heaps_k: Option<f32>,
+    heaps_beta: Option<f32>,
+    zipf_exponent: Option<f32>,
+    known_lemmas: usize,
+    known_tokens: usize,
+    coverage: f32,
+}
+
+impl Row for StatsRecord {
+    const COLUMNS: &'static [(&'static str, Align)] = &[
+        ("documents", Align::Right),
+        ("sentences", Align::Right),
+        ("tokens", Align::Right),
+        ("lemmas", Align::Right),
+        ("forms", Align::Right),
+        ("hapax", Align::Right),
+        ("dis_legomena", Align::Right),
+        ("type_token_ratio", Align::Right),
+        ("mattr", Align::Right),
+        ("mtld", Align::Right),
+        ("heaps_k", Align::Right),
+        ("heaps_beta", Align::Right),
+        ("zipf_exponent", Align::Right),
+        ("known_lemmas", Align::Right),
+        ("known_tokens", Align::Right),
+        ("coverage", Align::Right),
+    ];
+
+    fn fields(&self) -> Vec<String> {
+        vec![
+            self.documents.to_string(),
+            self.sentences.to_string(),
+            self.tokens.to_string(),
+            self.lemmas.to_string(),
+            self.forms.to_string(),
+            self.hapax.to_string(),
+            self.dis_legomena.to_string(),
+            format!("{:.4}", self.type_token_ratio),
+            format!("{:.4}", self.mattr),
+            format!("{:.1}", self.mtld),
+            optional_field(self.heaps_k),
+            optional_field(self.heaps_beta),
+            optional_field(self.zipf_exponent),
+            self.known_lemmas.to_string(),
+            self.known_tokens.to_string(),
+            format!("{:.4}", self.coverage),
+        ]
+    }
+}
+
+/// Lexical richness of one document
+#[derive(Serialize)]
+struct MetricsRow {
+    document: String,
+    tokens: usize,
+    lemmas: usize,
+    hapax: usize,
+    dis_legomena: usize,
+    type_token_ratio: f32,
+    mattr: f32,
+    mtld: f32,
+    heaps_k: Option<f32>,
+    heaps_beta: Option<f32>,
+    zipf_exponent: Option<f32>,
+}
+
+impl MetricsRow {
+    fn new(document: &str, metrics: &LexicalMetrics) -> MetricsRow {
+        MetricsRow {
+            document: document.to_string(),
+            tokens: metrics.stats.tokens,
+            lemmas: metrics.stats.types,
+            hapax: metrics.stats.hapax,
+            dis_legomena: metrics.dis_legomena,
+            type_token_ratio: metrics.stats.type_token_ratio(),
+            mattr: metrics.mattr,
+            mtld: metrics.mtld,
+            heaps_k: metrics.heaps.map(|heaps| heaps.k),
+            heaps_beta: metrics.heaps.map(|heaps| heaps.beta),
+            zipf_exponent: metrics.zipf_exponent,
+        }
+    }
+}
+
+impl Row for MetricsRow {
+    const COLUMNS: &'static [(&'static str, Align)] = &[
+        ("document", Align::Left),
+        ("tokens", Align::Right),
+        ("lemmas", Align::Right),
+        ("hapax", Align::Right),
+        ("dis_legomena", Align::Right),
+        ("type_token_ratio", Align::Right),
+        ("mattr", Align::Right),
+        ("mtld", Align::Right),
+        ("heaps_k", Align::Right),
+        ("heaps_beta", Align::Right),
+        ("zipf_exponent", Align::Right),
+    ];
+
+    fn fields(&self) -> Vec<String> {
+        vec![
+            self.document.clone(),
+            self.tokens.to_string(),
+            self.lemmas.to_string(),
+            self.hapax.to_string(),
+            self.dis_legomena.to_string(),
+            format!("{:.4}", self.type_token_ratio),
+            format!("{:.4}", self.mattr),
+            format!("{:.1}", self.mtld),
+            optional_field(self.heaps_k),
+            optional_field(self.heaps_beta),
+            optional_field(self.zipf_exponent),
+        ]
+    }
+}
+
+/// A fitted value, or nothing when there was too little text to fit it
+fn optional_field(value: Option<f32>) -> String {
+    value.map_or(String::new(), |value| format!("{:.4}", value))
+}
+
+#[derive(Serialize)]
+struct ComparisonRecord {
+    lemmas_a: usize,
+    lemmas_b: usize,
+    shared: usize,
+    only_a: usize,
+    only_b: usize,
+    jaccard: f32,
+    /// Tokens of B whose lemma A uses
+    met_tokens: usize,
+    tokens_b: usize,
+    coverage: f32,
+}
+
+impl Row for ComparisonRecord {
+    const COLUMNS: &'static [(&'static str, Align)] = &[
+        ("lemmas_a", Align::Right),
+        ("lemmas_b", Align::Right),
+        ("shared", Align::Right),
+        ("only_a", Align::Right),
+        ("only_b", Align::Right),
+        ("jaccard", Align::Right),
+        ("met_tokens", Align::Right),
+        ("tokens_b", Align::Right),
+        ("coverage", Align::Right),
+    ];
+
+    fn fields(&self) -> Vec<String> {
+        vec![
+            self.lemmas_a.to_string(),
+            self.lemmas_b.to_string(),
+            self.shared.to_string(),
+            self.only_a.to_string(),
+            self.only_b.to_string(),
+            format!("{:.4}", self.jaccard),
+            self.met_tokens.to_string(),
+            self.tokens_b.to_string(),
+            format!("{:.4}", self.coverage),
+        ]
+    }
+}
+
+#[derive(Serialize)]
+struct ComparedLemmaRow {
+    lemma: String,
+    count_a: usize,
+    count_b: usize,
+}
+
+impl Row for ComparedLemmaRow {
+    const COLUMNS: &'static [(&'static str, Align)] = &[
+        ("lemma", Align::Left),
+        ("count_a", Align::Right),
+        ("count_b", Align::Right),
+    ];
+
+    fn fields(&self) -> Vec<String> {
+        vec![
+            self.lemma.clone(),
+            self.count_a.to_string(),
+            self.count_b.to_string(),
+        ]
+    }
+}
+
+#[derive(Serialize)]
+struct LemmaRow {
+    rank: usize,
+    lemma: String,
+    count: usize,
+    juilland_d: f32,
+    gries_dp: f32,
+    /// Count times Juilland's D
+    adjusted: f32,
+    pos: String,
+    known: bool,
+}
+
+impl Row for LemmaRow {
+    const COLUMNS: &'static [(&'static str, Align)] = &[
+        ("rank", Align::Right),
+        ("lemma", Align::Left),
+        ("count", Align::Right),
+        ("juilland_d", Align::Right),
+        ("gries_dp", Align::Right),
+        ("adjusted", Align::Right),
+        ("pos", Align::Left),
+        ("known", Align::Left),
+    ];
+
+    fn fields(&self) -> Vec<String> {
+        vec![
+            self.rank.to_string(),
+            self.lemma.clone(),
+            self.count.to_string(),
+            format!("{:.3}", self.juilland_d),
+            format!("{:.3}", self.gries_dp),
+            format!("{:.1}", self.adjusted),
+            self.pos.clone(),
+            self.known.to_string(),
+        ]
+    }
+}
+
+#[derive(Serialize)]
+struct FormRow {
+    form: String,
+    count: usize,
+}
+
+impl Row for FormRow {
+    const COLUMNS: &'static [(&'static str, Align)] =
+        &[("form", Align::Left), ("count", Align::Right)];
+
+    fn fields(&self) -> Vec<String> {
+        vec![self.form.clone(), self.count.to_string()]
+    }
+}
+
+/// Vocabulary of one chapter or other section of a document
+#[derive(Serialize)]
+struct SectionRow {
+    document: String,
+    /// Counting from 1
+    section: usize,
+    title: Option<String>,
+    sentences: usize,
+    tokens: usize,
+    lemmas: usize,
+    /// Lemmas no earlier section of the document uses
+    new_lemmas: usize,
+    coverage: f32,
+}
+
+impl Row for SectionRow {
+    const COLUMNS: &'static [(&'static str, Align)] = &[
+        ("document", Align::Left),
+        ("section", Align::Right),
+        ("title", Align::Left),
+        ("sentences", Align::Right),
+        ("tokens", Align::Right),
+        ("lemmas", Align::Right),
+        ("new_lemmas", Align::Right),
+        ("coverage", Align::Right),
+    ];
+
+    fn fields(&self) -> Vec<String> {
+        vec![
+            self.document.clone(),
+            self.section.to_string(),
+            self.title.clone().unwrap_or_default(),
+            self.sentences.to_string(),
+            self.tokens.to_string(),
+            self.lemmas.to_string(),
+            self.new_lemmas.to_string(),
+            format!("{:.4}", self.coverage),
+        ]
+    }
+}
+
+#[derive(Serialize)]
+struct NewLemmaRow {
+    lemma: String,
+    count: usize,
+}
+
+impl Row for NewLemmaRow {
+    const COLUMNS: &'static [(&'static str, Align)] =
+        &[("lemma", Align::Left), ("count", Align::Right)];
+
+    fn fields(&self) -> Vec<String> {
+        vec![self.lemma.clone(), self.count.to_string()]
+    }
+}
+
+#[derive(Serialize)]
+struct SpeakerRow {
+    speaker: String,
+    sentences: usize,
+}
+
+impl Row for SpeakerRow {
+    const COLUMNS: &'static [(&'static str, Align)] =
+        &[("speaker", Align::Left), ("sentences", Align::Right)];
+
+    fn fields(&self) -> Vec<String> {
+        vec![self.speaker.clone(), self.sentences.to_string()]
+    }
+}
+
+#[derive(Serialize)]
+struct DuplicateRow {
+    document: String,
+    /// Index of the sentence within its document
+    sentence: usize,
+    original_document: String,
+    original_sentence: usize,
+    exact: bool,
+    text: String,
+}
+
+impl Row for DuplicateRow {
+    const COLUMNS: &'static [(&'static str, Align)] = &[
+        ("document", Align::Left),
+        ("sentence", Align::Right),
+        ("original_document", Align::Left),
+        ("original_sentence", Align::Right),
+        ("exact", Align::Left),
+        ("text", Align::Left),
+    ];
+
+    fn fields(&self) -> Vec<String> {
+        vec![
+            self.document.clone(),
+            self.sentence.to_string(),
+            self.original_document.clone(),
+            self.original_sentence.to_string(),
+            self.exact.to_string(),
+            self.text.clone(),
+        ]
+    }
+}
+
+#[derive(Serialize)]
+struct UsageRow {
+    document: String,
+    /// Index of the sentence within its document
+    sentence: usize,
+    /// Who says the sentence, in a transcript
+    speaker: Option<String>,
+    /// When the sentence starts in the video, for subtitles
+    time: Option<String>,
+    form: String,
+    before: String,
+    word: String,
+    after: String,
+}
+
+impl Row for UsageRow {
+    const COLUMNS: &'static [(&'static str, Align)] = &[
+        ("document", Align::Left),
+        ("sentence", Align::Right),
+        ("speaker", Align::Left),
+        ("time", Align::Left),
+        ("form", Align::Left),
+        ("before", Align::Right),
+        ("word", Align::Left),
+        ("after", Align::Left),
+    ];
+
+    fn fields(&self) -> Vec<String> {
+        vec![
+            self.document.clone(),
+            self.sentence.to_string(),
+            self.speaker.clone().unwrap_or_default(),
+            self.time.clone().unwrap_or_default(),
+            self.form.clone(),
+            self.before.clone(),
+            self.word.clone(),
+            self.after.clone(),
+        ]
+    }
+
+    /// Lines up the word in a keyword-in-context view
+    fn text_fields(&self) -> Vec<String> {
+        vec![
+            self.document.clone(),
+            self.sentence.to_string(),
+            self.speaker.clone().unwrap_or_default(),
+            self.time.clone().unwrap_or_default(),
+            self.form.clone(),
+            truncate_start(self.before.trim_end(), CONTEXT_WIDTH),
+            self.word.clone(),
+            truncate_end(self.after.trim_start(), CONTEXT_WIDTH),
+        ]
+    }
+}
+
+impl Row for Card {
+    const COLUMNS: &'static [(&'static str, Align)] = &[
+        ("lemma", Align::Left),
+        ("form", Align::Left),
+        ("sentence", Align::Left),
+        ("definition", Align::Left),
+        ("gloss", Align::Left),
+        ("note", Align::Left),
+        ("timestamp", Align::Left),
+    ];
+
+    fn fields(&self) -> Vec<String> {
+        vec![
+            self.lemma.clone(),
+            self.form.clone(),
+            self.sentence(),
+            self.definition.clone(),
+            self.gloss.clone(),
+            self.note.clone(),
+            self.timestamp.clone(),
+        ]
+    }
+}
+
+fn main() {
+    let opt = Opt::from_args();
+    let stdout = io::stdout();
+    let mut out = BufWriter::new(stdout.lock());
+    let result = run(opt.command, &mut out).and_then(|_| out.flush().map_err(Into::into));
+    if let Err(e) = result {
+        // A closed pipe, as from `| head`, just means nobody wants the rest
+        if let Some(e) = e.downcast_ref::<io::Error>() {
+            if e.kind() == ErrorKind::BrokenPipe {
+                return;
+            }
+        }
+        eprintln!("vocrab: {}", e);
+        std::process::exit(1);
+    }
+}
+
+fn run<W: Write>(command: Subcommand, out: &mut W) -> Result<(), Box<dyn Error>> {
+    match command {
+        Subcommand::Stats {
+            documents: true,
+            input,
+        } => {
+            let (lemmatizer, _) = load(&input)?;
+            let rows: Vec<MetricsRow> = lemmatizer
+                .documents()
+                .iter()
+                .map(|document| {
+                    let metrics = lemmatizer.get_range_metrics(document.sentences.clone());
+                    MetricsRow::new(&document.name, &metrics)
+                })
+                .collect();
+            write_rows(&rows, input.format, out)?;
+        }
+        Subcommand::Stats { input, .. } => {
+            let (lemmatizer, known) = load(&input)?;
+            let metrics = lemmatizer.get_metrics();
+            let stats = metrics.stats;
+            let coverage = lemmatizer.get_coverage(&known);
+            let record = StatsRecord {
+                documents: stats.documents,
+                sentences: stats.sentences,
+                tokens: stats.tokens,
+                lemmas: stats.types,
+                forms: stats.forms,
+                hapax: stats.hapax,
+                dis_legomena: metrics.dis_legomena,
+                type_token_ratio: stats.type_token_ratio(),
+                mattr: metrics.mattr,
+                mtld: metrics.mtld,
+                heaps_k: metrics.heaps.map(|heaps| heaps.k),
+                heaps_beta: metrics.heaps.map(|heaps| heaps.beta),
+                zipf_exponent: metrics.zipf_exponent,
+                known_lemmas: known.len(),
+                known_tokens: coverage.known,
+                coverage: coverage.ratio(),
+            };
+            write_record(&record, input.format, out)?;
+        }
+        Subcommand::Lemmas {
+            limit,
+            sort,
+            segments,
+            pos,
+            unknown,
+            input,
+        } => {
+            let (lemmatizer, known) = load(&input)?;
+            let rows = lemma_rows(&lemmatizer, &known, sort, segments, &pos, unknown, limit);
+            write_rows(&rows, input.format, out)?;
+        }
+        Subcommand::Forms { lemma, input } => {
+            let (lemmatizer, _) = load(&input)?;
+            let rows = form_rows(&lemmatizer, &lemma);
+            if rows.is_empty() {
+                return Err(format!("no lemma \"{}\" in the documents", lemma).into());
+            }
+            write_rows(&rows, input.format, out)?;
+        }
+        Subcommand::Sections {
+            new: None, input, ..
+        } => {
+            let (lemmatizer, known) = load(&input)?;
+            let mut rows = Vec::new();
+            for document in lemmatizer.documents() {
+                for (section_i, section) in document.sections.iter().enumerate() {
+                    let range = section.sentences.clone();
+                    let metrics = lemmatizer.get_range_metrics(range.clone());
+                    rows.push(SectionRow {
+                        document: document.name.clone(),
+                        section: section_i + 1,
+                        title: section.title.clone(),
+                        sentences: range.len(),
+                        tokens: metrics.stats.tokens,
+                        lemmas: metrics.stats.types,
+                        new_lemmas: lemmatizer
+                            .get_new_lemmas(range.clone(), document.sentences.start)
+                            .len(),
+                        coverage: lemmatizer.get_range_coverage(range, &known).ratio(),
+                    });
+                }
+            }
+            write_rows(&rows, input.format, out)?;
+        }
+        Subcommand::Sections {
+            new: Some(section),
+            document,
+            limit,
+            input,
+        } => {
+            let (lemmatizer, _) = load(&input)?;
+            let documents = lemmatizer.documents();
+            let document_i = match &document {
+                Some(name) => documents
+                    .iter()
+                    .position(|document| document.name == *name)
+                    .ok_or_else(|| format!("no document \"{}\"", name))?,
+                None => 0,
+            };
+            let new_lemmas = section
+                .checked_sub(1)
+                .and_then(|section_i| lemmatizer.get_section_new_lemmas(document_i, section_i))
+                .ok_or_else(|| match documents.get(document_i) {
+                    Some(document) => format!(
+                        "no section {} in {}, which has {}",
+                        section,
+                        document.name,
+                        document.sections.len()
+                    ),
+                    None => "no documents".to_string(),
+                })?;
+            let rows: Vec<NewLemmaRow> = new_lemmas
+                .into_iter()
+                .take(limit.unwrap_or(usize::MAX))
+                .map(|(lemma, count)| NewLemmaRow { lemma, count })
+                .collect();
+            write_rows(&rows, input.format, out)?;
+        }
+        Subcommand::Speakers { input } => {
+            let (lemmatizer, _) = load(&input)?;
+            let rows: Vec<SpeakerRow> = lemmatizer
+                .get_speakers()
+                .into_iter()
+                .map(|(speaker, sentences)| SpeakerRow { speaker, sentences })
+                .collect();
+            write_rows(&rows, input.format, out)?;
+        }
+        Subcommand::Duplicates { input } => {
+            let (lemmatizer, _) = load(&input)?;
+            let locate = |sentence_i| {
+                lemmatizer
+                    .locate_sentence(sentence_i)
+                    .map_or((String::new(), sentence_i), |(doc, i)| {
+                        (doc.name.clone(), i)
+                    })
+            };
+            let rows: Vec<DuplicateRow> = lemmatizer
+                .get_duplicates()
+                .into_iter()
+                .map(|(sentence_i, duplicate)| {
+                    let (document, sentence) = locate(sentence_i);
+                    let (original_document, original_sentence) = locate(duplicate.original);
+                    DuplicateRow {
+                        document,
+                        sentence,
+                        original_document,
+                        original_sentence,
+                        exact: duplicate.exact,
+                        text: lemmatizer.get_sentence(sentence_i),
+                    }
+                })
+                .collect();
+            write_rows(&rows, input.format, out)?;
+        }
+        Subcommand::Usages {
+            lemma,
+            form,
+            speaker,
+            limit,
+            input,
+        } => {
+            let (lemmatizer, _) = load(&input)?;
+            let rows = usage_rows(
+                &lemmatizer,
+                &lemma,
+                form.as_deref(),
+                speaker.as_deref(),
+                limit,
+            );
+            if rows.is_empty() {
+                return Err(match speaker {
+                    Some(speaker) => format!("no usages of \"{}\" by {}", lemma, speaker),
+                    None => format!("no usages of \"{}\" in the documents", lemma),
+                }
+                .into());
+            }
+            write_rows(&rows, input.format, out)?;
+        }
+        Subcommand::Export {
+            target,
+            limit,
+            exclude_pos,
+            dict,
+            notes,
+            output,
+            input,
+        } => {
+            let (lemmatizer, known) = load(&input)?;
+            let dictionary = load_dictionary(&dict)?;
+            let annotations = load_annotations(&notes)?;
+            let options = PlanOptions {
+                target,
+                exclude_pos,
+                limit,
+                ..PlanOptions::default()
+            };
+            let plan = lemmatizer
+                .get_learning_plan(&known, &options)
+                .ok_or("no such document to plan for")?;
+            let cards: Vec<Card> = lemmatizer
+                .get_plan_cards(&plan)
+                .into_iter()
+                .zip(&plan.steps)
+                .map(|(card, step)| {
+                    let definition = dictionary.gloss(&card.lemma, &card.form);
+                    let card = card.with_definition(definition);
+                    annotations.annotate(card, &lemmatizer, step.example.0)
+                })
+                .collect();
+
+            match output {
+                Some(path) if path.ends_with(".apkg") => {
+                    write_anki_package(&cards, &path).map_err(|e| format!("{}: {}", path, e))?;
+                    eprintln!("{} cards written to {}", cards.len(), path);
+                }
+                Some(path) => {
+                    let file = File::create(&path).map_err(|e| format!("{}: {}", path, e))?;
+                    let mut file = BufWriter::new(file);
+                    write_cards(&cards, input.format, &mut file)?;
+                    file.flush()?;
+                    eprintln!("{} cards written to {}", cards.len(), path);
+                }
+                None => write_cards(&cards, input.format, out)?,
+            }
+        }
+        Subcommand::Compare {
+            files,
+            to,
+            list,
+            limit,
+            format,
+        } => {
+            let a = load_documents(&files)?;
+            let b = load_documents(&to)?;
+            let comparison = a.compare(&b);
+            let limit = limit.unwrap_or(usize::MAX);
+            let row = |lemma: &String, count_a, count_b| ComparedLemmaRow {
+                lemma: lemma.clone(),
+                count_a,
+                count_b,
+            };
+            match list {
+                None => {
+                    let record = ComparisonRecord {
+                        lemmas_a: comparison.shared.len() + comparison.only_a.len(),
+                        lemmas_b: comparison.shared.len() + comparison.only_b.len(),
+                        shared: comparison.shared.len(),
+                        only_a: comparison.only_a.len(),
+                        only_b: comparison.only_b.len(),
+                        jaccard: comparison.jaccard(),
+                        met_tokens: comparison.coverage.known,
+                        tokens_b: comparison.coverage.total,
+                        coverage: comparison.coverage.ratio(),
+                    };
+                    write_record(&record, format, out)?;
+                }
+                Some(side) => {
+                    let rows: Vec<ComparedLemmaRow> = match side {
+                        Side::Shared => comparison
+                            .shared
+                            .iter()
+                            .take(limit)
+                            .map(|(lemma, a, b)| row(lemma, *a, *b))
+                            .collect(),
+                        Side::OnlyA => comparison
+                            .only_a
+                            .iter()
+                            .take(limit)
+                            .map(|(lemma, a)| row(lemma, *a, 0))
+                            .collect(),
+                        Side::OnlyB => comparison
+                            .only_b
+                            .iter()
+                            .take(limit)
+                            .map(|(lemma, b)| row(lemma, 0, *b))
+                            .collect(),
+                    };
+                    write_rows(&rows, format, out)?;
+                }
+            }
+        }
+        Subcommand::Import {
+            file,
+            lang,
+            output,
+            analyzer,
+        } => {
+            let analyzer = CommandAnalyzer::new(&analyzer).ok_or("empty analyzer command")?;
+            let json = import(&file, &analyzer, &lang).map_err(|e| format!("{}: {}", file, e))?;
+            let path = output.unwrap_or_else(|| {
+                Path::new(&file)
+                    .with_extension("json")
+                    .to_string_lossy()
+                    .to_string()
+            });
+            fs::write(&path, json).map_err(|e| format!("{}: {}", path, e))?;
+            eprintln!("{} analyzed into {}", file, path);
+        }
+        Subcommand::Serve {
+            files,
+            known,
+            host,
+            port,
+            analyzer,
+            skip_duplicates,
+        } => {
+            let input = Input {
+                files,
+                known,
+                format: Format::Json,
+                skip_duplicates,
+            };
+            let (lemmatizer, known) = load(&input)?;
+            let server = Server {
+                lemmatizer,
+                known,
+                known_path: input.known,
+                analyzer: CommandAnalyzer::new(&analyzer).ok_or("empty analyzer command")?,
+            };
+            server.run(&host, port)?;
+        }
+        Subcommand::Lsp {
+            files,
+            known,
+            lang,
+            dict,
+            notes,
+            analyzer,
+        } => {
+            let input = Input {
+                files,
+                known,
+                format: Format::Json,
+                skip_duplicates: false,
+            };
+            let (library, known) = load(&input)?;
+            let analyzer = CommandAnalyzer::new(&analyzer).ok_or("empty analyzer command")?;
+            let mut server = LanguageServer::new(analyzer, lang);
+            server.known = known;
+            server.known_path = input.known;
+            server.dictionary = load_dictionary(&dict)?;
+            server.annotations = load_annotations(&notes)?;
+            server.library_ranks = library
+                .get_lemmas()
+                .into_iter()
+                .enumerate()
+                .map(|(i, lemma)| (lemma, i + 1))
+                .collect();
+            let stdin = io::stdin();
+            let status = server.run(stdin.lock(), io::stdout())?;
+            std::process::exit(status);
+        }
+    }
+    Ok(())
+}
+
+/// Document JSON for a subtitle file, web page or book, by its extension
+fn import(file: &str, analyzer: &CommandAnalyzer, lang: &str) -> Result<String, LemmatizerError> {
+    let extension = Path::new(file)
+        .extension()
+        .map(|e| e.to_string_lossy().to_lowercase());
+    match extension.as_deref() {
+        Some("srt") | Some("vtt") => analyze_subtitles(&read_subtitles(file)?, analyzer, lang),
+        Some("html") | Some("htm") | Some("xhtml") => {
+            analyze_article(&read_article(file)?, analyzer, lang)
+        }
+        Some("epub") => analyze_book(&read_epub(file)?, analyzer, lang),
+        _ => Err(LemmatizerError::UnknownFormat(
+            "expected .srt, .vtt, .html or .epub".to_string(),
+        )),
+    }
+}
+
+/// Lemmas ranked by count, optionally only those mostly used as one of
+/// `pos` or not yet known
+fn lemma_rows(
+    lemmatizer: &Lemmatizer,
+    known: &KnownWords,
+    sort: LemmaSort,
+    segments: Segments,
+    pos: &[String],
+    unknown: bool,
+    limit: Option<usize>,
+) -> Vec<LemmaRow> {
+    let pos: Vec<String> = pos.iter().map(|tag| tag.to_uppercase()).collect();
+    let dispersions = lemmatizer.get_dispersions(segments);
+    let lemmas = match sort {
+        LemmaSort::Frequency => lemmatizer.get_lemmas(),
+        LemmaSort::Adjusted => lemmatizer.get_adjusted_lemmas(segments),
+    };
+    lemmas
+        .into_iter()
+        .enumerate()
+        .map(|(i, lemma)| {
+            let count = lemmatizer.get_lemma_count(&lemma);
+            let dispersion = dispersions[&lemma];
+            LemmaRow {
+                rank: i + 1,
+                count,
+                juilland_d: dispersion.juilland_d,
+                gries_dp: dispersion.gries_dp,
+                adjusted: dispersion.adjust(count),
+                pos: lemmatizer
+                    .get_lemma_pos(&lemma)
+                    .first()
+                    .map_or(String::new(), |(pos, _)| pos.clone()),
+                known: known.contains(&lemma),
+                lemma,
+            }
+        })
+        .filter(|row| pos.is_empty() || pos.contains(&row.pos))
+        .filter(|row| !(unknown && row.known))
+        .take(limit.unwrap_or(usize::MAX))
+        .collect()
+}
+
+/// Forms of a lemma, most used first; empty for an unknown lemma
+fn form_rows(lemmatizer: &Lemmatizer, lemma: &str) -> Vec<FormRow> {
+    let lemma = lemma.to_lowercase();
+    lemmatizer
+        .get_forms(&lemma)
+        .into_iter()
+        .map(|form| FormRow {
+            count: lemmatizer.get_usage_positions(&lemma, &form).len(),
+            form,
+        })
+        .collect()
+}
+
+/// Usages of a lemma, or of one of its forms, in text order. A speaker
+/// matches whatever the case.
+fn usage_rows(
+    lemmatizer: &Lemmatizer,
+    lemma: &str,
+    form: Option<&str>,
+    speaker: Option<&str>,
+    limit: Option<usize>,
+) -> Vec<UsageRow> {
+    let speaker = speaker.map(str::to_lowercase);
+    let lemma = lemma.to_lowercase();
+    let forms = match form {
+        Some(form) => vec![form.to_lowercase()],
+        None => lemmatizer.get_forms(&lemma),
+    };
+    let mut positions: Vec<(usize, usize, String)> = forms
+        .iter()
+        .flat_map(|form| {
+            lemmatizer
+                .get_usage_positions(&lemma, form)
+                .into_iter()
+                .map(move |(s, t)| (s, t, form.clone()))
+        })
+        .collect();
+    positions.sort();
+    positions
+        .into_iter()
+        .filter(|(sentence_i, _, _)| match &speaker {
+            Some(speaker) => lemmatizer
+                .speaker_of(*sentence_i)
+                .is_some_and(|said_by| said_by.to_lowercase() == *speaker),
+            None => true,
+        })
+        .take(limit.unwrap_or(usize::MAX))
+        .map(|(sentence_i, token_i, form)| {
+            let (document, sentence) = lemmatizer
+                .locate_sentence(sentence_i)
+                .map_or((String::new(), sentence_i), |(doc, i)| {
+                    (doc.name.clone(), i)
+                });
+            let (before, word, after) = lemmatizer.get_usage(sentence_i, token_i);
+            UsageRow {
+                document,
+                sentence,
+                speaker: lemmatizer.speaker_of(sentence_i).map(str::to_string),
+                time: lemmatizer
+                    .timing_of(sentence_i)
+                    .map(|timing| format_timestamp(timing.start)),
+                form,
+                before,
+                word,
+                after,
+            }
+        })
+        .collect()
+}
+
+fn write_cards<W: Write>(cards: &[Card], format: Format, out: &mut W) -> io::Result<()> {
+    match format {
+        Format::Tsv => write_anki_tsv(cards, out),
+        format => write_rows(cards, format, out),
+    }
+}
+
+fn load(input: &Input) -> Result<(Lemmatizer, KnownWords), Box<dyn Error>> {
+    let mut lemmatizer = load_documents(&input.files)?;
+    lemmatizer.skip_duplicates(input.skip_duplicates);
+    let known = match &input.known {
+        Some(path) => KnownWords::load_file(path).map_err(|e| format!("{}: {}", path, e))?,
+        None => KnownWords::new(),
+    };
+    Ok((lemmatizer, known))
+}
+
+fn load_documents(paths: &[String]) -> Result<Lemmatizer, Box<dyn Error>> {
+    let mut lemmatizer = Lemmatizer::new();
+    for path in paths {
+        lemmatizer
+            .load_file(path)
+            .map_err(|e| format!("{}: {}", path, e))?;
+    }
+    Ok(lemmatizer)
+}
+
+fn load_dictionary(paths: &[String]) -> Result<Dictionary, Box<dyn Error>> {
+    let mut dictionary = Dictionary::new();
+    for path in paths {
+        dictionary
+            .load_file(path)
+            .map_err(|e| format!("{}: {}", path, e))?;
+    }
+    Ok(dictionary)
+}
+
+fn load_annotations(path: &Option<String>) -> Result<Annotations, Box<dyn Error>> {
+    match path {
+        Some(path) => Ok(Annotations::load_file(path).map_err(|e| format!("{}: {}", path, e))?),
+        None => Ok(Annotations::new()),
+    }
+}
@@ -0,0 +1,156 @@
+use serde::Serialize;
+use std::io::{self, Write};
+use std::str::FromStr;
+use textwrap::core::display_width;
+
+/// How results are printed
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Format {
+    /// Aligned columns for reading
+    Text,
+    Json,
+    /// Tab-separated with a header line, for `cut`, `sort` and spreadsheets
+    Tsv,
+}
+
+impl FromStr for Format {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Format, String> {
+        match s {
+            "text" => Ok(Format::Text),
+            "json" => Ok(Format::Json),
+            "tsv" => Ok(Format::Tsv),
+            _ => Err(format!(
+                "unknown format \"{}\", expected text, json or tsv",
+                s
+            )),
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Align {
+    Left,
+    Right,
+}
+
+/// A record printed as one line of a table
+pub trait Row: Serialize {
+    /// Column names, and how to align them as text
+    const COLUMNS: &'static [(&'static str, Align)];
+
+    fn fields(&self) -> Vec<String>;
+
+    /// Fields as shown in a text table, when they should be shortened
+    fn text_fields(&self) -> Vec<String> {
+        self.fields()
+    }
+}
+
+pub fn write_rows<T: Row, W: Write>(rows: &[T], format: Format, out: &mut W) -> io::Result<()> {
+    match format {
+        Format::Json => {
+            serde_json::to_writer_pretty(&mut *out, rows)?;
+            writeln!(out)
+        }
+        Format::Tsv => {
+            let headers: Vec<&str> = T::COLUMNS.iter().map(|(name, _)| *name).collect();
+            writeln!(out, "{}", headers.join("\t"))?;
+            for row in rows {
+                let fields: Vec<String> = row.fields().iter().map(|f| tsv_field(f)).collect();
+                writeln!(out, "{}", fields.join("\t"))?;
+            }
+            Ok(())
+        }
+        Format::Text => {
+            let rows: Vec<Vec<String>> = rows.iter().map(Row::text_fields).collect();
+            let mut widths = vec![0; T::COLUMNS.len()];
+            for row in &rows {
+                for (width, field) in widths.iter_mut().zip(row) {
+                    *width = (*width).max(display_width(field));
+                }
+            }
+            for row in &rows {
+                let mut line = String::new();
+                for (i, field) in row.iter().enumerate() {
+                    let padding = " ".repeat(widths[i] - display_width(field));
+                    let last = i + 1 == row.len();
+                    match T::COLUMNS[i].1 {
+                        Align::Right => line.push_str(&format!("{}{}", padding, field)),
+                        Align::Left if last => line.push_str(field),
+                        Align::Left => line.push_str(&format!("{}{}", field, padding)),
+                    }
+                    if !last {
+                        line.push_str("  ");
+                    }
+                }
+                writeln!(out, "{}", line)?;
+            }
+            Ok(())
+        }
+    }
+}
+
+/// Writes a single record: one `name value` line per field as text, or a
+/// JSON object
+pub fn write_record<T: Row, W: Write>(record: &T, format: Format, out: &mut W) -> io::Result<()> {
+    match format {
+        Format::Json => {
+            serde_json::to_writer_pretty(&mut *out, record)?;
+            writeln!(out)
+        }
+        Format::Tsv => write_rows(std::slice::from_ref(record), format, out),
+        Format::Text => {
+            let width = T::COLUMNS
+                .iter()
+                .map(|(name, _)| display_width(name))
+                .max()
+                .unwrap_or(0);
+            for ((name, _), field) in T::COLUMNS.iter().zip(record.text_fields()) {
+                writeln!(out, "{:<width$}  {}", name, field, width = width)?;
+            }
+            Ok(())
+        }
+    }
+}
+
+fn tsv_field(field: &str) -> String {
+    field.replace(['\t', '\n'], " ")
+}
+
+/// The last `width` columns of a text, marked with `…` if anything was cut
+pub fn truncate_start(text: &str, width: usize) -> String {
+    if display_width(text) <= width {
+        return text.to_string();
+    }
+    let mut kept: Vec<char> = Vec::new();
+    let mut used = 1;
+    for c in text.chars().rev() {
+        used += display_width(c.encode_utf8(&mut [0; 4]));
+        if used > width {
+            break;
+        }
+        kept.push(c);
+    }
+    kept.push('…');
+    kept.iter().rev().collect()
+}
+
+/// The first `width` columns of a text, marked with `…` if anything was cut
+pub fn truncate_end(text: &str, width: usize) -> String {
+    if display_width(text) <= width {
+        return text.to_string();
+    }
+    let mut kept = String::new();
+    let mut used = 1;
+    for c in text.chars() {
+        used += display_width(c.encode_utf8(&mut [0; 4]));
+        if used > width {
+            break;
+        }
+        kept.push(c);
+    }
+    kept.push('…');
+    kept
+}
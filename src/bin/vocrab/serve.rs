@@ -0,0 +1,298 @@
+use crate::http::{read_request, write_response, Request, Response};
+use crate::{form_rows, lemma_rows, usage_rows, LemmaSort};
+use serde::Serialize;
+use std::error::Error;
+use std::io::{self, Write};
+use std::net::TcpListener;
+use vocrab::analyzer::{Analyzer, CommandAnalyzer};
+use vocrab::coverage::Coverage;
+use vocrab::dispersion::Segments;
+use vocrab::known::KnownWords;
+use vocrab::lemmatizer::{Document, Lemmatizer, LemmatizerError};
+
+/// Documents and known words shared by every request
+pub struct Server {
+    pub lemmatizer: Lemmatizer,
+    pub known: KnownWords,
+    /// Where known words are saved after each change
+    pub known_path: Option<String>,
+    pub analyzer: CommandAnalyzer,
+}
+
+#[derive(Serialize)]
+struct CoverageRecord {
+    known: usize,
+    total: usize,
+    coverage: f32,
+}
+
+impl From<Coverage> for CoverageRecord {
+    fn from(coverage: Coverage) -> CoverageRecord {
+        CoverageRecord {
+            known: coverage.known,
+            total: coverage.total,
+            coverage: coverage.ratio(),
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct DocumentCoverage {
+    name: String,
+    #[serde(flatten)]
+    coverage: CoverageRecord,
+}
+
+#[derive(Serialize)]
+struct CoverageResponse {
+    library: CoverageRecord,
+    documents: Vec<DocumentCoverage>,
+}
+
+#[derive(Serialize)]
+struct DocumentRecord {
+    name: String,
+    title: Option<String>,
+    language: String,
+    sentences: usize,
+    paragraphs: usize,
+}
+
+impl From<&Document> for DocumentRecord {
+    fn from(document: &Document) -> DocumentRecord {
+        DocumentRecord {
+            name: document.name.clone(),
+            title: document.title.clone(),
+            language: document.language.code().to_string(),
+            sentences: document.sentences.len(),
+            paragraphs: document.paragraphs.len(),
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct KnownRecord<'a> {
+    lemma: &'a str,
+    known: bool,
+}
+
+impl Server {
+    /// Answers requests one at a time until the process is stopped. The
+    /// address is printed first, so a caller that asked for port 0 can
+    /// find out which port it got.
+    pub fn run(mut self, host: &str, port: u16) -> Result<(), Box<dyn Error>> {
+        let listener = TcpListener::bind((host, port))?;
+        println!("Listening on http://{}", listener.local_addr()?);
+        io::stdout().flush()?;
+        for stream in listener.incoming() {
+            let stream = match stream {
+                Ok(stream) => stream,
+                Err(e) => {
+                    eprintln!("vocrab: {}", e);
+                    continue;
+                }
+            };
+            let response = match read_request(&stream) {
+                Ok(request) => self.handle(&request),
+                Err(response) => response,
+            };
+            if let Err(e) = write_response(&stream, &response) {
+                eprintln!("vocrab: {}", e);
+            }
+        }
+        Ok(())
+    }
+
+    fn handle(&mut self, request: &Request) -> Response {
+        let segments: Vec<&str> = request.segments.iter().map(String::as_str).collect();
+        let method = request.method.as_str();
+        if method == "OPTIONS" {
+            return Response::empty(204);
+        }
+        let result = match (method, segments.as_slice()) {
+            ("GET", ["lemmas"]) => self.lemmas(request),
+            ("GET", ["lemmas", lemma, "forms"]) => self.forms(lemma),
+            ("GET", ["lemmas", lemma, "usages"]) => self.usages(lemma, request),
+            ("GET", ["coverage"]) => Ok(self.coverage()),
+            ("GET", ["known"]) => Ok(Response::json(200, &self.known.sorted())),
+            ("GET", ["known", lemma]) => Ok(self.known_status(lemma)),
+            ("PUT", ["known", lemma]) => self.set_known(lemma, true),
+            ("DELETE", ["known", lemma]) => self.set_known(lemma, false),
+            ("GET", ["documents"]) => Ok(self.documents()),
+            ("POST", ["documents"]) => self.add_document(request),
+            (_, ["lemmas"])
+            | (_, ["lemmas", _, "forms"])
+            | (_, ["lemmas", _, "usages"])
+            | (_, ["coverage"])
+            | (_, ["known"])
+            | (_, ["known", _])
+            | (_, ["documents"]) => Err(Response::error(405, "method not allowed")),
+            _ => Err(Response::error(404, "no such endpoint")),
+        };
+        result.unwrap_or_else(|response| response)
+    }
+
+    fn lemmas(&self, request: &Request) -> Result<Response, Response> {
+        let limit = query_number(request, "limit")?;
+        let pos: Vec<String> = request.query.get("pos").map_or(Vec::new(), |pos| {
+            pos.split(',').map(str::to_string).collect()
+        });
+        let unknown = query_flag(request, "unknown")?;
+        let sort = match request.query.get("sort") {
+            Some(sort) => sort.parse().map_err(|e: String| Response::error(400, &e))?,
+            None => LemmaSort::Frequency,
+        };
+        let rows = lemma_rows(
+            &self.lemmatizer,
+            &self.known,
+            sort,
+            Segments::Paragraphs,
+            &pos,
+            unknown,
+            limit,
+        );
+        Ok(Response::json(200, &rows))
+    }
+
+    fn forms(&self, lemma: &str) -> Result<Response, Response> {
+        let rows = form_rows(&self.lemmatizer, lemma);
+        if rows.is_empty() {
+            return Err(no_lemma(lemma));
+        }
+        Ok(Response::json(200, &rows))
+    }
+
+    fn usages(&self, lemma: &str, request: &Request) -> Result<Response, Response> {
+        if self.lemmatizer.get_lemma_count(&lemma.to_lowercase()) == 0 {
+            return Err(no_lemma(lemma));
+        }
+        let limit = query_number(request, "limit")?;
+        let form = request.query.get("form").map(String::as_str);
+        let speaker = request.query.get("speaker").map(String::as_str);
+        let rows = usage_rows(&self.lemmatizer, lemma, form, speaker, limit);
+        Ok(Response::json(200, &rows))
+    }
+
+    fn coverage(&self) -> Response {
+        let documents: Vec<DocumentCoverage> = self
+            .lemmatizer
+            .documents()
+            .iter()
+            .map(|document| DocumentCoverage {
+                name: document.name.clone(),
+                coverage: self
+                    .lemmatizer
+                    .get_range_coverage(document.sentences.clone(), &self.known)
+                    .into(),
+            })
+            .collect();
+        Response::json(
+            200,
+            &CoverageResponse {
+                library: self.lemmatizer.get_coverage(&self.known).into(),
+                documents,
+            },
+        )
+    }
+
+    fn known_status(&self, lemma: &str) -> Response {
+        let lemma = lemma.to_lowercase();
+        Response::json(
+            200,
+            &KnownRecord {
+                known: self.known.contains(&lemma),
+                lemma: &lemma,
+            },
+        )
+    }
+
+    fn set_known(&mut self, lemma: &str, known: bool) -> Result<Response, Response> {
+        let changed = if known {
+            self.known.insert(lemma)
+        } else {
+            self.known.remove(lemma)
+        };
+        if let (true, Some(path)) = (changed, &self.known_path) {
+            self.known
+                .save_file(path)
+                .map_err(|e| Response::error(500, &format!("{}: {}", path, e)))?;
+        }
+        Ok(self.known_status(lemma))
+    }
+
+    fn documents(&self) -> Response {
+        let documents: Vec<DocumentRecord> = self
+            .lemmatizer
+            .documents()
+            .iter()
+            .map(DocumentRecord::from)
+            .collect();
+        Response::json(200, &documents)
+    }
+
+    /// Adds analyzed JSON as is, or runs raw text through the analyzer
+    fn add_document(&mut self, request: &Request) -> Result<Response, Response> {
+        let body = std::str::from_utf8(&request.body)
+            .map_err(|_| Response::error(400, "body isn't UTF-8"))?;
+        let name = match request.query.get("name") {
+            Some(name) => name.clone(),
+            None => format!("document-{}", self.lemmatizer.documents().len() + 1),
+        };
+        let json = match request.content_type() {
+            "application/json" => body.to_string(),
+            "text/plain" => {
+                let lang = request
+                    .query
+                    .get("lang")
+                    .ok_or_else(|| Response::error(400, "raw text needs a lang parameter"))?;
+                self.analyzer
+                    .analyze(body, lang)
+                    .map_err(|e| Response::error(502, &e.to_string()))?
+            }
+            _ => {
+                return Err(Response::error(
+                    415,
+                    "expected application/json or text/plain",
+                ))
+            }
+        };
+        self.lemmatizer
+            .load_json(&name, &json)
+            .map_err(|e| match e {
+                LemmatizerError::JSONParseFailed(e) => {
+                    Response::error(422, &format!("JSON parsing failed: {}", e))
+                }
+                e => Response::error(500, &e.to_string()),
+            })?;
+        let document = self.lemmatizer.documents().last().map(DocumentRecord::from);
+        Ok(Response::json(201, &document))
+    }
+}
+
+fn no_lemma(lemma: &str) -> Response {
+    Response::error(404, &format!("no lemma \"{}\" in the documents", lemma))
+}
+
+fn query_number(request: &Request, name: &str) -> Result<Option<usize>, Response> {
+    request
+        .query
+        .get(name)
+        .map(|value| {
+            value
+                .parse()
+                .map_err(|_| Response::error(400, &format!("{} must be a number", name)))
+        })
+        .transpose()
+}
+
+/// A flag given as `name`, `name=true` or `name=1`
+fn query_flag(request: &Request, name: &str) -> Result<bool, Response> {
+    match request.query.get(name).map(String::as_str) {
+        None | Some("false") | Some("0") => Ok(false),
+        Some("") | Some("true") | Some("1") => Ok(true),
+        Some(_) => Err(Response::error(
+            400,
+            &format!("{} must be true or false", name),
+        )),
+    }
+}
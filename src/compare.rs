@@ -0,0 +1,69 @@
+use crate::coverage::Coverage;
+use crate::known::KnownWords;
+use crate::lemmatizer::{Lemmatizer, WordCount};
+
+/// How the vocabulary of two texts or corpora, A and B, overlaps
+#[derive(Clone, Debug, Default)]
+pub struct Comparison {
+    /// Lemmas used on both sides with their counts in A and in B, most used
+    /// in B first
+    pub shared: Vec<(String, usize, usize)>,
+    /// Lemmas only A uses, with their counts, most used first
+    pub only_a: Vec<(String, usize)>,
+    /// Lemmas only B uses, with their counts, most used first. These are the
+    /// words a reader of A has not met yet.
+    pub only_b: Vec<(String, usize)>,
+    /// Share of B's word tokens whose lemma A uses
+    pub coverage: Coverage,
+}
+
+impl Comparison {
+    /// Shared lemmas out of all lemmas used on either side
+    pub fn jaccard(&self) -> f32 {
+        let shared = self.shared.len();
+        match shared + self.only_a.len() + self.only_b.len() {
+            0 => 1.0,
+            total => shared as f32 / total as f32,
+        }
+    }
+}
+
+impl Lemmatizer {
+    /// Compares the vocabulary of this library, A, to another, B
+    pub fn compare(&self, other: &Lemmatizer) -> Comparison {
+        let mut shared = Vec::new();
+        let mut only_a = Vec::new();
+        for (lemma, forms) in &self.lemma_map {
+            match other.lemma_map.get(lemma) {
+                Some(other_forms) => {
+                    shared.push((lemma.clone(), forms.word_count(), other_forms.word_count()))
+                }
+                None => only_a.push((lemma.clone(), forms.word_count())),
+            }
+        }
+        let mut only_b: Vec<(String, usize)> = other
+            .lemma_map
+            .iter()
+            .filter(|(lemma, _)| !self.lemma_map.contains_key(*lemma))
+            .map(|(lemma, forms)| (lemma.clone(), forms.word_count()))
+            .collect();
+
+        shared.sort_by(|a, b| {
+            b.2.cmp(&a.2)
+                .then_with(|| b.1.cmp(&a.1))
+                .then_with(|| a.0.cmp(&b.0))
+        });
+        for counts in [&mut only_a, &mut only_b] {
+            counts.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        }
+
+        // Everything A uses counts as met, like a known-word list
+        let met: KnownWords = self.lemma_map.keys().map(String::as_str).collect();
+        Comparison {
+            shared,
+            only_a,
+            only_b,
+            coverage: other.get_coverage(&met),
+        }
+    }
+}
@@ -0,0 +1,155 @@
+use crate::known::KnownWords;
+use crate::lemmatizer::{Lemmatizer, Token};
+use std::collections::HashMap;
+use std::ops::Range;
+
+/// Parts of speech a reader understands without having learnt them
+pub const TRANSPARENT_POS: [&str; 3] = ["PROPN", "NUM", "SYM"];
+
+/// Coverage a text should reach before it is comfortable to read
+pub const COMFORTABLE_COVERAGE: f32 = 0.90;
+/// Coverage at which a text can be read for pleasure
+pub const EASY_COVERAGE: f32 = 0.95;
+
+/// Known tokens out of all word tokens of a span of text
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Coverage {
+    pub known: usize,
+    pub total: usize,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Difficulty {
+    Easy,
+    Comfortable,
+    Hard,
+}
+
+impl Coverage {
+    /// Share of known tokens; an empty span counts as fully known
+    pub fn ratio(&self) -> f32 {
+        match self.total {
+            0 => 1.0,
+            total => self.known as f32 / total as f32,
+        }
+    }
+
+    pub fn difficulty(&self) -> Difficulty {
+        match self.ratio() {
+            r if r >= EASY_COVERAGE => Difficulty::Easy,
+            r if r >= COMFORTABLE_COVERAGE => Difficulty::Comfortable,
+            _ => Difficulty::Hard,
+        }
+    }
+
+    fn add(&mut self, other: Coverage) {
+        self.known += other.known;
+        self.total += other.total;
+    }
+}
+
+/// Coverage of one document at every level, and what to learn next
+#[derive(Clone, Debug)]
+pub struct CoverageReport {
+    pub document: Coverage,
+    pub paragraphs: Vec<Coverage>,
+    pub sentences: Vec<Coverage>,
+    /// Unknown lemmas that would raise coverage the most, with their counts
+    pub top_unknown: Vec<(String, usize)>,
+}
+
+pub(crate) fn is_known(token: &Token, known: &KnownWords) -> bool {
+    TRANSPARENT_POS.contains(&token.pos.as_str()) || known.contains(&token.lemma_key())
+}
+
+impl Lemmatizer {
+    /// Coverage of the whole library
+    pub fn get_coverage(&self, known: &KnownWords) -> Coverage {
+        self.get_range_coverage(0..self.tokens.len(), known)
+    }
+
+    /// Coverage of one sentence. A sentence skipped as a repeat of an
+    /// earlier one has no tokens to cover.
+    pub fn get_sentence_coverage(&self, sentence_i: usize, known: &KnownWords) -> Coverage {
+        self.get_range_coverage(sentence_i..sentence_i + 1, known)
+    }
+
+    pub fn get_range_coverage(&self, sentences: Range<usize>, known: &KnownWords) -> Coverage {
+        let mut coverage = Coverage::default();
+        for (_, sentence) in self.counted_sentences(sentences) {
+            for token in sentence.iter().filter(|t| !t.is_punct()) {
+                coverage.total += 1;
+                if is_known(token, known) {
+                    coverage.known += 1;
+                }
+            }
+        }
+        coverage
+    }
+
+    /// Unknown lemmas by how many tokens learning each would make known
+    pub fn get_top_unknown(&self, known: &KnownWords, limit: usize) -> Vec<(String, usize)> {
+        self.top_unknown_in(0..self.tokens.len(), known, limit)
+    }
+
+    /// The `limit` unknown lemmas with the most tokens in `sentences`
+    fn top_unknown_in(
+        &self,
+        sentences: Range<usize>,
+        known: &KnownWords,
+        limit: usize,
+    ) -> Vec<(String, usize)> {
+        let mut counts: HashMap<String, usize> = HashMap::new();
+        for (_, sentence) in self.counted_sentences(sentences) {
+            for token in sentence
+                .iter()
+                .filter(|t| !t.is_punct() && !is_known(t, known))
+            {
+                *counts.entry(token.lemma_key()).or_default() += 1;
+            }
+        }
+        let mut top_unknown: Vec<(String, usize)> = counts.into_iter().collect();
+        top_unknown.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        top_unknown.truncate(limit);
+        top_unknown
+    }
+
+    /// Coverage of the document at `document_i` per document, paragraph and
+    /// sentence, with the `limit` unknown lemmas worth learning first, or
+    /// `None` if there is no such document
+    pub fn get_coverage_report(
+        &self,
+        document_i: usize,
+        known: &KnownWords,
+        limit: usize,
+    ) -> Option<CoverageReport> {
+        let document = self.documents().get(document_i)?;
+        let sentences: Vec<Coverage> = document
+            .sentences
+            .clone()
+            .map(|sentence_i| self.get_sentence_coverage(sentence_i, known))
+            .collect();
+        let paragraphs: Vec<Coverage> = document
+            .paragraphs
+            .iter()
+            .map(|paragraph| {
+                let mut coverage = Coverage::default();
+                for sentence_i in paragraph.clone() {
+                    coverage.add(sentences[sentence_i - document.sentences.start]);
+                }
+                coverage
+            })
+            .collect();
+        let mut total = Coverage::default();
+        for coverage in &sentences {
+            total.add(*coverage);
+        }
+
+        Some(CoverageReport {
+            document: total,
+            paragraphs,
+            sentences,
+            top_unknown: self.top_unknown_in(document.sentences.clone(), known, limit),
+        })
+    }
+}
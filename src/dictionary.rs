@@ -0,0 +1,516 @@
+use crate::lemmatizer::LemmatizerError;
+use flate2::read::GzDecoder;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::{BufRead, BufReader, Read};
+use std::path::{Path, PathBuf};
+
+/// A definition of a headword, and the dictionary it came from
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Definition {
+    pub source: String,
+    pub text: String,
+}
+
+/// Where the text of an entry lives
+#[derive(Debug)]
+enum Entry {
+    /// A slice of a source's `.dict` data
+    Slice {
+        source: usize,
+        offset: usize,
+        size: usize,
+    },
+    Text {
+        source: usize,
+        text: String,
+    },
+}
+
+#[derive(Debug)]
+struct Source {
+    name: String,
+    data: Vec<u8>,
+    /// StarDict `sametypesequence`, empty when every entry carries its type
+    types: String,
+}
+
+/// Local dictionaries indexed by lowercase headword. Reads StarDict
+/// (`.ifo`), dictd (`.index`) and Wiktextract (`.jsonl`) files.
+#[derive(Debug, Default)]
+pub struct Dictionary {
+    sources: Vec<Source>,
+    index: HashMap<String, Vec<Entry>>,
+}
+
+impl Dictionary {
+    pub fn new() -> Dictionary {
+        Dictionary::default()
+    }
+
+    /// Loads a dictionary, picking the format from the file extension. The
+    /// `.idx`/`.dict` or `.dict` files next to an `.ifo` or `.index` are found
+    /// by name.
+    pub fn load_file<P: AsRef<Path>>(
+        &mut self,
+        path: P,
+    ) -> Result<&mut Dictionary, LemmatizerError> {
+        let path = path.as_ref();
+        match path.extension().and_then(|e| e.to_str()) {
+            Some("ifo") => self.load_stardict(path)?,
+            Some("index") => self.load_dictd(path)?,
+            Some("jsonl") | Some("json") => self.load_wiktextract(path)?,
+            _ => return Err(LemmatizerError::UnknownFormat(path.display().to_string())),
+        }
+        Ok(self)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.sources.is_empty()
+    }
+
+    pub fn lookup(&self, word: &str) -> Vec<Definition> {
+        match self.index.get(&word.to_lowercase()) {
+            Some(entries) => entries.iter().map(|entry| self.definition(entry)).collect(),
+            None => Vec::new(),
+        }
+    }
+
+    /// Looks up a lemma, falling back to the form it was used in. Returns
+    /// the headword that matched along with its definitions.
+    pub fn lookup_usage(&self, lemma: &str, form: &str) -> Option<(String, Vec<Definition>)> {
+        [lemma, form]
+            .iter()
+            .map(|word| (word.to_string(), self.lookup(word)))
+            .find(|(_, definitions)| !definitions.is_empty())
+    }
+
+    /// All definitions of a lemma or form joined into one line, for exports
+    pub fn gloss(&self, lemma: &str, form: &str) -> String {
+        match self.lookup_usage(lemma, form) {
+            Some((_, definitions)) => definitions
+                .iter()
+                .map(|d| d.text.split_whitespace().collect::<Vec<&str>>().join(" "))
+                .collect::<Vec<String>>()
+                .join(" | "),
+            None => String::new(),
+        }
+    }
+
+    fn definition(&self, entry: &Entry) -> Definition {
+        match entry {
+            Entry::Slice {
+                source,
+                offset,
+                size,
+            } => {
+                let source = &self.sources[*source];
+                let end = offset.saturating_add(*size).min(source.data.len());
+                let bytes = &source.data[(*offset).min(end)..end];
+                Definition {
+                    source: source.name.clone(),
+                    text: entry_text(bytes, &source.types),
+                }
+            }
+            Entry::Text { source, text } => Definition {
+                source: self.sources[*source].name.clone(),
+                text: text.clone(),
+            },
+        }
+    }
+
+    fn add_source(&mut self, name: String, data: Vec<u8>, types: String) -> usize {
+        self.sources.push(Source { name, data, types });
+        self.sources.len() - 1
+    }
+
+    fn add_entry(&mut self, headword: &str, entry: Entry) {
+        self.index
+            .entry(headword.to_lowercase())
+            .or_default()
+            .push(entry);
+    }
+
+    fn load_stardict(&mut self, ifo: &Path) -> Result<(), LemmatizerError> {
+        let info = fs::read_to_string(ifo).map_err(LemmatizerError::FileIOFailed)?;
+        let info: HashMap<&str, &str> = info
+            .lines()
+            .filter_map(|line| line.split_once('='))
+            .map(|(k, v)| (k.trim(), v.trim()))
+            .collect();
+        let name = info
+            .get("bookname")
+            .copied()
+            .unwrap_or("StarDict")
+            .to_string();
+        let offset_bytes = match info.get("idxoffsetbits") {
+            Some(&"64") => 8,
+            _ => 4,
+        };
+        let types = info
+            .get("sametypesequence")
+            .copied()
+            .unwrap_or("")
+            .to_string();
+
+        let idx = fs::read(ifo.with_extension("idx")).map_err(LemmatizerError::FileIOFailed)?;
+        let data = read_dict_data(ifo.with_extension("dict"))?;
+        let source = self.add_source(name, data, types);
+
+        let mut pos = 0;
+        while pos < idx.len() {
+            let end = idx[pos..]
+                .iter()
+                .position(|b| *b == 0)
+                .map(|i| pos + i)
+                .ok_or_else(|| malformed(ifo, "unterminated headword"))?;
+            let headword = String::from_utf8_lossy(&idx[pos..end]).to_string();
+            pos = end + 1;
+            if pos + offset_bytes + 4 > idx.len() {
+                return Err(malformed(ifo, "truncated index entry"));
+            }
+            let offset = read_be(&idx[pos..pos + offset_bytes]);
+            let size = read_be(&idx[pos + offset_bytes..pos + offset_bytes + 4]);
+            pos += offset_bytes + 4;
+            self.add_entry(
+                &headword,
+                Entry::Slice {
+                    source,
+                    offset,
+                    size,
+                },
+            );
+        }
+        Ok(())
+    }
+
+    fn load_dictd(&mut self, index: &Path) -> Result<(), LemmatizerError> {
+        let text = fs::read_to_string(index).map_err(LemmatizerError::FileIOFailed)?;
+        let data = read_dict_data(index.with_extension("dict"))?;
+        let name = index
+            .file_stem()
+            .map_or("dictd".to_string(), |s| s.to_string_lossy().to_string());
+        let source = self.add_source(name, data, "m".to_string());
+
+        for line in text.lines() {
+            let fields: Vec<&str> = line.split('\t').collect();
+            if fields.len() < 3 {
+                continue;
+            }
+            // Entries like 00-database-info describe the dictionary itself
+            if fields[0].starts_with("00-database") || fields[0].starts_with("00database") {
+                continue;
+            }
+            let offset = decode_dictd_number(fields[1]).ok_or_else(|| malformed(index, line))?;
+            let size = decode_dictd_number(fields[2]).ok_or_else(|| malformed(index, line))?;
+            self.add_entry(
+                fields[0],
+                Entry::Slice {
+                    source,
+                    offset,
+                    size,
+                },
+            );
+        }
+        Ok(())
+    }
+
+    fn load_wiktextract(&mut self, path: &Path) -> Result<(), LemmatizerError> {
+        let file = File::open(path).map_err(LemmatizerError::FileIOFailed)?;
+        let name = path.file_stem().map_or("Wiktionary".to_string(), |s| {
+            s.to_string_lossy().to_string()
+        });
+        let source = self.add_source(name, Vec::new(), String::new());
+
+        for line in BufReader::new(file).lines() {
+            let line = line.map_err(LemmatizerError::FileIOFailed)?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            let entry: WiktextractEntry =
+                serde_json::from_str(&line).map_err(LemmatizerError::JSONParseFailed)?;
+            let glosses: Vec<String> = entry
+                .senses
+                .iter()
+                .flat_map(|sense| sense.glosses.last())
+                .cloned()
+                .collect();
+            if glosses.is_empty() {
+                continue;
+            }
+            let mut text = String::new();
+            if let Some(pos) = &entry.pos {
+                text.push_str(&format!("({}) ", pos));
+            }
+            text.push_str(&glosses.join("; "));
+            self.add_entry(&entry.word, Entry::Text { source, text });
+        }
+        Ok(())
+    }
+}
+
+#[derive(Deserialize)]
+struct WiktextractEntry {
+    word: String,
+    #[serde(default)]
+    pos: Option<String>,
+    #[serde(default)]
+    senses: Vec<WiktextractSense>,
+}
+
+#[derive(Deserialize)]
+struct WiktextractSense {
+    #[serde(default)]
+    glosses: Vec<String>,
+}
+
+/// Reads `name.dict`, or the gzip-compatible `name.dict.dz` if that's what
+/// the dictionary ships
+fn read_dict_data(path: PathBuf) -> Result<Vec<u8>, LemmatizerError> {
+    if path.exists() {
+        return fs::read(path).map_err(LemmatizerError::FileIOFailed);
+    }
+    let mut dz = path.into_os_string();
+    dz.push(".dz");
+    let file = File::open(dz).map_err(LemmatizerError::FileIOFailed)?;
+    let mut data = Vec::new();
+    GzDecoder::new(file)
+        .read_to_end(&mut data)
+        .map_err(LemmatizerError::FileIOFailed)?;
+    Ok(data)
+}
+
+/// Text of a StarDict entry, its text fields one per line. With a
+/// `sametypesequence` the fields come in that order without their type
+/// characters, and the last runs to the end of the entry; otherwise each
+/// field starts with its type character. Lowercase types are text ended by a
+/// NUL, uppercase ones are binary data after a 4-byte size and are skipped,
+/// as are `r` lists of resource files.
+fn entry_text(bytes: &[u8], types: &str) -> String {
+    let sequence = types.as_bytes();
+    let mut fields = Vec::new();
+    let mut rest = bytes;
+    for i in 0.. {
+        let (kind, last) = match sequence.get(i) {
+            Some(kind) => (*kind, i + 1 == sequence.len()),
+            None if sequence.is_empty() => match rest.split_first() {
+                Some((kind, data)) => {
+                    rest = data;
+                    (*kind, false)
+                }
+                None => break,
+            },
+            None => break,
+        };
+        let data = if last {
+            std::mem::take(&mut rest)
+        } else if kind.is_ascii_uppercase() {
+            let size = match rest.get(..4) {
+                Some(size) => read_be(size).min(rest.len() - 4),
+                None => break,
+            };
+            let (data, next) = rest[4..].split_at(size);
+            rest = next;
+            data
+        } else {
+            let end = rest.iter().position(|b| *b == 0).unwrap_or(rest.len());
+            let (data, next) = rest.split_at(end);
+            rest = next.get(1..).unwrap_or(&[]);
+            data
+        };
+        if kind.is_ascii_lowercase() && kind != b'r' {
+            let data = data.strip_suffix(&[0]).unwrap_or(data);
+            fields.push(field_text(kind, data));
+        }
+    }
+    fields.join("\n")
+}
+
+fn field_text(kind: u8, bytes: &[u8]) -> String {
+    let text = String::from_utf8_lossy(bytes).to_string();
+    match kind {
+        b'h' | b'g' | b'x' => strip_tags(&text),
+        _ => text,
+    }
+}
+
+fn strip_tags(html: &str) -> String {
+    let mut text = String::new();
+    let mut in_tag = false;
+    for c in html.replace("<br>", "\n").replace("<br/>", "\n").chars() {
+        match c {
+            '<' => in_tag = true,
+            '>' => in_tag = false,
+            c if !in_tag => text.push(c),
+            _ => {}
+        }
+    }
+    text.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&amp;", "&")
+}
+
+fn read_be(bytes: &[u8]) -> usize {
+    bytes.iter().fold(0, |n, b| (n << 8) | *b as usize)
+}
+
+/// dictd stores offsets and sizes as base 64 numbers. `None` for a digit
+/// outside the alphabet or a number too large for `usize`.
+fn decode_dictd_number(digits: &str) -> Option<usize> {
+    const ALPHABET: &str = "ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    digits.chars().try_fold(0usize, |n, c| {
+        n.checked_mul(64)?.checked_add(ALPHABET.find(c)?)
+    })
+}
+
+fn malformed(path: &Path, detail: &str) -> LemmatizerError {
+    LemmatizerError::MalformedFile(format!("{}: {}", path.display(), detail))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("vocrab-dictionary-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    /// A StarDict dictionary of `entries`, as headword and entry data
+    fn write_stardict(dir: &Path, types: Option<&str>, entries: &[(&str, &[u8])]) -> PathBuf {
+        let mut info = "StarDict's dict ifo file\nversion=2.4.2\nbookname=Test\n".to_string();
+        if let Some(types) = types {
+            info.push_str(&format!("sametypesequence={}\n", types));
+        }
+        let (mut idx, mut dict) = (Vec::new(), Vec::new());
+        for (headword, data) in entries {
+            idx.extend_from_slice(headword.as_bytes());
+            idx.push(0);
+            idx.extend_from_slice(&(dict.len() as u32).to_be_bytes());
+            idx.extend_from_slice(&(data.len() as u32).to_be_bytes());
+            dict.extend_from_slice(data);
+        }
+        let ifo = dir.join("test.ifo");
+        fs::write(&ifo, info).unwrap();
+        fs::write(dir.join("test.idx"), idx).unwrap();
+        fs::write(dir.join("test.dict"), dict).unwrap();
+        ifo
+    }
+
+    fn texts(definitions: Vec<Definition>) -> Vec<String> {
+        definitions.into_iter().map(|d| d.text).collect()
+    }
+
+    #[test]
+    fn stardict_splits_a_sequence_of_types() {
+        let dir = temp_dir("sequence");
+        let ifo = write_stardict(
+            &dir,
+            Some("tm"),
+            &[
+                ("Gato", b"[\xcb\x88ga.to]\0cat"),
+                ("perro", b"[\xcb\x88pe.ro]\0dog"),
+            ],
+        );
+        let mut dictionary = Dictionary::new();
+        dictionary.load_file(&ifo).unwrap();
+        assert_eq!(texts(dictionary.lookup("gato")), vec!["[ˈga.to]\ncat"]);
+        assert_eq!(
+            dictionary.lookup("PERRO"),
+            vec![Definition {
+                source: "Test".to_string(),
+                text: "[ˈpe.ro]\ndog".to_string(),
+            }]
+        );
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn stardict_reads_typed_fields_and_skips_binary_ones() {
+        let dir = temp_dir("typed");
+        let mut entry = b"mcat\0W".to_vec();
+        entry.extend_from_slice(&3u32.to_be_bytes());
+        entry.extend_from_slice(b"\0\x01\x02h<b>feline</b> &amp; cute\0");
+        let ifo = write_stardict(&dir, None, &[("gato", &entry)]);
+        let mut dictionary = Dictionary::new();
+        dictionary.load_file(&ifo).unwrap();
+        assert_eq!(texts(dictionary.lookup("gato")), vec!["cat\nfeline & cute"]);
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn dictd_decodes_base64_offsets() {
+        let dir = temp_dir("dictd");
+        let index = dir.join("test.index");
+        fs::write(&index, "00-database-info\tA\tB\ngato\tA\tD\nperro\tE\tD\n").unwrap();
+        fs::write(dir.join("test.dict"), "cat\ndog\n").unwrap();
+        let mut dictionary = Dictionary::new();
+        dictionary.load_file(&index).unwrap();
+        assert_eq!(texts(dictionary.lookup("Gato")), vec!["cat"]);
+        assert_eq!(texts(dictionary.lookup("perro")), vec!["dog"]);
+        assert!(dictionary.lookup("00-database-info").is_empty());
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn dictd_numbers_too_large_are_malformed() {
+        assert_eq!(decode_dictd_number("BA"), Some(64));
+        assert_eq!(decode_dictd_number("A!"), None);
+        assert_eq!(decode_dictd_number(&"/".repeat(12)), None);
+
+        let dir = temp_dir("overflow");
+        let index = dir.join("test.index");
+        fs::write(&index, format!("gato\t{}\tD\n", "/".repeat(12))).unwrap();
+        fs::write(dir.join("test.dict"), "cat\n").unwrap();
+        let result = Dictionary::new().load_file(&index).map(|_| ());
+        assert!(matches!(result, Err(LemmatizerError::MalformedFile(_))));
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn lemmas_come_before_forms_and_glosses_join_sources() {
+        let dir = temp_dir("gloss");
+        let ifo = write_stardict(&dir, Some("m"), &[("gato", b"cat,\n  feline")]);
+        let jsonl = dir.join("wiktionary.jsonl");
+        fs::write(
+            &jsonl,
+            concat!(
+                r#"{"word": "gato", "pos": "noun", "senses": [{"glosses": ["animal", "cat"]}, {"glosses": ["jack (car)"]}]}"#,
+                "\n\n",
+                r#"{"word": "Perro", "senses": [{"glosses": ["dog"]}]}"#,
+                "\n",
+                r#"{"word": "sin", "senses": [{"tags": ["empty"]}]}"#,
+                "\n",
+            ),
+        )
+        .unwrap();
+        let mut dictionary = Dictionary::new();
+        dictionary
+            .load_file(&ifo)
+            .unwrap()
+            .load_file(&jsonl)
+            .unwrap();
+
+        let (headword, definitions) = dictionary.lookup_usage("gato", "perro").unwrap();
+        assert_eq!(headword, "gato");
+        assert_eq!(definitions.len(), 2);
+        let (headword, definitions) = dictionary.lookup_usage("perra", "perro").unwrap();
+        assert_eq!(
+            (headword.as_str(), texts(definitions)),
+            ("perro", vec!["dog".to_string()])
+        );
+        assert_eq!(dictionary.lookup_usage("sin", "caballo"), None);
+
+        assert_eq!(
+            dictionary.gloss("gato", "gatos"),
+            "cat, feline | (noun) cat; jack (car)"
+        );
+        assert_eq!(dictionary.gloss("caballo", "caballos"), "");
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
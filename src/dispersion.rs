@@ -0,0 +1,241 @@
+use crate::lemmatizer::{Lemmatizer, WordCount};
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::ops::Range;
+use std::str::FromStr;
+
+/// Runs of text a lemma's spread is measured over
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Segments {
+    Sentences,
+    Paragraphs,
+}
+
+impl FromStr for Segments {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Segments, String> {
+        match s {
+            "sentences" => Ok(Segments::Sentences),
+            "paragraphs" => Ok(Segments::Paragraphs),
+            _ => Err(format!(
+                "unknown segments \"{}\", expected sentences or paragraphs",
+                s
+            )),
+        }
+    }
+}
+
+/// How evenly a lemma is spread through the text. A name or topic word used
+/// many times in one scene and nowhere else is unevenly spread.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Dispersion {
+    /// Juilland's D, from 0 when every use is in one segment to 1 when
+    /// every segment uses the lemma equally often for its length
+    pub juilland_d: f32,
+    /// Gries' deviation of proportions, from 0 when uses follow segment
+    /// lengths exactly to nearly 1 when they're all in one segment
+    pub gries_dp: f32,
+}
+
+impl Dispersion {
+    /// Frequency discounted by unevenness, Juilland's usage coefficient U
+    pub fn adjust(&self, count: usize) -> f32 {
+        count as f32 * self.juilland_d
+    }
+}
+
+impl Lemmatizer {
+    /// Sentence ranges of each segment that has word tokens, with how many
+    fn segments(&self, segments: Segments) -> Vec<(Range<usize>, usize)> {
+        let ranges: Vec<Range<usize>> = match segments {
+            Segments::Sentences => (0..self.sentence_count()).map(|i| i..i + 1).collect(),
+            Segments::Paragraphs => self
+                .documents()
+                .iter()
+                .flat_map(|document| document.paragraphs.iter().cloned())
+                .collect(),
+        };
+        ranges
+            .into_iter()
+            .map(|range| {
+                let size = self
+                    .counted_sentences(range.clone())
+                    .flat_map(|(_, sentence)| sentence)
+                    .filter(|t| !t.is_punct())
+                    .count();
+                (range, size)
+            })
+            .filter(|(_, size)| *size > 0)
+            .collect()
+    }
+
+    /// Dispersion of every lemma over `segments`
+    pub fn get_dispersions(&self, segments: Segments) -> HashMap<String, Dispersion> {
+        let segments = SegmentIndex::new(self, segments);
+        self.lemma_map
+            .keys()
+            .map(|lemma| (lemma.clone(), segments.dispersion(self, lemma)))
+            .collect()
+    }
+
+    pub fn get_dispersion(&self, lemma: &str, segments: Segments) -> Option<Dispersion> {
+        match self.lemma_map.contains_key(lemma) {
+            true => Some(SegmentIndex::new(self, segments).dispersion(self, lemma)),
+            false => None,
+        }
+    }
+
+    /// Lemmas ranked by frequency adjusted for dispersion, so that bursty
+    /// lemmas fall below evenly used ones. Ties keep the order of
+    /// `get_lemmas`.
+    pub fn get_adjusted_lemmas(&self, segments: Segments) -> Vec<String> {
+        let dispersions = self.get_dispersions(segments);
+        let adjusted = |lemma: &String| {
+            dispersions
+                .get(lemma)
+                .map_or(0.0, |d| d.adjust(self.get_lemma_count(lemma)))
+        };
+        let mut lemmas = self.get_lemmas();
+        lemmas.sort_by(|a, b| {
+            adjusted(b)
+                .partial_cmp(&adjusted(a))
+                .unwrap_or(Ordering::Equal)
+        });
+        lemmas
+    }
+}
+
+/// Segments of the library, and which one each sentence is in
+struct SegmentIndex {
+    /// Sentence ranges of each segment, with its number of word tokens
+    segments: Vec<(Range<usize>, usize)>,
+    segment_of: Vec<usize>,
+    /// Word tokens in all segments
+    total: usize,
+}
+
+impl SegmentIndex {
+    fn new(lemmatizer: &Lemmatizer, segments: Segments) -> SegmentIndex {
+        let segments = lemmatizer.segments(segments);
+        let mut segment_of = vec![0; lemmatizer.sentence_count()];
+        for (i, (range, _)) in segments.iter().enumerate() {
+            for sentence_i in range.clone() {
+                segment_of[sentence_i] = i;
+            }
+        }
+        let total = segments.iter().map(|(_, size)| size).sum();
+        SegmentIndex {
+            segments,
+            segment_of,
+            total,
+        }
+    }
+
+    /// Dispersion of a lemma of the library
+    fn dispersion(&self, lemmatizer: &Lemmatizer, lemma: &str) -> Dispersion {
+        let forms = &lemmatizer.lemma_map[lemma];
+        // Only the segments that use the lemma, as all others add nothing
+        // but their share of the text
+        let mut counts: HashMap<usize, usize> = HashMap::new();
+        for (sentence_i, _) in forms.values().flatten() {
+            *counts.entry(self.segment_of[*sentence_i]).or_insert(0) += 1;
+        }
+        dispersion(&counts, &self.segments, forms.word_count(), self.total)
+    }
+}
+
+/// Dispersion of a lemma used `count` times, `counts[i]` of them in
+/// segment `i`, in a text of `total` word tokens
+fn dispersion(
+    counts: &HashMap<usize, usize>,
+    segments: &[(Range<usize>, usize)],
+    count: usize,
+    total: usize,
+) -> Dispersion {
+    let n = segments.len() as f64;
+    if n <= 1.0 {
+        return Dispersion {
+            juilland_d: 1.0,
+            gries_dp: 0.0,
+        };
+    }
+
+    // Juilland's D on uses per token of each segment, as segments differ
+    // in length
+    let mut sum = 0.0;
+    let mut sum_squares = 0.0;
+    // Gries' DP, starting from every segment's share of the text and
+    // correcting the segments that use the lemma
+    let mut deviation = 1.0;
+    for (segment_i, segment_count) in counts {
+        let size = segments[*segment_i].1 as f64;
+        let rate = *segment_count as f64 / size;
+        sum += rate;
+        sum_squares += rate * rate;
+
+        let expected = size / total as f64;
+        let observed = *segment_count as f64 / count as f64;
+        deviation += (observed - expected).abs() - expected;
+    }
+    let mean = sum / n;
+    let variance = (sum_squares / n - mean * mean).max(0.0);
+    let juilland_d = match mean {
+        mean if mean > 0.0 => 1.0 - variance.sqrt() / mean / (n - 1.0).sqrt(),
+        _ => 0.0,
+    };
+    Dispersion {
+        juilland_d: juilland_d.clamp(0.0, 1.0) as f32,
+        gries_dp: (deviation / 2.0).clamp(0.0, 1.0) as f32,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Four sentences of two words, "el" starting each of them
+    fn lemmatizer() -> Lemmatizer {
+        let sentences: Vec<String> = ["gato", "perro", "pan", "sol"]
+            .iter()
+            .map(|noun| {
+                format!(
+                    r#"[{{"text": "el", "lemma": "el", "pos": "DET"}},
+                        {{"text": "{0}", "lemma": "{0}", "pos": "NOUN"}},
+                        {{"text": ".", "lemma": ".", "pos": "PUNCT"}}]"#,
+                    noun
+                )
+            })
+            .collect();
+        let json = format!(r#"{{"sentences": [{}]}}"#, sentences.join(","));
+        let mut lemmatizer = Lemmatizer::new();
+        lemmatizer.load_json("el", &json).unwrap();
+        lemmatizer
+    }
+
+    #[test]
+    fn evenly_spread_lemmas_have_full_dispersion() {
+        let dispersion = lemmatizer()
+            .get_dispersion("el", Segments::Sentences)
+            .unwrap();
+        assert!((dispersion.juilland_d - 1.0).abs() < 1e-6);
+        assert!(dispersion.gries_dp.abs() < 1e-6);
+        assert!((dispersion.adjust(4) - 4.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn lemmas_in_one_segment_deviate_by_the_rest_of_the_text() {
+        let lemmatizer = lemmatizer();
+        let dispersion = lemmatizer
+            .get_dispersion("gato", Segments::Sentences)
+            .unwrap();
+        assert!(dispersion.juilland_d.abs() < 1e-6);
+        // The segment holds a quarter of the text, so DP is 1 - 1/4
+        assert!((dispersion.gries_dp - 0.75).abs() < 1e-6);
+        assert_eq!(
+            lemmatizer.get_dispersion("caballo", Segments::Sentences),
+            None
+        );
+        assert_eq!(lemmatizer.get_adjusted_lemmas(Segments::Sentences)[0], "el");
+    }
+}
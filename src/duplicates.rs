@@ -0,0 +1,279 @@
+use crate::lemmatizer::{Lemmatizer, Token};
+use std::collections::{HashMap, HashSet};
+
+/// Sentences with fewer words, like "Ok?", are repeated on purpose too
+/// often to count as duplicates
+pub const MIN_DUPLICATE_WORDS: usize = 3;
+/// Share of word pairs two sentences must have in common to be near
+/// duplicates, such as a sentence the speaker restarted
+pub const NEAR_DUPLICATE_SIMILARITY: f32 = 0.8;
+
+/// MinHash signature length, split into bands that must all match for two
+/// sentences to be compared. With 8 bands of 2, sentences at the similarity
+/// threshold are compared 99.9% of the time.
+const BANDS: usize = 8;
+const ROWS: usize = 2;
+
+/// An earlier sentence that a sentence repeats
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Duplicate {
+    /// First occurrence of the sentence in the library
+    pub original: usize,
+    /// Whether the words are the same, rather than nearly the same
+    pub exact: bool,
+}
+
+/// Fingerprints of every sentence loaded so far, to find repeats as new
+/// documents come in
+#[derive(Default)]
+pub(crate) struct DuplicateIndex {
+    /// The original of each sentence that repeats another
+    duplicates: HashMap<usize, Duplicate>,
+    /// First sentence with each sequence of words
+    exact: HashMap<Vec<String>, usize>,
+    /// Word pairs of each sentence, for near-duplicate candidates to be
+    /// checked against
+    shingles: HashMap<usize, HashSet<u64>>,
+    /// Sentences by MinHash band
+    buckets: HashMap<(usize, Vec<u64>), Vec<usize>>,
+}
+
+impl DuplicateIndex {
+    /// Fingerprints a sentence, which must come after every sentence added
+    /// before it
+    pub(crate) fn add(&mut self, sentence_i: usize, sentence: &[Token]) {
+        let words: Vec<String> = sentence
+            .iter()
+            .filter(|t| !t.is_punct())
+            .map(|t| t.text.to_lowercase())
+            .collect();
+        if words.len() < MIN_DUPLICATE_WORDS {
+            return;
+        }
+
+        if let Some(original) = self.exact.get(&words) {
+            let original = self.original_of(*original);
+            self.duplicates.insert(
+                sentence_i,
+                Duplicate {
+                    original,
+                    exact: true,
+                },
+            );
+            return;
+        }
+
+        let shingles = shingles(&words);
+        let signature = minhash(&shingles);
+        let mut candidates: Vec<usize> = signature
+            .chunks(ROWS)
+            .enumerate()
+            .filter_map(|(band, rows)| self.buckets.get(&(band, rows.to_vec())))
+            .flatten()
+            .copied()
+            .collect();
+        candidates.sort_unstable();
+        candidates.dedup();
+        let original = candidates
+            .into_iter()
+            .find(|i| similarity(&shingles, &self.shingles[i]) >= NEAR_DUPLICATE_SIMILARITY);
+        if let Some(original) = original {
+            let original = self.original_of(original);
+            self.duplicates.insert(
+                sentence_i,
+                Duplicate {
+                    original,
+                    exact: false,
+                },
+            );
+        }
+
+        self.exact.insert(words, sentence_i);
+        for (band, rows) in signature.chunks(ROWS).enumerate() {
+            self.buckets
+                .entry((band, rows.to_vec()))
+                .or_default()
+                .push(sentence_i);
+        }
+        self.shingles.insert(sentence_i, shingles);
+    }
+
+    pub(crate) fn get(&self, sentence_i: usize) -> Option<Duplicate> {
+        self.duplicates.get(&sentence_i).copied()
+    }
+
+    /// The first occurrence of whatever a sentence repeats
+    fn original_of(&self, sentence_i: usize) -> usize {
+        self.get(sentence_i)
+            .map_or(sentence_i, |duplicate| duplicate.original)
+    }
+}
+
+/// Hashes of each pair of neighbouring words, with the start and end of the
+/// sentence as words, so sentences that differ only at one end still share
+/// most pairs
+fn shingles(words: &[String]) -> HashSet<u64> {
+    let mut padded = vec![""];
+    padded.extend(words.iter().map(String::as_str));
+    padded.push("");
+    padded
+        .windows(2)
+        .map(|pair| fnv1a(format!("{}\u{0}{}", pair[0], pair[1]).as_bytes()))
+        .collect()
+}
+
+fn similarity(a: &HashSet<u64>, b: &HashSet<u64>) -> f32 {
+    let shared = a.intersection(b).count();
+    shared as f32 / (a.len() + b.len() - shared) as f32
+}
+
+fn minhash(shingles: &HashSet<u64>) -> Vec<u64> {
+    (0..BANDS * ROWS)
+        .map(|seed| {
+            shingles
+                .iter()
+                .map(|shingle| splitmix64(shingle ^ (seed as u64).wrapping_mul(0x9E37_79B9)))
+                .min()
+                .unwrap_or(0)
+        })
+        .collect()
+}
+
+/// Hashes are computed here rather than with `DefaultHasher` so fingerprints
+/// stay the same across Rust versions
+fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+        (hash ^ *byte as u64).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+fn splitmix64(x: u64) -> u64 {
+    let mut z = x.wrapping_add(0x9E37_79B9_7F4A_7C15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^ (z >> 31)
+}
+
+impl Lemmatizer {
+    /// The earlier sentence a sentence repeats exactly or nearly, if any,
+    /// whether in the same document or another
+    pub fn duplicate_of(&self, sentence_i: usize) -> Option<Duplicate> {
+        self.duplicates.get(sentence_i)
+    }
+
+    /// Every sentence that repeats an earlier one, in text order
+    pub fn get_duplicates(&self) -> Vec<(usize, Duplicate)> {
+        let mut duplicates: Vec<(usize, Duplicate)> = self
+            .duplicates
+            .duplicates
+            .iter()
+            .map(|(sentence_i, duplicate)| (*sentence_i, *duplicate))
+            .collect();
+        duplicates.sort_unstable_by_key(|(sentence_i, _)| *sentence_i);
+        duplicates
+    }
+
+    /// Leaves sentences that repeat earlier ones out of lemma counts and
+    /// usages, or counts them again. The first occurrence is always kept.
+    pub fn skip_duplicates(&mut self, skip: bool) -> &mut Lemmatizer {
+        if self.skip_duplicates != skip {
+            self.skip_duplicates = skip;
+            self.update_lemma_map();
+        }
+        self
+    }
+
+    /// Whether a sentence is left out of counts as a duplicate
+    pub(crate) fn is_skipped(&self, sentence_i: usize) -> bool {
+        self.skip_duplicates && self.duplicates.get(sentence_i).is_some()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SENTENCE: &str = "cuando llegamos a la plaza de la ciudad aquella noche de verano \
+                            ya no quedaba nadie que nos pudiera contar lo que había pasado";
+
+    /// A document of sentences written as words separated by spaces
+    fn lemmatizer(sentences: &[&str]) -> Lemmatizer {
+        let sentences: Vec<String> = sentences
+            .iter()
+            .map(|sentence| {
+                let mut tokens: Vec<String> = sentence
+                    .split_whitespace()
+                    .map(|word| {
+                        format!(
+                            r#"{{"text": "{}", "lemma": "{}", "pos": "X"}}"#,
+                            word,
+                            word.to_lowercase()
+                        )
+                    })
+                    .collect();
+                tokens.push(r#"{"text": ".", "lemma": ".", "pos": "PUNCT"}"#.to_string());
+                format!("[{}]", tokens.join(","))
+            })
+            .collect();
+        let json = format!(r#"{{"sentences": [{}]}}"#, sentences.join(","));
+        let mut lemmatizer = Lemmatizer::new();
+        lemmatizer.load_json("plaza", &json).unwrap();
+        lemmatizer
+    }
+
+    #[test]
+    fn identical_sentences_are_exact_duplicates() {
+        let shouted = SENTENCE.to_uppercase();
+        let lemmatizer = lemmatizer(&[SENTENCE, &shouted]);
+        assert_eq!(
+            lemmatizer.duplicate_of(1),
+            Some(Duplicate {
+                original: 0,
+                exact: true
+            })
+        );
+        assert_eq!(lemmatizer.duplicate_of(0), None);
+    }
+
+    #[test]
+    fn one_word_edits_are_near_duplicates() {
+        let edited = SENTENCE.replace("verano", "invierno");
+        let words: Vec<String> = SENTENCE.split(' ').map(str::to_string).collect();
+        let edited_words: Vec<String> = edited.split(' ').map(str::to_string).collect();
+        assert!(
+            similarity(&shingles(&words), &shingles(&edited_words)) >= NEAR_DUPLICATE_SIMILARITY
+        );
+
+        let lemmatizer = lemmatizer(&[SENTENCE, "otra frase sin nada que ver", &edited]);
+        assert_eq!(
+            lemmatizer.get_duplicates(),
+            vec![(
+                2,
+                Duplicate {
+                    original: 0,
+                    exact: false
+                }
+            )]
+        );
+    }
+
+    #[test]
+    fn unrelated_and_short_sentences_are_kept() {
+        let lemmatizer = lemmatizer(&[
+            SENTENCE,
+            "el perro del vecino ladra todas las mañanas antes de que salga el sol",
+            "Sí claro",
+            "Sí claro",
+        ]);
+        assert_eq!(lemmatizer.get_duplicates(), vec![]);
+    }
+
+    #[test]
+    fn skipped_duplicates_leave_the_counts() {
+        let mut lemmatizer = lemmatizer(&[SENTENCE, SENTENCE]);
+        assert_eq!(lemmatizer.get_lemma_count("plaza"), 2);
+        lemmatizer.skip_duplicates(true);
+        assert_eq!(lemmatizer.get_lemma_count("plaza"), 1);
+        assert!(lemmatizer.is_skipped(1) && !lemmatizer.is_skipped(0));
+    }
+}
@@ -0,0 +1,457 @@
+use crate::analyzer::{token_offsets, Analyzer};
+use crate::html::{attribute, decode, decode_entities, parse_body, strip_tags, tags, Tag};
+use crate::lemmatizer::{DocumentFile, LemmatizerError, SectionFile};
+use crate::zip::ZipArchive;
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+/// Text of a chapter, split into paragraphs
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Chapter {
+    /// From the book's table of contents
+    pub title: Option<String>,
+    pub paragraphs: Vec<String>,
+}
+
+/// A book read from an EPUB file, chapter by chapter
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Book {
+    pub title: Option<String>,
+    pub chapters: Vec<Chapter>,
+}
+
+fn malformed(reason: &str) -> LemmatizerError {
+    LemmatizerError::MalformedFile(format!("EPUB: {}", reason))
+}
+
+/// Reads the chapters of an EPUB 2 or 3 book in reading order. A chapter
+/// starts at each file of the spine listed in the table of contents, and
+/// runs on through the files after it that aren't, as books split long
+/// chapters into several files.
+pub fn read_epub<P: AsRef<Path>>(path: P) -> Result<Book, LemmatizerError> {
+    let data = fs::read(path).map_err(LemmatizerError::FileIOFailed)?;
+    let archive = ZipArchive::new(data)?;
+    let read_text = |name: &str| archive.read(name).map(decode);
+
+    let container = read_text("META-INF/container.xml")?;
+    let package_path = tags(&container)
+        .iter()
+        .find(|tag| tag.name == "rootfile")
+        .and_then(|tag| attribute(tag.attributes, "full-path"))
+        .ok_or_else(|| malformed("no package file in META-INF/container.xml"))?;
+    let package = read_text(&package_path)?;
+    let package_tags = tags(&package);
+
+    // Manifest items by id, as (path, properties)
+    let mut items: HashMap<String, (String, String)> = HashMap::new();
+    for tag in package_tags
+        .iter()
+        .filter(|tag| tag.name == "item" && !tag.closing)
+    {
+        if let (Some(id), Some(href)) = (
+            attribute(tag.attributes, "id"),
+            attribute(tag.attributes, "href"),
+        ) {
+            let properties = attribute(tag.attributes, "properties").unwrap_or_default();
+            items.insert(id, (resolve(&package_path, &href), properties));
+        }
+    }
+    let spine: Vec<&String> = package_tags
+        .iter()
+        .filter(|tag| tag.name == "itemref" && !tag.closing)
+        .filter_map(|tag| attribute(tag.attributes, "idref"))
+        .filter_map(|id| items.get(&id).map(|(path, _)| path))
+        .collect();
+    if spine.is_empty() {
+        return Err(malformed("empty spine"));
+    }
+
+    // The EPUB 3 navigation document, or else the EPUB 2 NCX file
+    let nav = items
+        .values()
+        .find(|(_, properties)| properties.split_whitespace().any(|p| p == "nav"));
+    let ncx = package_tags
+        .iter()
+        .find(|tag| tag.name == "spine")
+        .and_then(|tag| attribute(tag.attributes, "toc"))
+        .and_then(|id| items.get(&id));
+    let contents = match (nav, ncx) {
+        (Some((path, _)), _) => nav_contents(path, &read_text(path)?),
+        (None, Some((path, _))) => ncx_contents(path, &read_text(path)?),
+        (None, None) => HashMap::new(),
+    };
+
+    let mut chapters: Vec<Chapter> = Vec::new();
+    for path in spine {
+        let page = parse_body(&read_text(path)?);
+        match (contents.get(path.as_str()), chapters.last_mut()) {
+            (None, Some(chapter)) => chapter.paragraphs.extend(page.paragraphs),
+            (title, _) => chapters.push(Chapter {
+                title: title.cloned().or(page.title),
+                paragraphs: page.paragraphs,
+            }),
+        }
+    }
+    Ok(Book {
+        title: element_text(&package, &package_tags, "title"),
+        chapters,
+    })
+}
+
+/// Text of the first element named `name`
+fn element_text(xml: &str, tags: &[Tag], name: &str) -> Option<String> {
+    let i = tags
+        .iter()
+        .position(|tag| tag.name == name && !tag.closing)?;
+    let end = tags.get(i + 1).map_or(xml.len(), |tag| tag.start);
+    Some(strip_tags(&xml[tags[i].end..end])).filter(|text| !text.is_empty())
+}
+
+/// Chapter titles by file from the `<nav epub:type="toc">` of an EPUB 3
+/// navigation document. The first link to a file names it.
+fn nav_contents(path: &str, xml: &str) -> HashMap<String, String> {
+    let tags = tags(xml);
+    let mut contents = HashMap::new();
+    let mut in_toc = false;
+    for (i, tag) in tags.iter().enumerate() {
+        match (tag.name.as_str(), tag.closing) {
+            ("nav", false) => {
+                in_toc = attribute(tag.attributes, "epub:type")
+                    .is_some_and(|kind| kind.split_whitespace().any(|k| k == "toc"))
+            }
+            ("nav", true) => in_toc = false,
+            ("a", false) if in_toc => {
+                let close = tags[i..].iter().find(|t| t.name == "a" && t.closing);
+                let title = strip_tags(&xml[tag.end..close.map_or(xml.len(), |t| t.start)]);
+                if let Some(href) = attribute(tag.attributes, "href") {
+                    contents.entry(resolve(path, &href)).or_insert(title);
+                }
+            }
+            _ => {}
+        }
+    }
+    contents
+}
+
+/// Chapter titles by file from the `navPoint`s of an EPUB 2 NCX file, each
+/// a `<text>` label followed by a `<content src>`
+fn ncx_contents(path: &str, xml: &str) -> HashMap<String, String> {
+    let tags = tags(xml);
+    let mut contents = HashMap::new();
+    let mut label = None;
+    for (i, tag) in tags.iter().enumerate() {
+        match (tag.name.as_str(), tag.closing) {
+            ("text", false) => {
+                let end = tags.get(i + 1).map_or(xml.len(), |t| t.start);
+                label = Some(strip_tags(&xml[tag.end..end]));
+            }
+            ("content", false) => {
+                if let (Some(title), Some(src)) = (label.take(), attribute(tag.attributes, "src")) {
+                    contents.entry(resolve(path, &src)).or_insert(title);
+                }
+            }
+            _ => {}
+        }
+    }
+    contents
+}
+
+/// Path within the archive of a link from the file at `from`, without its
+/// fragment
+fn resolve(from: &str, href: &str) -> String {
+    let href = href.split('#').next().unwrap_or("");
+    let href = percent_decode(href);
+    let mut parts: Vec<&str> = from.split('/').collect();
+    // The file itself
+    parts.pop();
+    for part in href.split('/') {
+        match part {
+            "" | "." => {}
+            ".." => {
+                parts.pop();
+            }
+            part => parts.push(part),
+        }
+    }
+    parts.join("/")
+}
+
+/// Undoes escapes like `%20` in a link
+fn percent_decode(href: &str) -> String {
+    let bytes = href.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let hex = href
+            .get(i + 1..i + 3)
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match (bytes[i], hex) {
+            (b'%', Some(byte)) => {
+                decoded.push(byte);
+                i += 3;
+            }
+            (byte, _) => {
+                decoded.push(byte);
+                i += 1;
+            }
+        }
+    }
+    decode_entities(&String::from_utf8_lossy(&decoded))
+}
+
+/// Analyzes the text of a book into document JSON with a section for each
+/// chapter
+pub fn analyze_book<A: Analyzer>(
+    book: &Book,
+    analyzer: &A,
+    language: &str,
+) -> Result<String, LemmatizerError> {
+    // Chapters are analyzed together, as the analyzer may take a while to
+    // start, and told apart again by where their sentences are in the text
+    let mut text = String::new();
+    let mut chapter_starts = Vec::with_capacity(book.chapters.len());
+    for chapter in &book.chapters {
+        if !text.is_empty() && !chapter.paragraphs.is_empty() {
+            text.push_str("\n\n");
+        }
+        chapter_starts.push(text.len());
+        text.push_str(&chapter.paragraphs.join("\n\n"));
+    }
+    let json = analyzer.analyze(&text, language)?;
+    let mut document: DocumentFile =
+        serde_json::from_str(&json).map_err(LemmatizerError::JSONParseFailed)?;
+
+    let mut chapter_i = 0;
+    let mut sections = Vec::new();
+    for (sentence_i, offsets) in token_offsets(&text, &document.sentences)
+        .into_iter()
+        .enumerate()
+    {
+        // Sentences with no token found stay in the chapter before them
+        if let Some(offset) = offsets.into_iter().flatten().next() {
+            chapter_i = chapter_starts.partition_point(|start| *start <= offset) - 1;
+        }
+        if sections.last().is_none_or(|(i, _)| *i != chapter_i) {
+            sections.push((chapter_i, sentence_i));
+        }
+    }
+    document.sections = sections
+        .into_iter()
+        .map(|(chapter_i, start)| SectionFile {
+            title: book.chapters[chapter_i].title.clone(),
+            start,
+        })
+        .collect();
+    document.title = book.title.clone();
+    serde_json::to_string(&document).map_err(LemmatizerError::JSONSerializeFailed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analyzer::tests::SplitAnalyzer;
+    use crate::zip::write_zip;
+    use std::path::PathBuf;
+
+    const CONTAINER: &str = r#"<?xml version="1.0"?>
+<container version="1.0" xmlns="urn:oasis:names:tc:opendocument:xmlns:container">
+  <rootfiles>
+    <rootfile full-path="OEBPS/content.opf" media-type="application/oebps-package+xml"/>
+  </rootfiles>
+</container>"#;
+
+    /// Listed in the manifest out of reading order, with the second
+    /// chapter split over two files
+    const PACKAGE: &str = r#"<?xml version="1.0"?>
+<package xmlns="http://www.idpf.org/2007/opf" version="3.0">
+  <metadata xmlns:dc="http://purl.org/dc/elements/1.1/">
+    <dc:title>Cuentos &amp; leyendas</dc:title>
+  </metadata>
+  <manifest>
+    <item id="c2b" href="text/dos%20b.xhtml" media-type="application/xhtml+xml"/>
+    <item id="nav" href="nav.xhtml" properties="nav" media-type="application/xhtml+xml"/>
+    <item id="c2" href="text/dos.xhtml" media-type="application/xhtml+xml"/>
+    <item id="c1" href="text/uno.xhtml" media-type="application/xhtml+xml"/>
+  </manifest>
+  <spine>
+    <itemref idref="c1"/>
+    <itemref idref="c2"/>
+    <itemref idref="c2b"/>
+  </spine>
+</package>"#;
+
+    const NAV: &str = r#"<html xmlns:epub="http://www.idpf.org/2007/ops"><body>
+<nav epub:type="landmarks"><ol><li><a href="text/dos.xhtml">Empieza aquí</a></li></ol></nav>
+<nav epub:type="toc"><ol>
+  <li><a href="text/uno.xhtml">I. El <i>gato</i></a></li>
+  <li><a href="text/dos.xhtml#inicio">II. El perro</a></li>
+</ol></nav>
+</body></html>"#;
+
+    fn page(title: &str, body: &str) -> String {
+        format!(
+            "<html><head><title>{}</title></head><body>{}</body></html>",
+            title, body
+        )
+    }
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("vocrab-epub-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    /// The path of an EPUB of `files` written to `dir`
+    fn write_epub(dir: &Path, files: &[(&str, &str)]) -> PathBuf {
+        let files: Vec<(&str, &[u8])> = files
+            .iter()
+            .map(|(name, contents)| (*name, contents.as_bytes()))
+            .collect();
+        let path = dir.join("libro.epub");
+        fs::write(&path, write_zip(&files).unwrap()).unwrap();
+        path
+    }
+
+    fn chapters() -> Vec<(&'static str, String)> {
+        vec![
+            (
+                "OEBPS/text/uno.xhtml",
+                page("Uno", "<p>El gato come.</p><p>El gato duerme.</p>"),
+            ),
+            (
+                "OEBPS/text/dos.xhtml",
+                page("Dos", "<h1 id=\"inicio\">II</h1><p>El perro ladra.</p>"),
+            ),
+            (
+                "OEBPS/text/dos b.xhtml",
+                page("Dos b", "<p>El perro corre. El perro salta.</p>"),
+            ),
+        ]
+    }
+
+    #[test]
+    fn chapters_follow_the_spine() {
+        let dir = temp_dir("spine");
+        let chapters = chapters();
+        let mut files = vec![
+            ("mimetype", "application/epub+zip"),
+            ("META-INF/container.xml", CONTAINER),
+            ("OEBPS/content.opf", PACKAGE),
+            ("OEBPS/nav.xhtml", NAV),
+        ];
+        files.extend(chapters.iter().map(|(name, page)| (*name, page.as_str())));
+        let book = read_epub(write_epub(&dir, &files)).unwrap();
+        assert_eq!(
+            book,
+            Book {
+                title: Some("Cuentos & leyendas".to_string()),
+                chapters: vec![
+                    Chapter {
+                        title: Some("I. El gato".to_string()),
+                        paragraphs: vec![
+                            "El gato come.".to_string(),
+                            "El gato duerme.".to_string()
+                        ],
+                    },
+                    Chapter {
+                        title: Some("II. El perro".to_string()),
+                        paragraphs: vec![
+                            "II".to_string(),
+                            "El perro ladra.".to_string(),
+                            "El perro corre. El perro salta.".to_string(),
+                        ],
+                    },
+                ],
+            }
+        );
+
+        let json = analyze_book(&book, &SplitAnalyzer, "es").unwrap();
+        let document: DocumentFile = serde_json::from_str(&json).unwrap();
+        assert_eq!(document.title.as_deref(), Some("Cuentos & leyendas"));
+        assert_eq!(document.sentences.len(), 5);
+        let sections: Vec<(Option<&str>, usize)> = document
+            .sections
+            .iter()
+            .map(|section| (section.title.as_deref(), section.start))
+            .collect();
+        assert_eq!(
+            sections,
+            vec![(Some("I. El gato"), 0), (Some("II. El perro"), 2)]
+        );
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn ncx_files_title_chapters() {
+        let dir = temp_dir("ncx");
+        let package = PACKAGE
+            .replace(r#"properties="nav" "#, "")
+            .replace(r#"href="nav.xhtml""#, r#"href="toc.ncx""#)
+            .replace("<spine>", r#"<spine toc="nav">"#);
+        let ncx = r#"<ncx><navMap>
+<navPoint id="p1"><navLabel><text>Primero</text></navLabel><content src="text/uno.xhtml"/></navPoint>
+<navPoint id="p2"><navLabel><text>Segundo</text></navLabel><content src="text/dos%20b.xhtml"/></navPoint>
+</navMap></ncx>"#;
+        let chapters = chapters();
+        let mut files = vec![
+            ("META-INF/container.xml", CONTAINER),
+            ("OEBPS/content.opf", package.as_str()),
+            ("OEBPS/toc.ncx", ncx),
+        ];
+        files.extend(chapters.iter().map(|(name, page)| (*name, page.as_str())));
+        let book = read_epub(write_epub(&dir, &files)).unwrap();
+        let titles: Vec<Option<&str>> = book
+            .chapters
+            .iter()
+            .map(|chapter| chapter.title.as_deref())
+            .collect();
+        assert_eq!(titles, vec![Some("Primero"), Some("Segundo")]);
+        assert_eq!(book.chapters[0].paragraphs.len(), 4);
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn malformed_books_fail_cleanly() {
+        let dir = temp_dir("malformed");
+        let chapters = chapters();
+        let (name, page) = &chapters[0];
+
+        let no_container = write_epub(&dir, &[("OEBPS/content.opf", PACKAGE), (name, page)]);
+        assert!(matches!(
+            read_epub(no_container),
+            Err(LemmatizerError::MalformedFile(_))
+        ));
+
+        let no_spine = PACKAGE.replace("<itemref", "<notitemref");
+        let files = [
+            ("META-INF/container.xml", CONTAINER),
+            ("OEBPS/content.opf", no_spine.as_str()),
+        ];
+        assert!(matches!(
+            read_epub(write_epub(&dir, &files)),
+            Err(LemmatizerError::MalformedFile(_))
+        ));
+
+        // Cut off partway through a chapter
+        let mut files = vec![
+            ("META-INF/container.xml", CONTAINER),
+            ("OEBPS/content.opf", PACKAGE),
+        ];
+        files.extend(chapters.iter().map(|(name, page)| (*name, page.as_str())));
+        let path = write_epub(&dir, &files);
+        let data = fs::read(&path).unwrap();
+        fs::write(&path, &data[..data.len() / 2]).unwrap();
+        assert!(matches!(
+            read_epub(&path),
+            Err(LemmatizerError::MalformedFile(_))
+        ));
+
+        assert!(matches!(
+            read_epub(dir.join("missing.epub")),
+            Err(LemmatizerError::FileIOFailed(_))
+        ));
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
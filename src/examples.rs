@@ -0,0 +1,108 @@
+use crate::coverage::is_known;
+use crate::known::KnownWords;
+use crate::lemmatizer::{Lemmatizer, Token};
+use std::cmp::Ordering;
+use std::collections::HashSet;
+use std::ops::RangeInclusive;
+
+/// Sentence lengths, in word tokens, that make the easiest examples
+pub const IDEAL_LENGTH: RangeInclusive<usize> = 6..=15;
+
+/// Characters that end a complete sentence
+const SENTENCE_END: [char; 8] = ['.', '!', '?', '…', '。', '！', '？', '‼'];
+/// Characters that may follow the end of a sentence, like closing quotes
+const CLOSING: [char; 9] = ['"', '\'', '»', '”', '’', ')', ']', '」', '』'];
+
+fn is_closing(token: &Token) -> bool {
+    token.text.chars().all(|c| CLOSING.contains(&c))
+}
+
+impl Lemmatizer {
+    /// How good the sentence around a token is as an example of its lemma:
+    /// 0 at best, lower for sentences too short or too long to read easily,
+    /// for each unknown word besides the lemma, and for fragments that
+    /// don't start with a capital or end like a sentence
+    pub fn score_example(&self, sentence_i: usize, token_i: usize, known: &KnownWords) -> f32 {
+        let sentence = &self.tokens[sentence_i];
+        let lemma = sentence[token_i].lemma_key();
+        let words: Vec<&Token> = sentence.iter().filter(|t| !t.is_punct()).collect();
+
+        let length = words.len();
+        let mut score = match length {
+            n if n < *IDEAL_LENGTH.start() => -0.5 * (IDEAL_LENGTH.start() - n) as f32,
+            n if n > *IDEAL_LENGTH.end() => -0.25 * (n - IDEAL_LENGTH.end()) as f32,
+            _ => 0.0,
+        };
+
+        let unknown = words
+            .iter()
+            .filter(|t| t.lemma_key() != lemma && !is_known(t, known))
+            .count();
+        score -= unknown as f32;
+
+        // Scripts without case never start with a lowercase letter
+        let capitalized = words
+            .first()
+            .and_then(|t| t.text.chars().next())
+            .is_some_and(|c| !c.is_lowercase());
+        let ended = sentence
+            .iter()
+            .rev()
+            .find(|t| !is_closing(t))
+            .is_some_and(|t| t.is_punct() && t.text.chars().any(|c| SENTENCE_END.contains(&c)));
+        if !capitalized || !ended {
+            score -= 2.0;
+        }
+        score
+    }
+
+    /// Orders usages, as (sentence, token), best example first. Equally good
+    /// ones keep their order, and a sentence already ranked is left out
+    /// when it comes up again, so no sentence is shown twice.
+    pub fn rank_examples(
+        &self,
+        positions: &[(usize, usize)],
+        known: &KnownWords,
+    ) -> Vec<(usize, usize)> {
+        let mut scored: Vec<(f32, (usize, usize))> = positions
+            .iter()
+            .map(|&(sentence_i, token_i)| {
+                let score = self.score_example(sentence_i, token_i, known);
+                (score, (sentence_i, token_i))
+            })
+            .collect();
+        scored.sort_by(|a, b| b.0.partial_cmp(&a.0).unwrap_or(Ordering::Equal));
+
+        let mut seen = HashSet::new();
+        scored
+            .into_iter()
+            .map(|(_, usage)| usage)
+            .filter(|(sentence_i, _)| seen.insert(self.sentence_key(*sentence_i)))
+            .collect()
+    }
+
+    /// Usages of a form of a lemma, or of every form when `form` is `None`,
+    /// as (sentence, token), best example first
+    pub fn get_ranked_usages(
+        &self,
+        lemma: &str,
+        form: Option<&str>,
+        known: &KnownWords,
+    ) -> Vec<(usize, usize)> {
+        let mut positions: Vec<(usize, usize)> = match (self.lemma_map.get(lemma), form) {
+            (Some(forms), Some(form)) => forms.get(form).cloned().unwrap_or_default(),
+            (Some(forms), None) => forms.values().flatten().copied().collect(),
+            (None, _) => Vec::new(),
+        };
+        positions.sort_unstable();
+        self.rank_examples(&positions, known)
+    }
+
+    /// Words of a sentence, lowercased, to tell repeated sentences apart
+    fn sentence_key(&self, sentence_i: usize) -> Vec<String> {
+        self.tokens[sentence_i]
+            .iter()
+            .map(|t| t.text.to_lowercase())
+            .collect()
+    }
+}
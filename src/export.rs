@@ -0,0 +1,106 @@
+use crate::subtitles::{format_timestamp, Timing};
+use serde::Serialize;
+use std::fs::OpenOptions;
+use std::io::{self, Write};
+use std::path::Path;
+
+/// A flashcard for one target word, shown in the sentence it was found in
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct Card {
+    pub lemma: String,
+    pub form: String,
+    pub before: String,
+    pub word: String,
+    pub after: String,
+    /// Dictionary definition, empty if none was found
+    pub definition: String,
+    /// The learner's own gloss of the lemma
+    pub gloss: String,
+    /// The learner's note on the sentence
+    pub note: String,
+    /// When the sentence is said in the video, for sentences from subtitles
+    pub timestamp: String,
+}
+
+impl Card {
+    pub fn new(lemma: &str, form: &str, usage: &(String, String, String)) -> Card {
+        let (before, word, after) = usage.clone();
+        Card {
+            lemma: lemma.to_string(),
+            form: form.to_string(),
+            before,
+            word,
+            after,
+            definition: String::new(),
+            gloss: String::new(),
+            note: String::new(),
+            timestamp: String::new(),
+        }
+    }
+
+    pub fn with_definition(mut self, definition: String) -> Card {
+        self.definition = definition;
+        self
+    }
+
+    pub fn with_notes(mut self, gloss: String, note: String) -> Card {
+        self.gloss = gloss;
+        self.note = note;
+        self
+    }
+
+    pub fn with_timing(mut self, timing: Option<Timing>) -> Card {
+        self.timestamp = timing.map_or(String::new(), |timing| format_timestamp(timing.start));
+        self
+    }
+
+    pub fn sentence(&self) -> String {
+        format!("{}{}{}", self.before, self.word, self.after)
+    }
+}
+
+/// Writes cards as tab-separated notes that Anki's "Import File" reads
+/// directly: sentence with the word in bold, lemma, form, definition, gloss,
+/// note and timestamp.
+pub fn write_anki_tsv<W: Write>(cards: &[Card], out: &mut W) -> io::Result<()> {
+    writeln!(out, "#separator:tab")?;
+    writeln!(out, "#html:true")?;
+    for card in cards {
+        writeln!(
+            out,
+            "{}<b>{}</b>{}\t{}\t{}\t{}\t{}\t{}\t{}",
+            escape(&card.before),
+            escape(&card.word),
+            escape(&card.after),
+            escape(&card.lemma),
+            escape(&card.form),
+            escape(&card.definition),
+            escape(&card.gloss),
+            escape(&card.note),
+            escape(&card.timestamp),
+        )?;
+    }
+    Ok(())
+}
+
+/// Appends cards to an Anki TSV file, writing the header if the file is new
+pub fn append_anki_tsv<P: AsRef<Path>>(cards: &[Card], path: P) -> io::Result<()> {
+    let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+    let mut buffer: Vec<u8> = Vec::new();
+    write_anki_tsv(cards, &mut buffer)?;
+    if file.metadata()?.len() > 0 {
+        // Headers are only valid at the top of the file
+        let body = buffer.splitn(3, |b| *b == b'\n').nth(2).unwrap_or(&[]);
+        file.write_all(body)
+    } else {
+        file.write_all(&buffer)
+    }
+}
+
+pub(crate) fn escape(field: &str) -> String {
+    field
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace(['\t', '\n'], " ")
+}
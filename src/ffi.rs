@@ -0,0 +1,351 @@
+//! C API over `Lemmatizer`, declared in `include/vocrab.h`. Regenerate the
+//! header with `cbindgen --config cbindgen.toml --output include/vocrab.h`
+//! after changing anything here.
+//!
+//! Every handle returned by a `vocrab_*` function is owned by the caller and
+//! freed with the matching `*_free` function. Strings borrowed from a handle
+//! live as long as the handle. Failures return `NULL` or a status other than
+//! `VOCRAB_OK`, and `vocrab_last_error_message` says what went wrong.
+
+use crate::lemmatizer::{Lemmatizer, LemmatizerError};
+use std::cell::RefCell;
+use std::ffi::{CStr, CString};
+use std::os::raw::c_char;
+use std::ptr;
+
+/// Outcome of a call, one per `LemmatizerError` variant plus argument errors
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum VocrabStatus {
+    Ok = 0,
+    NullArgument = 1,
+    InvalidUtf8 = 2,
+    FileIoFailed = 3,
+    JsonParseFailed = 4,
+    UnknownFormat = 5,
+    MalformedFile = 6,
+    AnalyzerFailed = 7,
+    JsonSerializeFailed = 8,
+}
+
+impl From<&LemmatizerError> for VocrabStatus {
+    fn from(e: &LemmatizerError) -> VocrabStatus {
+        match e {
+            LemmatizerError::FileIOFailed(_) => VocrabStatus::FileIoFailed,
+            LemmatizerError::JSONParseFailed(_) => VocrabStatus::JsonParseFailed,
+            LemmatizerError::UnknownFormat(_) => VocrabStatus::UnknownFormat,
+            LemmatizerError::MalformedFile(_) => VocrabStatus::MalformedFile,
+            LemmatizerError::AnalyzerFailed(_) => VocrabStatus::AnalyzerFailed,
+            LemmatizerError::JSONSerializeFailed(_) => VocrabStatus::JsonSerializeFailed,
+        }
+    }
+}
+
+/// A list of strings, such as ranked lemmas or the forms of a lemma
+pub struct VocrabStrings {
+    items: Vec<CString>,
+}
+
+/// One usage, borrowed from the `VocrabUsages` it was read from
+#[repr(C)]
+pub struct VocrabUsage {
+    pub before: *const c_char,
+    pub word: *const c_char,
+    pub after: *const c_char,
+}
+
+/// The sentences a lemma or form is used in
+pub struct VocrabUsages {
+    items: Vec<(CString, CString, CString)>,
+}
+
+thread_local! {
+    static LAST_ERROR: RefCell<Option<CString>> = const { RefCell::new(None) };
+}
+
+fn set_error(status: VocrabStatus, message: String) -> VocrabStatus {
+    LAST_ERROR.with(|last| *last.borrow_mut() = Some(to_c_string(&message)));
+    status
+}
+
+fn clear_error() {
+    LAST_ERROR.with(|last| *last.borrow_mut() = None);
+}
+
+/// Documents may hold NUL characters, which C strings can't
+fn to_c_string(text: &str) -> CString {
+    CString::new(text.replace('\0', "")).unwrap_or_default()
+}
+
+/// Borrows a C string argument as UTF-8, recording why it can't be
+unsafe fn str_arg<'a>(text: *const c_char, name: &str) -> Result<&'a str, VocrabStatus> {
+    if text.is_null() {
+        return Err(set_error(
+            VocrabStatus::NullArgument,
+            format!("{} is NULL", name),
+        ));
+    }
+    CStr::from_ptr(text)
+        .to_str()
+        .map_err(|_| set_error(VocrabStatus::InvalidUtf8, format!("{} isn't UTF-8", name)))
+}
+
+unsafe fn lemmatizer_arg<'a>(
+    lemmatizer: *const Lemmatizer,
+) -> Result<&'a Lemmatizer, VocrabStatus> {
+    lemmatizer
+        .as_ref()
+        .ok_or_else(|| set_error(VocrabStatus::NullArgument, "lemmatizer is NULL".to_string()))
+}
+
+fn load_result(result: Result<&mut Lemmatizer, LemmatizerError>, source: &str) -> VocrabStatus {
+    match result {
+        Ok(_) => {
+            clear_error();
+            VocrabStatus::Ok
+        }
+        Err(e) => set_error((&e).into(), format!("{}: {}", source, e)),
+    }
+}
+
+/// The message of the last failed call on this thread, or `NULL`. Valid
+/// until the next `vocrab_*` call on the thread.
+#[no_mangle]
+pub extern "C" fn vocrab_last_error_message() -> *const c_char {
+    LAST_ERROR.with(|last| last.borrow().as_ref().map_or(ptr::null(), |e| e.as_ptr()))
+}
+
+#[no_mangle]
+pub extern "C" fn vocrab_lemmatizer_new() -> *mut Lemmatizer {
+    Box::into_raw(Box::new(Lemmatizer::new()))
+}
+
+/// # Safety
+/// `lemmatizer` is `NULL` or came from `vocrab_lemmatizer_new` and hasn't
+/// been freed.
+#[no_mangle]
+pub unsafe extern "C" fn vocrab_lemmatizer_free(lemmatizer: *mut Lemmatizer) {
+    if !lemmatizer.is_null() {
+        drop(Box::from_raw(lemmatizer));
+    }
+}
+
+/// Adds a document written by `tokenizer.py`
+///
+/// # Safety
+/// `lemmatizer` is a live handle and `path` a NUL-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn vocrab_load_file(
+    lemmatizer: *mut Lemmatizer,
+    path: *const c_char,
+) -> VocrabStatus {
+    let lemmatizer = match lemmatizer.as_mut() {
+        Some(lemmatizer) => lemmatizer,
+        None => return set_error(VocrabStatus::NullArgument, "lemmatizer is NULL".to_string()),
+    };
+    match str_arg(path, "path") {
+        Ok(path) => load_result(lemmatizer.load_file(path), path),
+        Err(status) => status,
+    }
+}
+
+/// Adds a document from `len` bytes of JSON in the layout `tokenizer.py`
+/// writes, which need not be NUL-terminated
+///
+/// # Safety
+/// `lemmatizer` is a live handle, `name` a NUL-terminated string and `json`
+/// points to at least `len` readable bytes.
+#[no_mangle]
+pub unsafe extern "C" fn vocrab_load_buffer(
+    lemmatizer: *mut Lemmatizer,
+    name: *const c_char,
+    json: *const c_char,
+    len: usize,
+) -> VocrabStatus {
+    let lemmatizer = match lemmatizer.as_mut() {
+        Some(lemmatizer) => lemmatizer,
+        None => return set_error(VocrabStatus::NullArgument, "lemmatizer is NULL".to_string()),
+    };
+    let name = match str_arg(name, "name") {
+        Ok(name) => name,
+        Err(status) => return status,
+    };
+    if json.is_null() {
+        return set_error(VocrabStatus::NullArgument, "json is NULL".to_string());
+    }
+    let bytes = std::slice::from_raw_parts(json as *const u8, len);
+    match std::str::from_utf8(bytes) {
+        Ok(json) => load_result(lemmatizer.load_json(name, json), name),
+        Err(_) => set_error(
+            VocrabStatus::InvalidUtf8,
+            format!("{}: json isn't UTF-8", name),
+        ),
+    }
+}
+
+/// Number of documents loaded
+///
+/// # Safety
+/// `lemmatizer` is `NULL` or a live handle.
+#[no_mangle]
+pub unsafe extern "C" fn vocrab_document_count(lemmatizer: *const Lemmatizer) -> usize {
+    lemmatizer.as_ref().map_or(0, |l| l.documents().len())
+}
+
+/// Lemmas, most used first, or `NULL` on error
+///
+/// # Safety
+/// `lemmatizer` is a live handle.
+#[no_mangle]
+pub unsafe extern "C" fn vocrab_get_lemmas(lemmatizer: *const Lemmatizer) -> *mut VocrabStrings {
+    match lemmatizer_arg(lemmatizer) {
+        Ok(lemmatizer) => strings(lemmatizer.get_lemmas()),
+        Err(_) => ptr::null_mut(),
+    }
+}
+
+/// Number of times any form of a lemma is used; 0 on error
+///
+/// # Safety
+/// `lemmatizer` is a live handle and `lemma` a NUL-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn vocrab_get_lemma_count(
+    lemmatizer: *const Lemmatizer,
+    lemma: *const c_char,
+) -> usize {
+    match (lemmatizer_arg(lemmatizer), str_arg(lemma, "lemma")) {
+        (Ok(lemmatizer), Ok(lemma)) => {
+            clear_error();
+            lemmatizer.get_lemma_count(lemma)
+        }
+        _ => 0,
+    }
+}
+
+/// Forms of a lemma, most used first, or `NULL` on error. A lemma that
+/// isn't in the documents has no forms.
+///
+/// # Safety
+/// `lemmatizer` is a live handle and `lemma` a NUL-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn vocrab_get_forms(
+    lemmatizer: *const Lemmatizer,
+    lemma: *const c_char,
+) -> *mut VocrabStrings {
+    match (lemmatizer_arg(lemmatizer), str_arg(lemma, "lemma")) {
+        (Ok(lemmatizer), Ok(lemma)) => strings(lemmatizer.get_forms(lemma)),
+        _ => ptr::null_mut(),
+    }
+}
+
+fn strings(items: Vec<String>) -> *mut VocrabStrings {
+    clear_error();
+    let items = items.iter().map(|item| to_c_string(item)).collect();
+    Box::into_raw(Box::new(VocrabStrings { items }))
+}
+
+/// # Safety
+/// `strings` is `NULL` or a live handle.
+#[no_mangle]
+pub unsafe extern "C" fn vocrab_strings_len(strings: *const VocrabStrings) -> usize {
+    strings.as_ref().map_or(0, |s| s.items.len())
+}
+
+/// The string at `index`, or `NULL` past the end
+///
+/// # Safety
+/// `strings` is `NULL` or a live handle.
+#[no_mangle]
+pub unsafe extern "C" fn vocrab_strings_get(
+    strings: *const VocrabStrings,
+    index: usize,
+) -> *const c_char {
+    strings
+        .as_ref()
+        .and_then(|s| s.items.get(index))
+        .map_or(ptr::null(), |item| item.as_ptr())
+}
+
+/// # Safety
+/// `strings` is `NULL` or a live handle, which is invalid afterwards.
+#[no_mangle]
+pub unsafe extern "C" fn vocrab_strings_free(strings: *mut VocrabStrings) {
+    if !strings.is_null() {
+        drop(Box::from_raw(strings));
+    }
+}
+
+/// Usages of a form of a lemma in text order, of every form when `form` is
+/// `NULL`, or `NULL` on error
+///
+/// # Safety
+/// `lemmatizer` is a live handle, `lemma` a NUL-terminated string and
+/// `form` `NULL` or a NUL-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn vocrab_get_usages(
+    lemmatizer: *const Lemmatizer,
+    lemma: *const c_char,
+    form: *const c_char,
+) -> *mut VocrabUsages {
+    let (lemmatizer, lemma) = match (lemmatizer_arg(lemmatizer), str_arg(lemma, "lemma")) {
+        (Ok(lemmatizer), Ok(lemma)) => (lemmatizer, lemma),
+        _ => return ptr::null_mut(),
+    };
+    let usages = if form.is_null() {
+        lemmatizer.get_lemma_usages(lemma)
+    } else {
+        match str_arg(form, "form") {
+            Ok(form) => lemmatizer.get_usages(lemma, form),
+            Err(_) => return ptr::null_mut(),
+        }
+    };
+    clear_error();
+    let items = usages
+        .iter()
+        .map(|(before, word, after)| (to_c_string(before), to_c_string(word), to_c_string(after)))
+        .collect();
+    Box::into_raw(Box::new(VocrabUsages { items }))
+}
+
+/// # Safety
+/// `usages` is `NULL` or a live handle.
+#[no_mangle]
+pub unsafe extern "C" fn vocrab_usages_len(usages: *const VocrabUsages) -> usize {
+    usages.as_ref().map_or(0, |u| u.items.len())
+}
+
+/// Reads the usage at `index` into `out`. Returns `false` past the end.
+///
+/// # Safety
+/// `usages` is `NULL` or a live handle and `out` is `NULL` or writable.
+#[no_mangle]
+pub unsafe extern "C" fn vocrab_usages_get(
+    usages: *const VocrabUsages,
+    index: usize,
+    out: *mut VocrabUsage,
+) -> bool {
+    let usage = match usages.as_ref().and_then(|u| u.items.get(index)) {
+        Some(usage) => usage,
+        None => return false,
+    };
+    match out.as_mut() {
+        Some(out) => {
+            *out = VocrabUsage {
+                before: usage.0.as_ptr(),
+                word: usage.1.as_ptr(),
+                after: usage.2.as_ptr(),
+            };
+            true
+        }
+        None => false,
+    }
+}
+
+/// # Safety
+/// `usages` is `NULL` or a live handle, which is invalid afterwards.
+#[no_mangle]
+pub unsafe extern "C" fn vocrab_usages_free(usages: *mut VocrabUsages) {
+    if !usages.is_null() {
+        drop(Box::from_raw(usages));
+    }
+}
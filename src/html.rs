@@ -0,0 +1,804 @@
+use crate::analyzer::Analyzer;
+use crate::lemmatizer::{DocumentFile, LemmatizerError};
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+/// Elements that never hold article text
+const SKIPPED: [&str; 14] = [
+    "script", "style", "noscript", "template", "svg", "iframe", "nav", "aside", "form", "button",
+    "select", "header", "footer", "menu",
+];
+/// Elements with no content or end tag
+const VOID: [&str; 14] = [
+    "area", "base", "br", "col", "embed", "hr", "img", "input", "link", "meta", "param", "source",
+    "track", "wbr",
+];
+/// Elements that start a paragraph of their own
+const BLOCKS: [&str; 30] = [
+    "address",
+    "article",
+    "blockquote",
+    "body",
+    "dd",
+    "div",
+    "dl",
+    "dt",
+    "figcaption",
+    "figure",
+    "h1",
+    "h2",
+    "h3",
+    "h4",
+    "h5",
+    "h6",
+    "hr",
+    "li",
+    "main",
+    "ol",
+    "p",
+    "pre",
+    "section",
+    "table",
+    "tbody",
+    "td",
+    "th",
+    "thead",
+    "tr",
+    "ul",
+];
+/// Class and id words of page furniture around an article
+const UNLIKELY: [&str; 22] = [
+    "ad-",
+    "advert",
+    "banner",
+    "breadcrumb",
+    "byline",
+    "comment",
+    "cookie",
+    "disqus",
+    "footer",
+    "gdpr",
+    "header",
+    "menu",
+    "nav",
+    "newsletter",
+    "pagination",
+    "popup",
+    "promo",
+    "related",
+    "share",
+    "sidebar",
+    "social",
+    "sponsor",
+];
+/// Class and id words that outweigh an unlikely one, as in "main-header"
+const LIKELY: [&str; 6] = ["article", "body", "content", "main", "post", "story"];
+
+/// Text of a web page or book chapter, split into paragraphs
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Article {
+    /// From `<title>`, without the site name
+    pub title: Option<String>,
+    pub paragraphs: Vec<String>,
+}
+
+impl Article {
+    /// Paragraphs separated by blank lines, the way `tokenizer.py` splits
+    /// them
+    pub fn text(&self) -> String {
+        self.paragraphs.join("\n\n")
+    }
+}
+
+/// Reads a saved web page and keeps its main article, leaving out menus,
+/// sidebars, comments and other boilerplate
+pub fn read_article<P: AsRef<Path>>(path: P) -> Result<Article, LemmatizerError> {
+    let bytes = fs::read(path).map_err(LemmatizerError::FileIOFailed)?;
+    Ok(parse_article(&decode(bytes)))
+}
+
+/// UTF-8, or else Latin-1, which older pages are often saved in
+pub(crate) fn decode(bytes: Vec<u8>) -> String {
+    String::from_utf8(bytes)
+        .unwrap_or_else(|e| e.into_bytes().into_iter().map(char::from).collect())
+}
+
+/// The main article of a page. Like Readability, paragraphs score their
+/// container by their length and number of commas, and the best scored
+/// container is kept along with the siblings that score nearly as well.
+pub fn parse_article(html: &str) -> Article {
+    let dom = Dom::parse(html);
+    let mut scores: HashMap<usize, f32> = HashMap::new();
+    for i in 0..dom.elements.len() {
+        let element = &dom.elements[i];
+        if !["p", "pre", "td", "blockquote"].contains(&element.name.as_str()) || dom.is_skipped(i) {
+            continue;
+        }
+        let text = dom.text(i);
+        let length = text.chars().count();
+        if length < 25 {
+            continue;
+        }
+        let commas = text.matches([',', '،', '，', '、']).count();
+        let score = 1.0 + commas as f32 + (length as f32 / 100.0).min(3.0);
+        if let Some(parent) = element.parent {
+            *scores.entry(parent).or_insert(0.0) += score;
+            if let Some(grandparent) = dom.elements[parent].parent {
+                *scores.entry(grandparent).or_insert(0.0) += score / 2.0;
+            }
+        }
+    }
+    for (i, score) in scores.iter_mut() {
+        *score *= 1.0 - dom.link_density(*i);
+    }
+
+    // The first of equally scored containers, so the pick doesn't depend on
+    // hash order
+    let best = scores
+        .iter()
+        .max_by(|a, b| {
+            a.1.partial_cmp(b.1)
+                .unwrap_or(Ordering::Equal)
+                .then_with(|| b.0.cmp(a.0))
+        })
+        .map(|(i, score)| (*i, *score));
+    let mut paragraphs = Vec::new();
+    match best {
+        Some((best, best_score)) => {
+            let threshold = (best_score * 0.2).max(10.0);
+            let siblings = match dom.elements[best].parent {
+                Some(parent) => dom.child_elements(parent),
+                None => vec![best],
+            };
+            for sibling in siblings {
+                let keep = sibling == best
+                    || scores
+                        .get(&sibling)
+                        .is_some_and(|score| *score >= threshold)
+                    || (dom.elements[sibling].name == "p"
+                        && dom.text(sibling).chars().count() > 80
+                        && dom.link_density(sibling) < 0.25);
+                if keep && !dom.is_skipped(sibling) {
+                    dom.paragraphs(sibling, &mut paragraphs);
+                }
+            }
+        }
+        // Pages without paragraphs of prose, like a list of links
+        None => dom.paragraphs(dom.body(), &mut paragraphs),
+    }
+    Article {
+        title: dom.title().map(|title| site_name_removed(&title)),
+        paragraphs,
+    }
+}
+
+/// Every paragraph in the body of a page, for pages that are all content,
+/// like the chapters of a book
+pub fn parse_body(html: &str) -> Article {
+    let mut dom = Dom::parse(html);
+    dom.skip_boilerplate = false;
+    let mut paragraphs = Vec::new();
+    dom.paragraphs(dom.body(), &mut paragraphs);
+    Article {
+        title: dom.title(),
+        paragraphs,
+    }
+}
+
+/// "Headline | Site" or "Headline - Site" as "Headline", if the headline
+/// is long enough not to be a section name
+fn site_name_removed(title: &str) -> String {
+    for separator in [" | ", " – ", " — ", " - ", " :: ", " » "] {
+        if let Some((headline, _)) = title.rsplit_once(separator) {
+            if headline.split_whitespace().count() >= 3 {
+                return headline.trim().to_string();
+            }
+        }
+    }
+    title.to_string()
+}
+
+/// Analyzes the text of an article into document JSON, keeping its title
+pub fn analyze_article<A: Analyzer>(
+    article: &Article,
+    analyzer: &A,
+    language: &str,
+) -> Result<String, LemmatizerError> {
+    let json = analyzer.analyze(&article.text(), language)?;
+    let mut document: DocumentFile =
+        serde_json::from_str(&json).map_err(LemmatizerError::JSONParseFailed)?;
+    document.title = article.title.clone();
+    serde_json::to_string(&document).map_err(LemmatizerError::JSONSerializeFailed)
+}
+
+enum Child {
+    Element(usize),
+    Text(String),
+}
+
+struct Element {
+    name: String,
+    /// Class and id, lowercased, to tell boilerplate by
+    class_id: String,
+    parent: Option<usize>,
+    children: Vec<Child>,
+}
+
+/// A forgiving parse of HTML into a tree, enough to find the text in it.
+/// Unclosed elements end with their parent, and stray end tags are
+/// ignored.
+struct Dom {
+    /// Elements in document order, the root first
+    elements: Vec<Element>,
+    /// Whether paragraphs leave out elements whose class or id names page
+    /// furniture, as on web pages
+    skip_boilerplate: bool,
+}
+
+impl Dom {
+    fn parse(html: &str) -> Dom {
+        let mut dom = Dom {
+            elements: vec![Element {
+                name: "#root".to_string(),
+                class_id: String::new(),
+                parent: None,
+                children: Vec::new(),
+            }],
+            skip_boilerplate: true,
+        };
+        let mut open = vec![0];
+        let mut rest = html;
+        while !rest.is_empty() {
+            let (text, tag) = match rest.find('<') {
+                Some(i) => rest.split_at(i),
+                None => (rest, ""),
+            };
+            if !text.is_empty() {
+                dom.push_text(*open.last().unwrap(), text);
+            }
+            if tag.is_empty() {
+                break;
+            }
+            rest = dom.parse_tag(tag, &mut open);
+        }
+        dom
+    }
+
+    /// Reads the tag at the start of `html`, returning what follows it
+    fn parse_tag<'a>(&mut self, html: &'a str, open: &mut Vec<usize>) -> &'a str {
+        if let Some(comment) = html.strip_prefix("<!--") {
+            return comment.find("-->").map_or("", |end| &comment[end + 3..]);
+        }
+        if html.starts_with("<!") || html.starts_with("<?") {
+            return html.find('>').map_or("", |end| &html[end + 1..]);
+        }
+        let closing = html.starts_with("</");
+        let name_start = if closing { 2 } else { 1 };
+        let name_len = html[name_start..]
+            .find(|c: char| !c.is_ascii_alphanumeric() && c != '-' && c != ':')
+            .unwrap_or(html.len() - name_start);
+        if name_len == 0 {
+            // A lone "<", as in "a < b"
+            self.push_text(*open.last().unwrap(), "<");
+            return &html[1..];
+        }
+        let name = html[name_start..name_start + name_len].to_ascii_lowercase();
+        let end = tag_end(html).unwrap_or(html.len());
+        let attributes = html.get(name_start + name_len..end).unwrap_or("");
+        let rest = html.get(end..).unwrap_or("");
+
+        if closing {
+            if let Some(i) = open.iter().rposition(|e| self.elements[*e].name == name) {
+                if i > 0 {
+                    open.truncate(i);
+                }
+            }
+            return rest;
+        }
+
+        // Tags that end the paragraph or list item before them
+        let top = &self.elements[*open.last().unwrap()].name;
+        if (top == "p" && BLOCKS.contains(&name.as_str()))
+            || (top == "li" && name == "li")
+            || ((top == "dt" || top == "dd") && (name == "dt" || name == "dd"))
+        {
+            open.pop();
+        }
+
+        let parent = *open.last().unwrap();
+        let element = self.elements.len();
+        self.elements.push(Element {
+            name: name.clone(),
+            class_id: class_and_id(attributes),
+            parent: Some(parent),
+            children: Vec::new(),
+        });
+        self.elements[parent].children.push(Child::Element(element));
+
+        if ["script", "style", "title", "textarea"].contains(&name.as_str()) {
+            // Raw text, up to the end tag
+            let close = format!("</{}", name);
+            let end = rest.to_ascii_lowercase().find(&close).unwrap_or(rest.len());
+            if name == "title" {
+                self.push_text(element, &rest[..end]);
+            }
+            return rest[end..].find('>').map_or("", |i| &rest[end + i + 1..]);
+        }
+        if !VOID.contains(&name.as_str()) && !html[..end].ends_with("/>") {
+            open.push(element);
+        }
+        rest
+    }
+
+    fn push_text(&mut self, element: usize, text: &str) {
+        self.elements[element]
+            .children
+            .push(Child::Text(decode_entities(text)));
+    }
+
+    fn child_elements(&self, element: usize) -> Vec<usize> {
+        self.elements[element]
+            .children
+            .iter()
+            .filter_map(|child| match child {
+                Child::Element(i) => Some(*i),
+                Child::Text(_) => None,
+            })
+            .collect()
+    }
+
+    fn find(&self, name: &str) -> Option<usize> {
+        self.elements.iter().position(|e| e.name == name)
+    }
+
+    fn body(&self) -> usize {
+        self.find("body").unwrap_or(0)
+    }
+
+    fn title(&self) -> Option<String> {
+        let title = normalize_space(&self.text(self.find("title")?));
+        Some(title).filter(|title| !title.is_empty())
+    }
+
+    /// Whether an element, or one it's in, is page furniture
+    fn is_skipped(&self, element: usize) -> bool {
+        let mut current = Some(element);
+        while let Some(i) = current {
+            let element = &self.elements[i];
+            if SKIPPED.contains(&element.name.as_str()) || is_unlikely(element) {
+                return true;
+            }
+            current = element.parent;
+        }
+        false
+    }
+
+    /// Text of an element and everything in it
+    fn text(&self, element: usize) -> String {
+        let mut text = String::new();
+        self.collect_text(element, &mut text, false);
+        text
+    }
+
+    /// Text inside links, if `links_only`, or all of it
+    fn collect_text(&self, element: usize, text: &mut String, links_only: bool) {
+        let links_only = links_only && self.elements[element].name != "a";
+        for child in &self.elements[element].children {
+            match child {
+                Child::Text(t) if !links_only => text.push_str(t),
+                Child::Text(_) => {}
+                Child::Element(i) if SKIPPED.contains(&self.elements[*i].name.as_str()) => {}
+                Child::Element(i) => self.collect_text(*i, text, links_only),
+            }
+        }
+    }
+
+    /// Share of an element's text that is link text
+    fn link_density(&self, element: usize) -> f32 {
+        let length = self.text(element).chars().count();
+        let mut links = String::new();
+        self.collect_text(element, &mut links, true);
+        match length {
+            0 => 0.0,
+            length => links.chars().count() as f32 / length as f32,
+        }
+    }
+
+    /// Paragraphs of text in an element, one per block element, leaving out
+    /// boilerplate and link lists within it
+    fn paragraphs(&self, element: usize, paragraphs: &mut Vec<String>) {
+        let mut current = String::new();
+        self.collect_paragraphs(element, paragraphs, &mut current);
+        push_paragraph(paragraphs, &current);
+    }
+
+    fn collect_paragraphs(
+        &self,
+        element: usize,
+        paragraphs: &mut Vec<String>,
+        current: &mut String,
+    ) {
+        for child in &self.elements[element].children {
+            match child {
+                Child::Text(text) => current.push_str(text),
+                Child::Element(i) => {
+                    let child = &self.elements[*i];
+                    let name = child.name.as_str();
+                    if SKIPPED.contains(&name)
+                        || name == "title"
+                        || (self.skip_boilerplate && is_unlikely(child))
+                        || (["ul", "ol", "div", "section", "table"].contains(&name)
+                            && self.link_density(*i) > 0.5)
+                    {
+                        continue;
+                    }
+                    if name == "br" {
+                        // Pages that don't mark up paragraphs separate them
+                        // with line breaks
+                        push_paragraph(paragraphs, current);
+                        current.clear();
+                    } else if BLOCKS.contains(&name) {
+                        push_paragraph(paragraphs, current);
+                        current.clear();
+                        self.collect_paragraphs(*i, paragraphs, current);
+                        push_paragraph(paragraphs, current);
+                        current.clear();
+                    } else {
+                        self.collect_paragraphs(*i, paragraphs, current);
+                    }
+                }
+            }
+        }
+    }
+}
+
+fn push_paragraph(paragraphs: &mut Vec<String>, text: &str) {
+    let text = normalize_space(text);
+    if !text.is_empty() {
+        paragraphs.push(text);
+    }
+}
+
+fn normalize_space(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<&str>>().join(" ")
+}
+
+/// Whether an element's class or id names page furniture. Classes on the
+/// page as a whole, like `<body class="nav-open">`, don't count.
+fn is_unlikely(element: &Element) -> bool {
+    let class_id = &element.class_id;
+    !["html", "body", "article", "main"].contains(&element.name.as_str())
+        && UNLIKELY.iter().any(|word| class_id.contains(word))
+        && !LIKELY.iter().any(|word| class_id.contains(word))
+}
+
+/// Offset just past the `>` that ends a tag, skipping quoted attribute
+/// values
+fn tag_end(html: &str) -> Option<usize> {
+    let mut quote = None;
+    for (i, c) in html.char_indices() {
+        match (quote, c) {
+            (None, '"') | (None, '\'') => quote = Some(c),
+            (Some(q), c) if c == q => quote = None,
+            (None, '>') => return Some(i + 1),
+            _ => {}
+        }
+    }
+    None
+}
+
+/// Values of the class and id attributes, lowercased
+fn class_and_id(attributes: &str) -> String {
+    let values: Vec<String> = ["class", "id"]
+        .iter()
+        .filter_map(|name| attribute(attributes, name))
+        .collect();
+    values.join(" ").to_lowercase()
+}
+
+/// Value of an attribute, by its name in any case, with entities decoded
+pub(crate) fn attribute(attributes: &str, name: &str) -> Option<String> {
+    // ASCII lowercasing keeps byte offsets the same
+    let lower = attributes.to_ascii_lowercase();
+    let mut from = 0;
+    while let Some(i) = lower[from..].find(name).map(|i| from + i) {
+        from = i + name.len();
+        let preceded = i == 0 || lower[..i].ends_with(char::is_whitespace);
+        let value = match attributes[from..].trim_start().strip_prefix('=') {
+            Some(value) if preceded => value.trim_start(),
+            _ => continue,
+        };
+        let value = match value.chars().next() {
+            Some(q @ '"') | Some(q @ '\'') => value[1..].split(q).next().unwrap_or(""),
+            _ => value
+                .split(|c: char| c.is_whitespace() || c == '>')
+                .next()
+                .unwrap_or(""),
+        };
+        return Some(decode_entities(value));
+    }
+    None
+}
+
+/// A start or end tag of an HTML or XML document
+pub(crate) struct Tag<'a> {
+    /// Lowercased, without a namespace prefix like `dc:`
+    pub(crate) name: String,
+    pub(crate) attributes: &'a str,
+    pub(crate) closing: bool,
+    /// Byte offsets of the `<` and of what follows the `>`
+    pub(crate) start: usize,
+    pub(crate) end: usize,
+}
+
+/// Every tag of a document in order, for files simpler than a web page,
+/// like the XML that describes an EPUB
+pub(crate) fn tags(xml: &str) -> Vec<Tag<'_>> {
+    let mut tags = Vec::new();
+    let mut from = 0;
+    while let Some(start) = xml[from..].find('<').map(|i| from + i) {
+        let rest = &xml[start..];
+        if let Some(comment) = rest.strip_prefix("<!--") {
+            from = comment.find("-->").map_or(xml.len(), |i| start + 4 + i + 3);
+            continue;
+        }
+        let end = match tag_end(rest) {
+            Some(end) => start + end,
+            None => break,
+        };
+        from = end;
+        let closing = rest.starts_with("</");
+        let inner = &xml[start + if closing { 2 } else { 1 }..end - 1];
+        if inner.starts_with(['!', '?']) {
+            continue;
+        }
+        let name_len = inner
+            .find(|c: char| c.is_whitespace() || c == '/')
+            .unwrap_or(inner.len());
+        let name = &inner[..name_len];
+        tags.push(Tag {
+            name: name.rsplit(':').next().unwrap_or(name).to_ascii_lowercase(),
+            attributes: &inner[name_len..],
+            closing,
+            start,
+            end,
+        });
+    }
+    tags
+}
+
+/// Text of a fragment of markup, without its tags
+pub(crate) fn strip_tags(fragment: &str) -> String {
+    let mut text = String::new();
+    let mut rest = fragment;
+    while let Some(i) = rest.find('<') {
+        text.push_str(&rest[..i]);
+        rest = rest[i..].find('>').map_or("", |end| &rest[i + end + 1..]);
+    }
+    text.push_str(rest);
+    normalize_space(&decode_entities(&text))
+}
+
+/// Named entities common in running text, besides numeric ones
+const ENTITIES: [(&str, &str); 44] = [
+    ("amp", "&"),
+    ("lt", "<"),
+    ("gt", ">"),
+    ("quot", "\""),
+    ("apos", "'"),
+    ("nbsp", " "),
+    ("shy", ""),
+    ("hellip", "…"),
+    ("mdash", "—"),
+    ("ndash", "–"),
+    ("laquo", "«"),
+    ("raquo", "»"),
+    ("lsquo", "‘"),
+    ("rsquo", "’"),
+    ("ldquo", "“"),
+    ("rdquo", "”"),
+    ("bdquo", "„"),
+    ("iexcl", "¡"),
+    ("iquest", "¿"),
+    ("middot", "·"),
+    ("deg", "°"),
+    ("ordm", "º"),
+    ("ordf", "ª"),
+    ("copy", "©"),
+    ("euro", "€"),
+    ("aacute", "á"),
+    ("eacute", "é"),
+    ("iacute", "í"),
+    ("oacute", "ó"),
+    ("uacute", "ú"),
+    ("ntilde", "ñ"),
+    ("uuml", "ü"),
+    ("agrave", "à"),
+    ("egrave", "è"),
+    ("ugrave", "ù"),
+    ("acirc", "â"),
+    ("ecirc", "ê"),
+    ("icirc", "î"),
+    ("ocirc", "ô"),
+    ("ucirc", "û"),
+    ("euml", "ë"),
+    ("iuml", "ï"),
+    ("ccedil", "ç"),
+    ("oelig", "œ"),
+];
+
+/// Longest text between `&` and `;` of an entity, a numeric one such as
+/// `#x10FFFF`, so a stray `&` isn't followed to a far-off `;`
+const MAX_ENTITY_LEN: usize = 8;
+
+pub(crate) fn decode_entities(text: &str) -> String {
+    let mut decoded = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(i) = rest.find('&') {
+        decoded.push_str(&rest[..i]);
+        rest = &rest[i..];
+        let entity = rest[1..]
+            .bytes()
+            .take(MAX_ENTITY_LEN + 1)
+            .position(|b| b == b';')
+            .map(|end| &rest[1..end + 1]);
+        let replacement = entity.and_then(|entity| match entity.strip_prefix('#') {
+            Some(number) => {
+                let code = match number.strip_prefix(['x', 'X']) {
+                    Some(hex) => u32::from_str_radix(hex, 16).ok(),
+                    None => number.parse().ok(),
+                };
+                code.and_then(char::from_u32).map(String::from)
+            }
+            None => ENTITIES
+                .iter()
+                .find(|(name, _)| *name == entity)
+                .or_else(|| {
+                    // Capitalized letters, like &Eacute;
+                    let lower = entity.to_lowercase();
+                    ENTITIES
+                        .iter()
+                        .find(|(name, _)| *name == lower && name.len() > 4)
+                })
+                .map(|(name, value)| match *name != entity {
+                    true => value.to_uppercase(),
+                    false => value.to_string(),
+                }),
+        });
+        match (entity, replacement) {
+            (Some(entity), Some(replacement)) => {
+                decoded.push_str(&replacement);
+                rest = &rest[entity.len() + 2..];
+            }
+            _ => {
+                decoded.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    decoded.push_str(rest);
+    decoded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analyzer::tests::SplitAnalyzer;
+    use std::time::Instant;
+
+    const PAGE: &str = r#"<!DOCTYPE html>
+<html><head>
+<title>Los emos y los punks de la Glorieta | El Diario</title>
+<style>p { color: red; }</style>
+<script>var teaser = "<p>Texto de un script, que no es parte del artículo.</p>";</script>
+</head>
+<body class="nav-open">
+<nav><ul><li><a href="/">Inicio</a></li><li><a href="/a">Noticias, deportes y cultura</a></li></ul></nav>
+<div class="sidebar"><p>Suscríbete a nuestro boletín, recibe noticias, ofertas y mucho más.</p></div>
+<article><div class="content">
+<p>En 2008, los punks y los emos se enfrentaron en la Glorieta de Insurgentes, en la Ciudad de México.</p>
+<p>Los emos, con flequillos largos, escuchaban música triste.<br>Los punks, en cambio, los acusaban de copiar su estética.</p>
+<p>Hoy, muchos de ellos recuerdan aquellos días con nostalgia, humor y algo de vergüenza.</p>
+</div></article>
+<footer><p>© 2024 El Diario, todos los derechos reservados, aviso de privacidad.</p></footer>
+</body></html>"#;
+
+    #[test]
+    fn articles_leave_out_boilerplate() {
+        assert_eq!(
+            parse_article(PAGE),
+            Article {
+                title: Some("Los emos y los punks de la Glorieta".to_string()),
+                paragraphs: vec![
+                    "En 2008, los punks y los emos se enfrentaron en la Glorieta de Insurgentes, \
+                     en la Ciudad de México."
+                        .to_string(),
+                    "Los emos, con flequillos largos, escuchaban música triste.".to_string(),
+                    "Los punks, en cambio, los acusaban de copiar su estética.".to_string(),
+                    "Hoy, muchos de ellos recuerdan aquellos días con nostalgia, humor y algo de \
+                     vergüenza."
+                        .to_string(),
+                ],
+            }
+        );
+    }
+
+    #[test]
+    fn bodies_keep_every_paragraph_but_scripts() {
+        let article = parse_body(PAGE);
+        assert_eq!(
+            article.title.as_deref(),
+            Some("Los emos y los punks de la Glorieta | El Diario")
+        );
+        assert_eq!(article.paragraphs.len(), 5);
+        assert_eq!(
+            article.paragraphs[0],
+            "Suscríbete a nuestro boletín, recibe noticias, ofertas y mucho más."
+        );
+        assert!(article.paragraphs.iter().all(|p| !p.contains("script")
+            && !p.contains("color")
+            && !p.contains("Inicio")
+            && !p.contains("reservados")));
+    }
+
+    #[test]
+    fn short_titles_keep_their_site_name() {
+        assert_eq!(
+            site_name_removed("Noticias | El Diario"),
+            "Noticias | El Diario"
+        );
+        assert_eq!(
+            site_name_removed("Una noticia muy larga - Sección - El Diario"),
+            "Una noticia muy larga - Sección"
+        );
+        assert_eq!(
+            site_name_removed("Una noticia muy larga — El Diario"),
+            "Una noticia muy larga"
+        );
+    }
+
+    #[test]
+    fn lines_and_paragraphs_split_text() {
+        let article = parse_body("<p>uno<p>dos<br/>tres<br><br>cuatro</p>cinco <b>seis</b>");
+        assert_eq!(
+            article.paragraphs,
+            vec!["uno", "dos", "tres", "cuatro", "cinco seis"]
+        );
+        assert_eq!(article.text(), "uno\n\ndos\n\ntres\n\ncuatro\n\ncinco seis");
+    }
+
+    #[test]
+    fn entities_decode() {
+        assert_eq!(
+            decode_entities(
+                "&laquo;Caf&eacute;&raquo; &amp; t&#233; &#x2014; &Eacute;xito&hellip;"
+            ),
+            "«Café» & té — Éxito…"
+        );
+        // Unknown, unterminated and invalid entities stay as they are
+        assert_eq!(
+            decode_entities("a &b; c&d &#xZZ; &#1114112; &"),
+            "a &b; c&d &#xZZ; &#1114112; &"
+        );
+        assert_eq!(decode_entities("&amplifier; &amp"), "&amplifier; &amp");
+    }
+
+    #[test]
+    fn stray_ampersands_decode_in_linear_time() {
+        let text = format!("{};", "&".repeat(200_000));
+        let start = Instant::now();
+        assert_eq!(decode_entities(&text), text);
+        assert!(start.elapsed().as_secs() < 2);
+    }
+
+    #[test]
+    fn analyzed_articles_keep_their_title() {
+        let article = parse_article(PAGE);
+        let json = analyze_article(&article, &SplitAnalyzer, "es").unwrap();
+        let document: DocumentFile = serde_json::from_str(&json).unwrap();
+        assert_eq!(document.title, article.title);
+        assert_eq!(document.sentences.len(), 4);
+    }
+}
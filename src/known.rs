@@ -0,0 +1,87 @@
+use crate::lemmatizer::LemmatizerError;
+use std::collections::HashSet;
+use std::fs;
+use std::io::ErrorKind;
+use std::iter::FromIterator;
+use std::path::Path;
+
+/// The set of lemmas a learner already knows, stored one lemma per line
+#[derive(Default, Debug, Clone)]
+pub struct KnownWords {
+    lemmas: HashSet<String>,
+}
+
+impl KnownWords {
+    pub fn new() -> KnownWords {
+        KnownWords::default()
+    }
+
+    /// Reads a known-word file. A missing file is an empty set, so a new
+    /// learner can start without creating one.
+    pub fn load_file<P: AsRef<Path>>(path: P) -> Result<KnownWords, LemmatizerError> {
+        let text = match fs::read_to_string(path) {
+            Ok(text) => text,
+            Err(e) if e.kind() == ErrorKind::NotFound => String::new(),
+            Err(e) => return Err(LemmatizerError::FileIOFailed(e)),
+        };
+        Ok(text.lines().collect())
+    }
+
+    pub fn save_file<P: AsRef<Path>>(&self, path: P) -> Result<(), LemmatizerError> {
+        let mut text = String::new();
+        for lemma in self.sorted() {
+            text.push_str(lemma);
+            text.push('\n');
+        }
+        fs::write(path, text).map_err(LemmatizerError::FileIOFailed)
+    }
+
+    pub fn contains(&self, lemma: &str) -> bool {
+        self.lemmas.contains(&lemma.to_lowercase())
+    }
+
+    pub fn insert(&mut self, lemma: &str) -> bool {
+        self.lemmas.insert(lemma.to_lowercase())
+    }
+
+    pub fn remove(&mut self, lemma: &str) -> bool {
+        self.lemmas.remove(&lemma.to_lowercase())
+    }
+
+    /// Marks a lemma known if it wasn't, unknown if it was. Returns whether
+    /// the lemma is now known.
+    pub fn toggle(&mut self, lemma: &str) -> bool {
+        if self.remove(lemma) {
+            false
+        } else {
+            self.insert(lemma)
+        }
+    }
+
+    /// Known lemmas in alphabetical order
+    pub fn sorted(&self) -> Vec<&str> {
+        let mut lemmas: Vec<&str> = self.lemmas.iter().map(String::as_str).collect();
+        lemmas.sort_unstable();
+        lemmas
+    }
+
+    pub fn len(&self) -> usize {
+        self.lemmas.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.lemmas.is_empty()
+    }
+}
+
+impl<'a> FromIterator<&'a str> for KnownWords {
+    fn from_iter<I: IntoIterator<Item = &'a str>>(iter: I) -> KnownWords {
+        KnownWords {
+            lemmas: iter
+                .into_iter()
+                .map(|line| line.trim().to_lowercase())
+                .filter(|line| !line.is_empty())
+                .collect(),
+        }
+    }
+}
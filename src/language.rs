@@ -0,0 +1,110 @@
+/// How a writing system separates words from each other.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Script {
+    /// Words are separated by spaces (Latin, Cyrillic, Greek, Korean...)
+    Spaced,
+    /// Words run together without spaces (Japanese, Chinese, Thai...)
+    Unspaced,
+}
+
+impl Script {
+    /// Whether a space goes between two adjacent tokens when rebuilding a
+    /// sentence. Punctuation never gets a leading space.
+    pub fn needs_space(self, prev: &str, next: &str, next_is_punct: bool) -> bool {
+        if next_is_punct {
+            return false;
+        }
+        match self {
+            Script::Spaced => true,
+            // Embedded latin words and numbers keep their spaces, e.g. "iPhone 13"
+            Script::Unspaced => match (prev.chars().last(), next.chars().next()) {
+                (Some(a), Some(b)) => a.is_ascii_alphanumeric() && b.is_ascii_alphanumeric(),
+                _ => false,
+            },
+        }
+    }
+}
+
+/// The language of a document, as given by the `lang` field of its JSON
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Language {
+    code: String,
+}
+
+impl Language {
+    /// Accepts ISO 639-1 codes ("ja"), BCP 47 tags ("zh-Hans") or the
+    /// english names used by `tokenizer.py` ("japanese").
+    pub fn from_tag(tag: &str) -> Language {
+        let tag = tag.trim().to_lowercase();
+        let primary = tag.split(['-', '_']).next().unwrap_or("");
+        let code = match primary {
+            "english" => "en",
+            "spanish" => "es",
+            "french" => "fr",
+            "german" => "de",
+            "italian" => "it",
+            "portuguese" => "pt",
+            "russian" => "ru",
+            "korean" => "ko",
+            "japanese" => "ja",
+            "chinese" | "mandarin" | "cantonese" => "zh",
+            "thai" => "th",
+            "lao" => "lo",
+            "khmer" => "km",
+            "burmese" => "my",
+            "tibetan" => "bo",
+            "" => "und",
+            other => other,
+        };
+        Language {
+            code: code.to_string(),
+        }
+    }
+
+    pub fn code(&self) -> &str {
+        &self.code
+    }
+
+    pub fn script(&self) -> Script {
+        match self.code.as_str() {
+            "ja" | "zh" | "yue" | "th" | "lo" | "km" | "my" | "bo" => Script::Unspaced,
+            _ => Script::Spaced,
+        }
+    }
+}
+
+impl Default for Language {
+    fn default() -> Language {
+        Language::from_tag("und")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn spaced_scripts_space_words_but_not_punctuation() {
+        assert!(Script::Spaced.needs_space("gato", "come", false));
+        assert!(Script::Spaced.needs_space(",", "y", false));
+        assert!(!Script::Spaced.needs_space("come", ".", true));
+    }
+
+    #[test]
+    fn unspaced_scripts_only_space_latin_words_and_numbers() {
+        assert!(!Script::Unspaced.needs_space("猫", "が", false));
+        assert!(!Script::Unspaced.needs_space("新しい", "iPhone", false));
+        assert!(Script::Unspaced.needs_space("iPhone", "13", false));
+        assert!(!Script::Unspaced.needs_space("食べる", "。", true));
+    }
+
+    #[test]
+    fn tags_and_names_pick_the_script() {
+        assert_eq!(Language::from_tag("ja").script(), Script::Unspaced);
+        assert_eq!(Language::from_tag("zh-Hans").script(), Script::Unspaced);
+        assert_eq!(Language::from_tag("Thai").script(), Script::Unspaced);
+        assert_eq!(Language::from_tag("es").script(), Script::Spaced);
+        assert_eq!(Language::from_tag("korean").code(), "ko");
+        assert_eq!(Language::default().script(), Script::Spaced);
+    }
+}
@@ -1,10 +1,13 @@
 use std::collections::HashMap;
 use std::fs::File;
-use std::io::BufReader;
+use std::io::{BufReader, BufWriter, Write};
+use std::ops::Range;
 use std::path::Path;
 //use cpython::{ObjectProtocol, PyModule, PyObject, PyResult, PySet, Python};
-use serde::Deserialize;
-use serde_json::Value as JsonValue;
+use crate::duplicates::DuplicateIndex;
+use crate::language::{Language, Script};
+use crate::subtitles::Timing;
+use serde::{Deserialize, Serialize};
 use thiserror::Error;
 
 pub type LemmaVecItem<'a> = (&'a String, &'a HashMap<String, Vec<(usize, usize)>>);
@@ -14,11 +17,94 @@ pub type FormVec<'a> = Vec<FormVecItem<'a>>;
 pub type LemmaMap = HashMap<String, HashMap<String, Vec<(usize, usize)>>>;
 pub type FormMap = HashMap<String, Vec<(usize, usize)>>;
 
-#[derive(Deserialize, Debug)]
-struct Token {
-    text: String,
-    lemma: String,
-    pos: String,
+/// Parts of speech that carry vocabulary, as opposed to function words
+pub const CONTENT_POS: [&str; 4] = ["NOUN", "VERB", "ADJ", "ADV"];
+
+#[derive(Deserialize, Serialize, Debug)]
+pub(crate) struct Token {
+    pub(crate) text: String,
+    pub(crate) lemma: String,
+    pub(crate) pos: String,
+}
+
+impl Token {
+    pub(crate) fn is_punct(&self) -> bool {
+        self.pos == "PUNCT"
+    }
+
+    pub(crate) fn is_content(&self) -> bool {
+        CONTENT_POS.contains(&self.pos.as_str())
+    }
+
+    /// Key of the token's lemma in the `LemmaMap`
+    pub(crate) fn lemma_key(&self) -> String {
+        self.lemma.to_lowercase()
+    }
+}
+
+/// On-disk layout of an analyzed document, as written by `tokenizer.py`
+#[derive(Deserialize, Serialize, Debug)]
+pub(crate) struct DocumentFile {
+    #[serde(default)]
+    lang: Option<String>,
+    /// Title of an imported web page or book
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) title: Option<String>,
+    /// Index of the first sentence of each paragraph
+    #[serde(default)]
+    paragraphs: Vec<usize>,
+    /// Speaker of each sentence, only written for transcripts
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    speakers: Vec<Option<String>>,
+    /// Chapters of an imported book, in order
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub(crate) sections: Vec<SectionFile>,
+    /// When each sentence is said, only for documents imported from
+    /// subtitles
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub(crate) timings: Vec<Option<Timing>>,
+    pub(crate) sentences: Vec<Vec<Token>>,
+}
+
+/// Where a chapter starts, as stored in document JSON
+#[derive(Deserialize, Serialize, Debug)]
+pub(crate) struct SectionFile {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) title: Option<String>,
+    /// Index of the section's first sentence
+    pub(crate) start: usize,
+}
+
+/// A chapter or other titled part of a document
+#[derive(Clone, Debug)]
+pub struct Section {
+    pub title: Option<String>,
+    pub sentences: Range<usize>,
+}
+
+/// A loaded document and the sentences it owns in the token array
+#[derive(Debug)]
+pub struct Document {
+    /// File name without extension
+    pub name: String,
+    /// Hash of the document's tokens, used to key per-document data so
+    /// that documents with the same name in different folders stay apart
+    pub id: String,
+    /// Title given in the document itself, as by a web page's `<title>`
+    pub title: Option<String>,
+    pub language: Language,
+    pub sentences: Range<usize>,
+    /// Sentence ranges of each paragraph, covering all of `sentences`
+    pub paragraphs: Vec<Range<usize>>,
+    /// Chapters of a book, covering all of `sentences`. Documents without
+    /// chapters are a single untitled section.
+    pub sections: Vec<Section>,
+    /// Who says each sentence of a transcript, from its speaker tags. Empty
+    /// for other texts.
+    pub speakers: Vec<Option<String>>,
+    /// When each sentence is said in the video a document was subtitled
+    /// from. Empty for other texts.
+    pub timings: Vec<Option<Timing>>,
 }
 
 pub trait WordCount {
@@ -28,7 +114,7 @@ pub trait WordCount {
 impl WordCount for FormMap {
     fn word_count(&self) -> usize {
         let mut len: usize = 0;
-        for (_, list) in self {
+        for list in self.values() {
             len += list.len();
         }
         len
@@ -38,7 +124,7 @@ impl WordCount for FormMap {
 impl WordCount for LemmaMap {
     fn word_count(&self) -> usize {
         let mut count: usize = 0;
-        for (_, form_map) in self {
+        for form_map in self.values() {
             count += form_map.word_count();
         }
         count
@@ -51,44 +137,193 @@ pub enum LemmatizerError {
     FileIOFailed(std::io::Error),
     #[error("JSON parsing failed")]
     JSONParseFailed(serde_json::Error),
+    #[error("JSON serialization failed")]
+    JSONSerializeFailed(serde_json::Error),
+    #[error("Unknown file format: {0}")]
+    UnknownFormat(String),
+    #[error("Malformed file: {0}")]
+    MalformedFile(String),
+    #[error("Text analysis failed: {0}")]
+    AnalyzerFailed(String),
 }
 
+#[derive(Default)]
 pub struct Lemmatizer {
-    tokens: Vec<Vec<Token>>,
-    lemma_map: LemmaMap,
+    pub(crate) tokens: Vec<Vec<Token>>,
+    documents: Vec<Document>,
+    pub(crate) lemma_map: LemmaMap,
+    pub(crate) duplicates: DuplicateIndex,
+    /// Whether repeated sentences are left out of `lemma_map`
+    pub(crate) skip_duplicates: bool,
 }
 
 impl Lemmatizer {
     pub fn new() -> Lemmatizer {
-        Lemmatizer {
-            tokens: Vec::new(),
-            lemma_map: HashMap::new(),
-        }
+        Lemmatizer::default()
     }
 
     pub fn load_file<P: AsRef<Path>>(
         &mut self,
         path: P,
     ) -> Result<&mut Lemmatizer, LemmatizerError> {
-        self.tokens.append(&mut tokens_from_file(path)?); // TODO Append instead of overwrite
-        self.lemma_map = map_from_array(&self.tokens);
-        Ok(self)
+        let name = path
+            .as_ref()
+            .file_stem()
+            .map_or(String::new(), |s| s.to_string_lossy().to_string());
+        let file = document_from_file(path)?;
+        Ok(self.add_document(name, file))
+    }
+
+    /// Adds a document from JSON in the layout `tokenizer.py` writes
+    pub fn load_json(
+        &mut self,
+        name: &str,
+        json: &str,
+    ) -> Result<&mut Lemmatizer, LemmatizerError> {
+        let file = serde_json::from_str(json).map_err(LemmatizerError::JSONParseFailed)?;
+        Ok(self.add_document(name.to_string(), file))
+    }
+
+    fn add_document(&mut self, name: String, file: DocumentFile) -> &mut Lemmatizer {
+        let start = self.tokens.len();
+        let end = start + file.sentences.len();
+        self.tokens.extend(file.sentences);
+        for sentence_i in start..end {
+            self.duplicates.add(sentence_i, &self.tokens[sentence_i]);
+        }
+        self.documents.push(Document {
+            name,
+            id: content_id(&self.tokens[start..end]),
+            title: file.title,
+            language: file
+                .lang
+                .as_deref()
+                .map(Language::from_tag)
+                .unwrap_or_default(),
+            sentences: start..end,
+            paragraphs: paragraph_ranges(&file.paragraphs, start, end),
+            sections: section_ranges(file.sections, start, end),
+            speakers: file.speakers,
+            timings: file.timings,
+        });
+        self.update_lemma_map();
+        self
+    }
+
+    /// Sentences of a range that count towards frequencies, with their
+    /// indices
+    pub(crate) fn counted_sentences(
+        &self,
+        sentences: Range<usize>,
+    ) -> impl Iterator<Item = (usize, &Vec<Token>)> {
+        sentences
+            .filter(move |sentence_i| !self.is_skipped(*sentence_i))
+            .map(move |sentence_i| (sentence_i, &self.tokens[sentence_i]))
+    }
+
+    pub(crate) fn update_lemma_map(&mut self) {
+        let skipped: Vec<bool> = (0..self.tokens.len())
+            .map(|sentence_i| self.is_skipped(sentence_i))
+            .collect();
+        self.lemma_map = map_from_array(&self.tokens, &skipped);
+    }
+
+    pub fn documents(&self) -> &[Document] {
+        &self.documents
+    }
+
+    /// The document a sentence belongs to and the sentence's index in it
+    pub fn locate_sentence(&self, sentence_i: usize) -> Option<(&Document, usize)> {
+        self.documents
+            .iter()
+            .find(|doc| doc.sentences.contains(&sentence_i))
+            .map(|doc| (doc, sentence_i - doc.sentences.start))
     }
 
+    /// The language of the document a sentence belongs to
+    pub fn language_of(&self, sentence_i: usize) -> Language {
+        self.documents
+            .iter()
+            .find(|doc| doc.sentences.contains(&sentence_i))
+            .map(|doc| doc.language.clone())
+            .unwrap_or_default()
+    }
+
+    /// Who says a sentence, in a transcript with speaker tags
+    pub fn speaker_of(&self, sentence_i: usize) -> Option<&str> {
+        let (document, i) = self.locate_sentence(sentence_i)?;
+        document.speakers.get(i)?.as_deref()
+    }
+
+    /// When a sentence is said, in a document imported from subtitles
+    pub fn timing_of(&self, sentence_i: usize) -> Option<Timing> {
+        let (document, i) = self.locate_sentence(sentence_i)?;
+        *document.timings.get(i)?
+    }
+
+    /// Speakers of all transcripts and how many sentences each says, most
+    /// first
+    pub fn get_speakers(&self) -> Vec<(String, usize)> {
+        let mut counts: HashMap<&str, usize> = HashMap::new();
+        for speaker in self
+            .documents
+            .iter()
+            .flat_map(|doc| doc.speakers.iter().flatten())
+        {
+            *counts.entry(speaker).or_insert(0) += 1;
+        }
+        let mut counts: Vec<(String, usize)> = counts
+            .into_iter()
+            .map(|(speaker, count)| (speaker.to_string(), count))
+            .collect();
+        counts.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        counts
+    }
+
+    /// Lemmas, most used first. Ties are alphabetical so rankings are the
+    /// same from run to run.
     pub fn get_lemmas(&self) -> Vec<String> {
         let mut lemma_vec: LemmaVec = self.lemma_map.iter().collect();
-        lemma_vec.sort_by(|a, b| b.1.word_count().cmp(&a.1.word_count()));
+        lemma_vec.sort_by(|a, b| {
+            b.1.word_count()
+                .cmp(&a.1.word_count())
+                .then_with(|| a.0.cmp(b.0))
+        });
         lemma_vec
             .into_iter()
             .map(|(lemma, _)| lemma.to_string())
             .collect()
     }
 
+    /// Number of times any form of a lemma is used
+    pub fn get_lemma_count(&self, lemma: &str) -> usize {
+        self.lemma_map.get(lemma).map_or(0, |map| map.word_count())
+    }
+
+    /// Parts of speech a lemma is tagged with and how often, most common
+    /// first
+    pub fn get_lemma_pos(&self, lemma: &str) -> Vec<(String, usize)> {
+        let mut counts: HashMap<&str, usize> = HashMap::new();
+        if let Some(map) = self.lemma_map.get(lemma) {
+            for (sentence_i, token_i) in map.values().flatten() {
+                *counts
+                    .entry(&self.tokens[*sentence_i][*token_i].pos)
+                    .or_insert(0) += 1;
+            }
+        }
+        let mut counts: Vec<(String, usize)> = counts
+            .into_iter()
+            .map(|(pos, count)| (pos.to_string(), count))
+            .collect();
+        counts.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        counts
+    }
+
     pub fn get_forms(&self, lemma: &str) -> Vec<String> {
         match self.lemma_map.get(lemma) {
             Some(map) => {
-                let mut form_vec: FormVec = map.into_iter().collect();
-                form_vec.sort_by(|a, b| b.1.len().cmp(&a.1.len()));
+                let mut form_vec: FormVec = map.iter().collect();
+                form_vec.sort_by_key(|b| std::cmp::Reverse(b.1.len()));
                 form_vec
                     .into_iter()
                     .map(|(form, _)| form.to_string())
@@ -99,49 +334,149 @@ impl Lemmatizer {
     }
 
     pub fn get_usages(&self, lemma: &str, form: &str) -> Vec<(String, String, String)> {
+        self.get_usage_positions(lemma, form)
+            .into_iter()
+            .map(|(sentence_i, token_i)| self.get_usage(sentence_i, token_i))
+            .collect()
+    }
+
+    /// Where a form of a lemma is used, as (sentence, token) in text order
+    pub fn get_usage_positions(&self, lemma: &str, form: &str) -> Vec<(usize, usize)> {
         match self.lemma_map.get(lemma) {
             Some(map) => match map.get(form) {
-                Some(forms) => forms
-                    .into_iter()
-                    .map(|(sentence_i, token_i)| {
-                        get_sentence_split(&self.tokens, *sentence_i, *token_i)
-                    })
-                    .collect(),
+                Some(forms) => forms.clone(),
                 None => Vec::new(),
             },
             None => Vec::new(),
         }
     }
+
+    /// Usages of every form of a lemma, in text order
+    pub fn get_lemma_usages(&self, lemma: &str) -> Vec<(String, String, String)> {
+        match self.lemma_map.get(lemma) {
+            Some(map) => {
+                let mut positions: Vec<&(usize, usize)> = map.values().flatten().collect();
+                positions.sort();
+                positions
+                    .into_iter()
+                    .map(|(sentence_i, token_i)| self.get_usage(*sentence_i, *token_i))
+                    .collect()
+            }
+            None => Vec::new(),
+        }
+    }
+
+    pub fn sentence_count(&self) -> usize {
+        self.tokens.len()
+    }
+
+    /// A whole sentence rebuilt from its tokens
+    pub fn get_sentence(&self, sentence_i: usize) -> String {
+        let script = self.language_of(sentence_i).script();
+        let sentence = &self.tokens[sentence_i];
+        let mut text = String::new();
+        for (i, token) in sentence.iter().enumerate() {
+            if i != 0 && script.needs_space(&sentence[i - 1].text, &token.text, token.is_punct()) {
+                text.push(' ');
+            }
+            text.push_str(&token.text);
+        }
+        text
+    }
+
+    /// The sentence around a single token, split as (before, word, after)
+    pub fn get_usage(&self, sentence_i: usize, token_i: usize) -> (String, String, String) {
+        let script = self.language_of(sentence_i).script();
+        get_sentence_split(&self.tokens, script, sentence_i, token_i)
+    }
 }
 
-fn tokens_from_file<P: AsRef<Path>>(path: P) -> Result<Vec<Vec<Token>>, LemmatizerError> {
-    let file = File::open(path).map_err(|e| LemmatizerError::FileIOFailed(e))?;
+fn document_from_file<P: AsRef<Path>>(path: P) -> Result<DocumentFile, LemmatizerError> {
+    let file = File::open(path).map_err(LemmatizerError::FileIOFailed)?;
     let reader = BufReader::new(file);
-    let values: JsonValue =
-        serde_json::from_reader(reader).map_err(|e| LemmatizerError::JSONParseFailed(e))?;
-    let sentences: Vec<JsonValue> = values
-        .get("sentences")
-        .unwrap()
-        .as_array()
-        .unwrap()
-        .to_vec();
-
-    let mut token_array: Vec<Vec<Token>> = Vec::new();
+    serde_json::from_reader(reader).map_err(LemmatizerError::JSONParseFailed)
+}
 
-    for (sentence_i, sentence) in sentences.iter().enumerate() {
-        token_array.push(Vec::new());
+/// Writes `value` to a file as pretty-printed JSON
+pub(crate) fn save_json<T: Serialize, P: AsRef<Path>>(
+    value: &T,
+    path: P,
+) -> Result<(), LemmatizerError> {
+    let file = File::create(path).map_err(LemmatizerError::FileIOFailed)?;
+    let mut writer = BufWriter::new(file);
+    serde_json::to_writer_pretty(&mut writer, value).map_err(|e| match e.is_io() {
+        true => LemmatizerError::FileIOFailed(e.into()),
+        false => LemmatizerError::JSONSerializeFailed(e),
+    })?;
+    writer.flush().map_err(LemmatizerError::FileIOFailed)
+}
 
-        let tokens: Vec<JsonValue> = sentence.as_array().unwrap().to_vec();
-        for token in tokens.into_iter() {
-            let token_struct: Token = serde_json::from_value(token).unwrap();
-            token_array[sentence_i].push(token_struct);
+/// 64-bit FNV-1a hash of every token, in hex. Unlike `DefaultHasher` it's
+/// the same from one build to the next, so it can be saved.
+fn content_id(sentences: &[Vec<Token>]) -> String {
+    const OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+    const PRIME: u64 = 0x0000_0100_0000_01b3;
+    let mut hash = OFFSET_BASIS;
+    let mut write = |bytes: &[u8]| {
+        for byte in bytes {
+            hash = (hash ^ u64::from(*byte)).wrapping_mul(PRIME);
+        }
+    };
+    for sentence in sentences {
+        for token in sentence {
+            for field in [&token.text, &token.lemma, &token.pos] {
+                write(field.as_bytes());
+                // Separates fields, as it can't appear in UTF-8
+                write(&[0xff]);
+            }
         }
+        write(&[0xfe]);
+    }
+    format!("{:016x}", hash)
+}
+
+/// Turns paragraph start indices local to a document into global sentence
+/// ranges. Documents without paragraph data are a single paragraph.
+fn paragraph_ranges(starts: &[usize], start: usize, end: usize) -> Vec<Range<usize>> {
+    let mut starts: Vec<usize> = starts
+        .iter()
+        .map(|i| start + i)
+        .filter(|i| *i > start && *i < end)
+        .collect();
+    starts.sort_unstable();
+    starts.dedup();
+    starts.insert(0, start);
+    starts
+        .iter()
+        .zip(starts.iter().skip(1).chain(std::iter::once(&end)))
+        .map(|(a, b)| *a..*b)
+        .filter(|range| !range.is_empty())
+        .collect()
+}
+
+/// Turns section starts local to a document into global sentence ranges,
+/// leaving out sections without sentences
+fn section_ranges(files: Vec<SectionFile>, start: usize, end: usize) -> Vec<Section> {
+    let mut sections = vec![Section {
+        title: None,
+        sentences: start..end,
+    }];
+    for file in files {
+        let last = sections.last_mut().unwrap();
+        let section_start = (start + file.start).clamp(last.sentences.start, end);
+        last.sentences.end = section_start;
+        sections.push(Section {
+            title: file.title,
+            sentences: section_start..end,
+        });
     }
-    Ok(token_array)
+    // The untitled first section only holds text before the first chapter
+    sections.retain(|section| !section.sentences.is_empty());
+    sections
 }
 
 fn add_to_map(token: &Token, pos: (usize, usize), lemma_map: &mut LemmaMap) {
-    let lemma_key = token.lemma.to_lowercase();
+    let lemma_key = token.lemma_key();
     let form_key = token.text.to_lowercase();
 
     match lemma_map.get_mut(&lemma_key) {
@@ -157,23 +492,29 @@ fn add_to_map(token: &Token, pos: (usize, usize), lemma_map: &mut LemmaMap) {
     }
 }
 
-fn map_from_array(token_array: &Vec<Vec<Token>>) -> LemmaMap {
+/// Maps lemmas to where their forms are used, except in `skipped`
+/// sentences
+fn map_from_array(token_array: &[Vec<Token>], skipped: &[bool]) -> LemmaMap {
     let mut lemma_map: LemmaMap = HashMap::new();
 
     for (sentence_i, sentence) in token_array.iter().enumerate() {
+        if skipped[sentence_i] {
+            continue;
+        }
         for (token_i, token) in sentence.iter().enumerate() {
-            if token.pos == "PUNCT" {
+            if token.is_punct() {
                 continue;
             }
 
-            add_to_map(&token, (sentence_i, token_i), &mut lemma_map);
+            add_to_map(token, (sentence_i, token_i), &mut lemma_map);
         }
     }
     lemma_map
 }
 
 fn get_sentence_split(
-    token_array: &Vec<Vec<Token>>,
+    token_array: &[Vec<Token>],
+    script: Script,
     sentence_i: usize,
     token_i: usize,
 ) -> (String, String, String) {
@@ -181,11 +522,12 @@ fn get_sentence_split(
     let word: String = String::from(&token_array[sentence_i][token_i].text);
     let mut after: String = String::from("");
 
-    for (i, token) in token_array[sentence_i].iter().enumerate() {
-        if i != 0 && token.pos != "PUNCT" {
-            if i < token_i {
+    let sentence = &token_array[sentence_i];
+    for (i, token) in sentence.iter().enumerate() {
+        if i != 0 && script.needs_space(&sentence[i - 1].text, &token.text, token.is_punct()) {
+            if i <= token_i {
                 before.push(' ');
-            } else if i > token_i {
+            } else {
                 after.push(' ');
             }
         }
@@ -195,27 +537,65 @@ fn get_sentence_split(
             after.push_str(&token.text);
         }
     }
-    before.push(' ');
     (before, word, after)
 }
 
-fn get_sentence_bolded(token_array: &Vec<Vec<Token>>, sentence_i: usize, token_i: usize) -> String {
-    let mut sentence: String = String::from("");
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-    for (i, token) in token_array[sentence_i].iter().enumerate() {
-        if i != 0 && token.pos != "PUNCT" {
-            sentence.push(' ');
-        }
-        if i == token_i {
-            sentence.push(0o033 as char);
-            sentence.push_str("[1;4m");
-        }
-        sentence.push_str(&token.text);
-        if i == token_i {
-            sentence.push(0o033 as char);
-            sentence.push_str("[22;24m");
-        }
+    const SPANISH: &str = r#"{"lang": "es", "sentences": [[
+        {"text": "¡", "lemma": "¡", "pos": "PUNCT"},
+        {"text": "Hola", "lemma": "hola", "pos": "INTJ"},
+        {"text": ",", "lemma": ",", "pos": "PUNCT"},
+        {"text": "mundo", "lemma": "mundo", "pos": "NOUN"},
+        {"text": "!", "lemma": "!", "pos": "PUNCT"}
+    ]]}"#;
+
+    const JAPANESE: &str = r#"{"lang": "ja", "sentences": [[
+        {"text": "猫", "lemma": "猫", "pos": "NOUN"},
+        {"text": "が", "lemma": "が", "pos": "ADP"},
+        {"text": "iPhone", "lemma": "iPhone", "pos": "PROPN"},
+        {"text": "13", "lemma": "13", "pos": "NUM"},
+        {"text": "を", "lemma": "を", "pos": "ADP"},
+        {"text": "見る", "lemma": "見る", "pos": "VERB"},
+        {"text": "。", "lemma": "。", "pos": "PUNCT"}
+    ]]}"#;
+
+    fn lemmatizer() -> Lemmatizer {
+        let mut lemmatizer = Lemmatizer::new();
+        lemmatizer.load_json("es", SPANISH).unwrap();
+        lemmatizer.load_json("ja", JAPANESE).unwrap();
+        lemmatizer
     }
 
-    sentence
+    #[test]
+    fn spaced_sentences_join_words_with_spaces() {
+        // Opening punctuation isn't special cased, so it keeps the space
+        // before the next word
+        assert_eq!(lemmatizer().get_sentence(0), "¡ Hola, mundo!");
+    }
+
+    #[test]
+    fn unspaced_sentences_join_words_directly() {
+        assert_eq!(lemmatizer().get_sentence(1), "猫がiPhone 13を見る。");
+    }
+
+    #[test]
+    fn usages_split_around_the_word_by_script() {
+        let lemmatizer = lemmatizer();
+        assert_eq!(
+            lemmatizer.get_usage(0, 3),
+            // The space before the word stays with the text before it
+            ("¡ Hola, ".to_string(), "mundo".to_string(), "!".to_string())
+        );
+        assert_eq!(
+            lemmatizer.get_usage(1, 5),
+            (
+                "猫がiPhone 13を".to_string(),
+                "見る".to_string(),
+                "。".to_string()
+            )
+        );
+    }
 }
@@ -1 +1,28 @@
+pub mod analyzer;
+pub mod apkg;
+pub mod compare;
+pub mod coverage;
+pub mod dictionary;
+pub mod dispersion;
+pub mod duplicates;
+pub mod epub;
+pub mod examples;
+pub mod export;
+pub mod ffi;
+pub mod html;
+pub mod known;
+pub mod language;
 pub mod lemmatizer;
+pub mod metrics;
+pub mod mining;
+pub mod notes;
+pub mod plan;
+pub mod review;
+pub mod sections;
+pub mod spans;
+pub mod stats;
+pub mod subtitles;
+mod zip;
+
+#[cfg(feature = "python")]
+pub mod python;
@@ -0,0 +1,293 @@
+use crate::lemmatizer::Lemmatizer;
+use crate::stats::CorpusStats;
+use std::collections::{HashMap, HashSet};
+use std::ops::Range;
+
+/// Tokens in each window of the moving-average type-token ratio
+pub const MATTR_WINDOW: usize = 50;
+/// Type-token ratio at which MTLD closes a factor, from McCarthy and Jarvis
+pub const MTLD_THRESHOLD: f64 = 0.72;
+
+/// Heaps' law, `lemmas = k * tokens^beta`, fitted to how the vocabulary
+/// grows through a text
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct HeapsLaw {
+    pub k: f32,
+    pub beta: f32,
+}
+
+impl HeapsLaw {
+    /// Distinct lemmas expected after `tokens` tokens
+    pub fn predict(&self, tokens: usize) -> f32 {
+        self.k * (tokens as f32).powf(self.beta)
+    }
+}
+
+/// Lexical richness of a text, counted on the lemmas of its word tokens
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct LexicalMetrics {
+    /// Tokens, distinct lemmas, hapax legomena and the type-token ratio
+    pub stats: CorpusStats,
+    /// Lemmas used exactly twice
+    pub dis_legomena: usize,
+    /// Mean type-token ratio of every run of `MATTR_WINDOW` tokens, which
+    /// unlike the plain ratio doesn't fall as texts get longer
+    pub mattr: f32,
+    /// Mean length of the runs of tokens it takes the type-token ratio to
+    /// fall to `MTLD_THRESHOLD`, read forwards and backwards
+    pub mtld: f32,
+    /// Needs at least two tokens
+    pub heaps: Option<HeapsLaw>,
+    /// `s` in `frequency ∝ rank^-s`. Needs at least two lemmas.
+    pub zipf_exponent: Option<f32>,
+}
+
+impl LexicalMetrics {
+    /// Metrics of a stream of lemmas, in text order, whose size `stats`
+    /// already counts
+    pub fn new(stats: CorpusStats, lemmas: &[&str]) -> LexicalMetrics {
+        let mut counts: HashMap<&str, usize> = HashMap::new();
+        // Distinct lemmas after each token, to fit Heaps' law to
+        let mut growth = Vec::with_capacity(lemmas.len());
+        for lemma in lemmas {
+            *counts.entry(lemma).or_insert(0) += 1;
+            growth.push(counts.len());
+        }
+
+        let mut frequencies: Vec<usize> = counts.values().copied().collect();
+        frequencies.sort_unstable_by(|a, b| b.cmp(a));
+        let zipf_points: Vec<(f64, f64)> = frequencies
+            .iter()
+            .enumerate()
+            .map(|(i, count)| ((i + 1) as f64, *count as f64))
+            .collect();
+
+        LexicalMetrics {
+            stats,
+            dis_legomena: frequencies.iter().filter(|count| **count == 2).count(),
+            mattr: match lemmas.len() {
+                tokens if tokens <= MATTR_WINDOW => stats.type_token_ratio(),
+                _ => mattr(lemmas, MATTR_WINDOW),
+            },
+            mtld: mtld(lemmas),
+            heaps: fit_power_law(&heaps_points(&growth)).map(|(k, beta)| HeapsLaw { k, beta }),
+            zipf_exponent: fit_power_law(&zipf_points).map(|(_, exponent)| -exponent),
+        }
+    }
+}
+
+/// Samples of the vocabulary growth curve, spaced evenly on a log scale so
+/// the long tail of the text doesn't outweigh its start
+fn heaps_points(growth: &[usize]) -> Vec<(f64, f64)> {
+    let mut points = Vec::new();
+    let mut n = 1.0_f64;
+    let mut last = 0;
+    while (n as usize) <= growth.len() {
+        let tokens = n as usize;
+        if tokens != last {
+            points.push((tokens as f64, growth[tokens - 1] as f64));
+            last = tokens;
+        }
+        n *= 1.05;
+    }
+    if last != growth.len() && !growth.is_empty() {
+        points.push((growth.len() as f64, growth[growth.len() - 1] as f64));
+    }
+    points
+}
+
+/// Least-squares fit of `y = coefficient * x^exponent` on log-log axes, as
+/// (coefficient, exponent). Needs two distinct `x`.
+fn fit_power_law(points: &[(f64, f64)]) -> Option<(f32, f32)> {
+    if points.len() < 2 {
+        return None;
+    }
+    let logs: Vec<(f64, f64)> = points.iter().map(|(x, y)| (x.ln(), y.ln())).collect();
+    let n = logs.len() as f64;
+    let mean_x = logs.iter().map(|(x, _)| x).sum::<f64>() / n;
+    let mean_y = logs.iter().map(|(_, y)| y).sum::<f64>() / n;
+    let sxx: f64 = logs.iter().map(|(x, _)| (x - mean_x).powi(2)).sum();
+    let sxy: f64 = logs.iter().map(|(x, y)| (x - mean_x) * (y - mean_y)).sum();
+    if sxx == 0.0 {
+        return None;
+    }
+    let exponent = sxy / sxx;
+    let coefficient = (mean_y - exponent * mean_x).exp();
+    Some((coefficient as f32, exponent as f32))
+}
+
+fn mattr(lemmas: &[&str], window: usize) -> f32 {
+    let mut counts: HashMap<&str, usize> = HashMap::new();
+    for lemma in &lemmas[..window] {
+        *counts.entry(lemma).or_insert(0) += 1;
+    }
+    let mut total = counts.len();
+    for i in window..lemmas.len() {
+        *counts.entry(lemmas[i]).or_insert(0) += 1;
+        let dropped = counts.get_mut(lemmas[i - window]).unwrap();
+        *dropped -= 1;
+        if *dropped == 0 {
+            counts.remove(lemmas[i - window]);
+        }
+        total += counts.len();
+    }
+    total as f32 / (lemmas.len() - window + 1) as f32 / window as f32
+}
+
+fn mtld(lemmas: &[&str]) -> f32 {
+    let forward = mtld_factors(lemmas.iter());
+    let backward = mtld_factors(lemmas.iter().rev());
+    let tokens = lemmas.len() as f64;
+    // A text that never falls to the threshold is as long as its factor
+    let length = |factors: f64| match factors {
+        f if f > 0.0 => tokens / f,
+        _ => tokens,
+    };
+    ((length(forward) + length(backward)) / 2.0) as f32
+}
+
+/// Number of runs it takes the type-token ratio to fall to the threshold,
+/// counting the unfinished last run by how far it got
+fn mtld_factors<'a>(lemmas: impl Iterator<Item = &'a &'a str>) -> f64 {
+    let mut factors = 0.0;
+    let mut types = HashSet::new();
+    let mut tokens = 0;
+    for lemma in lemmas {
+        types.insert(*lemma);
+        tokens += 1;
+        if (types.len() as f64 / tokens as f64) <= MTLD_THRESHOLD {
+            factors += 1.0;
+            types.clear();
+            tokens = 0;
+        }
+    }
+    if tokens > 0 {
+        let ratio = types.len() as f64 / tokens as f64;
+        factors += (1.0 - ratio) / (1.0 - MTLD_THRESHOLD);
+    }
+    factors
+}
+
+impl Lemmatizer {
+    /// Lexical richness of the whole library
+    pub fn get_metrics(&self) -> LexicalMetrics {
+        self.get_range_metrics(0..self.tokens.len())
+    }
+
+    /// Lexical richness of the document at `document_i`, or `None` if there
+    /// is no such document
+    pub fn get_document_metrics(&self, document_i: usize) -> Option<LexicalMetrics> {
+        let document = self.documents().get(document_i)?;
+        Some(self.get_range_metrics(document.sentences.clone()))
+    }
+
+    pub fn get_range_metrics(&self, sentences: Range<usize>) -> LexicalMetrics {
+        let keys: Vec<String> = self
+            .counted_sentences(sentences.clone())
+            .flat_map(|(_, sentence)| sentence)
+            .filter(|t| !t.is_punct())
+            .map(|t| t.lemma_key())
+            .collect();
+        let lemmas: Vec<&str> = keys.iter().map(String::as_str).collect();
+        LexicalMetrics::new(self.get_range_stats(sentences), &lemmas)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(value: f32, expected: f32) {
+        assert!(
+            (value - expected).abs() < 1e-4,
+            "{} isn't {}",
+            value,
+            expected
+        );
+    }
+
+    /// `n` different lemmas
+    fn distinct(n: usize) -> Vec<String> {
+        (0..n).map(|i| format!("w{}", i)).collect()
+    }
+
+    fn as_strs(lemmas: &[String]) -> Vec<&str> {
+        lemmas.iter().map(String::as_str).collect()
+    }
+
+    #[test]
+    fn mattr_averages_each_window() {
+        assert_close(mattr(&["a"; 100], MATTR_WINDOW), 1.0 / MATTR_WINDOW as f32);
+        assert_close(mattr(&as_strs(&distinct(60)), MATTR_WINDOW), 1.0);
+        // Windows "a a" and "a b" have one and two lemmas
+        assert_close(mattr(&["a", "a", "b"], 2), 0.75);
+    }
+
+    #[test]
+    fn mtld_counts_runs_down_to_the_threshold() {
+        // Every second token brings the ratio down to 1/2
+        assert_close(mtld(&["a"; 10]), 2.0);
+        // A text that never repeats is one unfinished run as long as itself
+        assert_close(mtld(&as_strs(&distinct(10))), 10.0);
+        // Forwards "a b a" falls to 2/3 on the third token, and the "b"
+        // left is a run that got nowhere; backwards likewise
+        assert_close(mtld(&["a", "b", "a", "b"]), 4.0);
+    }
+
+    #[test]
+    fn power_laws_fit_exact_series() {
+        let points: Vec<(f64, f64)> = (1..10).map(|x| (x as f64, 3.0 * (x * x) as f64)).collect();
+        let (coefficient, exponent) = fit_power_law(&points).unwrap();
+        assert_close(coefficient, 3.0);
+        assert_close(exponent, 2.0);
+        assert_eq!(fit_power_law(&[(1.0, 1.0)]), None);
+        assert_eq!(fit_power_law(&[(2.0, 1.0), (2.0, 3.0)]), None);
+    }
+
+    #[test]
+    fn zipf_exponent_of_a_perfect_series_is_one() {
+        // The lemma of rank r is used 60 / r times
+        let mut lemmas = Vec::new();
+        for (rank, lemma) in ["a", "b", "c", "d", "e", "f"].iter().enumerate() {
+            lemmas.extend(std::iter::repeat_n(*lemma, 60 / (rank + 1)));
+        }
+        let metrics = LexicalMetrics::new(CorpusStats::default(), &lemmas);
+        assert_close(metrics.zipf_exponent.unwrap(), 1.0);
+        assert_eq!(metrics.dis_legomena, 0);
+    }
+
+    #[test]
+    fn heaps_law_of_a_text_that_never_repeats_is_linear() {
+        let lemmas = distinct(200);
+        let metrics = LexicalMetrics::new(CorpusStats::default(), &as_strs(&lemmas));
+        let heaps = metrics.heaps.unwrap();
+        assert_close(heaps.k, 1.0);
+        assert_close(heaps.beta, 1.0);
+        assert_close(heaps.predict(500), 500.0);
+        assert_eq!(
+            LexicalMetrics::new(CorpusStats::default(), &["a"]).heaps,
+            None
+        );
+    }
+
+    #[test]
+    fn document_metrics_need_a_document() {
+        let mut lemmatizer = Lemmatizer::new();
+        lemmatizer
+            .load_json(
+                "uno",
+                r#"{"sentences": [[
+                    {"text": "uno", "lemma": "uno", "pos": "NUM"},
+                    {"text": "dos", "lemma": "dos", "pos": "NUM"},
+                    {"text": "dos", "lemma": "dos", "pos": "NUM"}
+                ]]}"#,
+            )
+            .unwrap();
+        let metrics = lemmatizer.get_document_metrics(0).unwrap();
+        assert_eq!((metrics.stats.tokens, metrics.stats.hapax), (3, 1));
+        assert_eq!(metrics.dis_legomena, 1);
+        // Too short for a window, so the plain ratio
+        assert_close(metrics.mattr, 2.0 / 3.0);
+        assert_eq!(lemmatizer.get_document_metrics(1), None);
+    }
+}
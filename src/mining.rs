@@ -0,0 +1,83 @@
+use crate::known::KnownWords;
+use crate::lemmatizer::{Lemmatizer, WordCount};
+use std::collections::HashMap;
+
+/// An "i+1" sentence: every content lemma in it is known except the target
+#[derive(Clone, Debug)]
+pub struct MinedSentence {
+    pub sentence_i: usize,
+    pub token_i: usize,
+    /// The target word as it appears in the sentence
+    pub form: String,
+    /// Number of non-punctuation tokens
+    pub length: usize,
+    pub usage: (String, String, String),
+}
+
+/// All i+1 sentences that teach the same unknown lemma
+#[derive(Clone, Debug)]
+pub struct MinedLemma {
+    pub lemma: String,
+    /// Occurrences of the lemma in the whole text
+    pub frequency: usize,
+    pub sentences: Vec<MinedSentence>,
+}
+
+impl Lemmatizer {
+    /// Finds sentences with exactly one unknown content lemma, grouped by
+    /// that lemma. Groups come most frequent lemma first and sentences
+    /// shortest first.
+    pub fn get_mined_sentences(&self, known: &KnownWords) -> Vec<MinedLemma> {
+        let mut groups: HashMap<String, Vec<MinedSentence>> = HashMap::new();
+
+        for (sentence_i, sentence) in self.counted_sentences(0..self.tokens.len()) {
+            let mut target: Option<(usize, String)> = None;
+            let mut unknown_count = 0;
+            for (token_i, token) in sentence.iter().enumerate() {
+                if !token.is_content() {
+                    continue;
+                }
+                let lemma = token.lemma_key();
+                if known.contains(&lemma) {
+                    continue;
+                }
+                match &target {
+                    Some((_, target_lemma)) if *target_lemma == lemma => {}
+                    _ => {
+                        unknown_count += 1;
+                        target = Some((token_i, lemma));
+                    }
+                }
+            }
+
+            if let (1, Some((token_i, lemma))) = (unknown_count, target) {
+                groups.entry(lemma).or_default().push(MinedSentence {
+                    sentence_i,
+                    token_i,
+                    form: sentence[token_i].text.to_lowercase(),
+                    length: sentence.iter().filter(|token| !token.is_punct()).count(),
+                    usage: self.get_usage(sentence_i, token_i),
+                });
+            }
+        }
+
+        let mut mined: Vec<MinedLemma> = groups
+            .into_iter()
+            .map(|(lemma, mut sentences)| {
+                sentences.sort_by_key(|s| (s.length, s.sentence_i));
+                MinedLemma {
+                    frequency: self.lemma_map.get(&lemma).map_or(0, |m| m.word_count()),
+                    lemma,
+                    sentences,
+                }
+            })
+            .collect();
+        mined.sort_by(|a, b| {
+            b.frequency
+                .cmp(&a.frequency)
+                .then_with(|| a.sentences[0].length.cmp(&b.sentences[0].length))
+                .then_with(|| a.lemma.cmp(&b.lemma))
+        });
+        mined
+    }
+}
@@ -0,0 +1,98 @@
+use crate::export::Card;
+use crate::lemmatizer::{save_json, Lemmatizer, LemmatizerError};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::{BufReader, ErrorKind};
+use std::path::Path;
+
+/// The learner's own glosses for lemmas and notes on usage sentences
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct Annotations {
+    /// Gloss by lemma
+    glosses: BTreeMap<String, String>,
+    /// Note by document id, then by sentence index within the document
+    notes: BTreeMap<String, BTreeMap<usize, String>>,
+}
+
+impl Annotations {
+    pub fn new() -> Annotations {
+        Annotations::default()
+    }
+
+    /// Reads annotations saved with `save_file`. A missing file has none.
+    pub fn load_file<P: AsRef<Path>>(path: P) -> Result<Annotations, LemmatizerError> {
+        let file = match File::open(path) {
+            Ok(file) => file,
+            Err(e) if e.kind() == ErrorKind::NotFound => return Ok(Annotations::new()),
+            Err(e) => return Err(LemmatizerError::FileIOFailed(e)),
+        };
+        serde_json::from_reader(BufReader::new(file)).map_err(LemmatizerError::JSONParseFailed)
+    }
+
+    pub fn save_file<P: AsRef<Path>>(&self, path: P) -> Result<(), LemmatizerError> {
+        save_json(self, path)
+    }
+
+    pub fn gloss(&self, lemma: &str) -> Option<&str> {
+        self.glosses.get(lemma).map(String::as_str)
+    }
+
+    /// Sets the gloss of a lemma; an empty gloss removes it
+    pub fn set_gloss(&mut self, lemma: &str, gloss: &str) {
+        match gloss.trim() {
+            "" => self.glosses.remove(lemma),
+            gloss => self.glosses.insert(lemma.to_string(), gloss.to_string()),
+        };
+    }
+
+    /// Note on a sentence of the document with the given `Document::id`
+    pub fn note(&self, document: &str, sentence_i: usize) -> Option<&str> {
+        self.notes
+            .get(document)
+            .and_then(|notes| notes.get(&sentence_i))
+            .map(String::as_str)
+    }
+
+    /// Sets the note on a sentence of a document; an empty note removes it
+    pub fn set_note(&mut self, document: &str, sentence_i: usize, note: &str) {
+        match note.trim() {
+            "" => {
+                if let Some(notes) = self.notes.get_mut(document) {
+                    notes.remove(&sentence_i);
+                    if notes.is_empty() {
+                        self.notes.remove(document);
+                    }
+                }
+            }
+            note => {
+                self.notes
+                    .entry(document.to_string())
+                    .or_default()
+                    .insert(sentence_i, note.to_string());
+            }
+        }
+    }
+
+    /// Note on a sentence given by its index in the lemmatizer
+    pub fn sentence_note(&self, lemmatizer: &Lemmatizer, sentence_i: usize) -> Option<&str> {
+        let (document, sentence_i) = lemmatizer.locate_sentence(sentence_i)?;
+        self.note(&document.id, sentence_i)
+    }
+
+    pub fn set_sentence_note(&mut self, lemmatizer: &Lemmatizer, sentence_i: usize, note: &str) {
+        if let Some((document, sentence_i)) = lemmatizer.locate_sentence(sentence_i) {
+            self.set_note(&document.id, sentence_i, note);
+        }
+    }
+
+    /// Fills in a card's gloss and the note on the sentence it was taken from
+    pub fn annotate(&self, card: Card, lemmatizer: &Lemmatizer, sentence_i: usize) -> Card {
+        let gloss = self.gloss(&card.lemma).unwrap_or("").to_string();
+        let note = self
+            .sentence_note(lemmatizer, sentence_i)
+            .unwrap_or("")
+            .to_string();
+        card.with_notes(gloss, note)
+    }
+}
@@ -0,0 +1,177 @@
+use crate::coverage::{is_known, Coverage};
+use crate::export::Card;
+use crate::known::KnownWords;
+use crate::lemmatizer::Lemmatizer;
+use std::ops::Range;
+
+/// What a learning plan should aim for and which tokens it looks at
+#[derive(Clone, Debug)]
+pub struct PlanOptions {
+    /// Coverage to reach, between 0 and 1
+    pub target: f32,
+    /// Parts of speech never to plan. Their tokens are left out of the
+    /// coverage figures entirely, so excluding names doesn't make a target
+    /// unreachable.
+    pub exclude_pos: Vec<String>,
+    /// Indices of the documents to plan for; empty means the whole library.
+    /// Repeated indices count once.
+    pub documents: Vec<usize>,
+    /// Stop after this many lemmas even if the target isn't reached
+    pub limit: Option<usize>,
+}
+
+impl Default for PlanOptions {
+    fn default() -> PlanOptions {
+        PlanOptions {
+            target: 0.95,
+            exclude_pos: Vec::new(),
+            documents: Vec::new(),
+            limit: None,
+        }
+    }
+}
+
+/// One lemma to learn and where learning it gets the reader
+#[derive(Clone, Debug)]
+pub struct PlanStep {
+    pub lemma: String,
+    /// Tokens that become known by learning this lemma
+    pub count: usize,
+    /// Number of planned documents the lemma appears in
+    pub documents: usize,
+    /// Coverage once this and every previous step is learnt
+    pub coverage: Coverage,
+    /// Best example of the lemma in the planned documents, as (sentence,
+    /// token), to show and to put on its flashcard
+    pub example: (usize, usize),
+}
+
+#[derive(Clone, Debug)]
+pub struct LearningPlan {
+    pub start: Coverage,
+    pub steps: Vec<PlanStep>,
+    pub target: f32,
+}
+
+impl LearningPlan {
+    /// Whether the last step reaches the target coverage
+    pub fn reaches_target(&self) -> bool {
+        let end = self.steps.last().map_or(self.start, |step| step.coverage);
+        end.ratio() >= self.target
+    }
+
+    /// Coverage gained by the step at `step_i`, as a ratio
+    pub fn gain(&self, step_i: usize) -> f32 {
+        let before = match step_i {
+            0 => self.start,
+            i => self.steps[i - 1].coverage,
+        };
+        self.steps[step_i].coverage.ratio() - before.ratio()
+    }
+}
+
+#[derive(Default)]
+struct Candidate {
+    documents: Vec<usize>,
+    /// Every use, as (sentence, token)
+    positions: Vec<(usize, usize)>,
+}
+
+impl Lemmatizer {
+    /// Orders the unknown lemmas so that learning them one by one raises
+    /// coverage the fastest. Equal counts go to the lemma spread over more
+    /// documents, then alphabetically, so plans are reproducible. `None` if
+    /// a document index is out of range.
+    pub fn get_learning_plan(
+        &self,
+        known: &KnownWords,
+        options: &PlanOptions,
+    ) -> Option<LearningPlan> {
+        let mut documents: Vec<usize> = match options.documents.is_empty() {
+            true => (0..self.documents().len()).collect(),
+            false => options.documents.clone(),
+        };
+        documents.sort_unstable();
+        documents.dedup();
+        let ranges: Vec<Range<usize>> = documents
+            .iter()
+            .map(|document_i| Some(self.documents().get(*document_i)?.sentences.clone()))
+            .collect::<Option<_>>()?;
+        let document_of = |sentence_i: usize| {
+            let i = ranges
+                .iter()
+                .position(|range| range.contains(&sentence_i))?;
+            Some(documents[i])
+        };
+
+        let mut start = Coverage::default();
+        let mut candidates: Vec<(String, Candidate)> = Vec::new();
+        for (lemma, form_map) in &self.lemma_map {
+            let mut candidate = Candidate::default();
+            for &(sentence_i, token_i) in form_map.values().flatten() {
+                let token = &self.tokens[sentence_i][token_i];
+                let document_i = match document_of(sentence_i) {
+                    Some(document_i) if !options.exclude_pos.contains(&token.pos) => document_i,
+                    _ => continue,
+                };
+                start.total += 1;
+                if is_known(token, known) {
+                    start.known += 1;
+                    continue;
+                }
+                candidate.positions.push((sentence_i, token_i));
+                if !candidate.documents.contains(&document_i) {
+                    candidate.documents.push(document_i);
+                }
+            }
+            if !candidate.positions.is_empty() {
+                // Forms come out of the map in no particular order
+                candidate.positions.sort_unstable();
+                candidates.push((lemma.to_string(), candidate));
+            }
+        }
+
+        candidates.sort_by(|(a_lemma, a), (b_lemma, b)| {
+            b.positions
+                .len()
+                .cmp(&a.positions.len())
+                .then_with(|| b.documents.len().cmp(&a.documents.len()))
+                .then_with(|| a_lemma.cmp(b_lemma))
+        });
+
+        let mut coverage = start;
+        let mut steps: Vec<PlanStep> = Vec::new();
+        for (lemma, candidate) in candidates {
+            if coverage.ratio() >= options.target || Some(steps.len()) == options.limit {
+                break;
+            }
+            coverage.known += candidate.positions.len();
+            steps.push(PlanStep {
+                lemma,
+                count: candidate.positions.len(),
+                documents: candidate.documents.len(),
+                coverage,
+                example: self.rank_examples(&candidate.positions, known)[0],
+            });
+        }
+
+        Some(LearningPlan {
+            start,
+            steps,
+            target: options.target,
+        })
+    }
+
+    /// Flashcards for every step of a plan, in plan order
+    pub fn get_plan_cards(&self, plan: &LearningPlan) -> Vec<Card> {
+        plan.steps
+            .iter()
+            .map(|step| {
+                let (sentence_i, token_i) = step.example;
+                let usage = self.get_usage(sentence_i, token_i);
+                Card::new(&step.lemma, &usage.1.to_lowercase(), &usage)
+                    .with_timing(self.timing_of(sentence_i))
+            })
+            .collect()
+    }
+}
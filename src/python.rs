@@ -0,0 +1,157 @@
+//! Python extension module, built with `cargo build --release --features
+//! python`. Copy `target/release/libvocrab.so` to `vocrab.so` somewhere on
+//! the Python path to use it:
+//!
+//! ```python
+//! import vocrab
+//! lemmatizer = vocrab.Lemmatizer()
+//! lemmatizer.load_file("data/rights-fr.json")
+//! lemmatizer.get_lemmas()[:3]
+//! ```
+//!
+//! Errors are raised as `OSError` for files that can't be read, `ValueError`
+//! for malformed ones and `vocrab.VocrabError` otherwise.
+
+// `py_class!` expands to impls inside a function, with code clippy would
+// write differently
+#![allow(non_local_definitions, clippy::manual_strip)]
+
+use crate::known::KnownWords;
+use crate::lemmatizer::{self, LemmatizerError};
+use cpython::{
+    exc, py_class, py_exception, py_module_initializer, ObjectProtocol, PyErr, PyObject, PyResult,
+    PyString, Python,
+};
+use std::cell::RefCell;
+
+py_exception!(vocrab, VocrabError);
+
+py_module_initializer!(vocrab, |py, m| {
+    m.add(py, "__doc__", "Vocabulary analysis of tokenized documents")?;
+    m.add(py, "VocrabError", py.get_type::<VocrabError>())?;
+    m.add_class::<Lemmatizer>(py)?;
+    Ok(())
+});
+
+py_class!(pub class Lemmatizer |py| {
+    data inner: RefCell<lemmatizer::Lemmatizer>;
+
+    def __new__(_cls) -> PyResult<Lemmatizer> {
+        Lemmatizer::create_instance(py, RefCell::new(lemmatizer::Lemmatizer::new()))
+    }
+
+    /// Adds a document written by tokenizer.py
+    def load_file(&self, path: &str) -> PyResult<PyObject> {
+        self.inner(py)
+            .borrow_mut()
+            .load_file(path)
+            .map_err(|e| to_py_err(py, path, e))?;
+        Ok(py.None())
+    }
+
+    /// Adds a document from JSON in the layout tokenizer.py writes
+    def load_json(&self, name: &str, json: &str) -> PyResult<PyObject> {
+        self.inner(py)
+            .borrow_mut()
+            .load_json(name, json)
+            .map_err(|e| to_py_err(py, name, e))?;
+        Ok(py.None())
+    }
+
+    /// Loaded documents as (name, language, sentence count) tuples
+    def documents(&self) -> PyResult<Vec<(String, String, usize)>> {
+        Ok(self
+            .inner(py)
+            .borrow()
+            .documents()
+            .iter()
+            .map(|doc| (doc.name.clone(), doc.language.code().to_string(), doc.sentences.len()))
+            .collect())
+    }
+
+    /// Lemmas, most used first
+    def get_lemmas(&self) -> PyResult<Vec<String>> {
+        Ok(self.inner(py).borrow().get_lemmas())
+    }
+
+    def get_lemma_count(&self, lemma: &str) -> PyResult<usize> {
+        Ok(self.inner(py).borrow().get_lemma_count(lemma))
+    }
+
+    /// Forms of a lemma, most used first
+    def get_forms(&self, lemma: &str) -> PyResult<Vec<String>> {
+        Ok(self.inner(py).borrow().get_forms(lemma))
+    }
+
+    /// Usages of a form as (before, word, after) tuples, or of every form
+    /// when no form is given
+    def get_usages(&self, lemma: &str, form: Option<&str> = None)
+        -> PyResult<Vec<(String, String, String)>>
+    {
+        let inner = self.inner(py).borrow();
+        Ok(match form {
+            Some(form) => inner.get_usages(lemma, form),
+            None => inner.get_lemma_usages(lemma),
+        })
+    }
+
+    /// Coverage of the library by an iterable of known lemmas, as a
+    /// (known tokens, total tokens, ratio) tuple
+    def get_coverage(&self, known: PyObject) -> PyResult<(usize, usize, f32)> {
+        let known = known_words(py, &known)?;
+        let coverage = self.inner(py).borrow().get_coverage(&known);
+        Ok((coverage.known, coverage.total, coverage.ratio()))
+    }
+
+    /// Coverage of each document as (name, known, total, ratio) tuples
+    def get_document_coverage(&self, known: PyObject)
+        -> PyResult<Vec<(String, usize, usize, f32)>>
+    {
+        let known = known_words(py, &known)?;
+        let inner = self.inner(py).borrow();
+        Ok(inner
+            .documents()
+            .iter()
+            .map(|doc| {
+                let coverage = inner.get_range_coverage(doc.sentences.clone(), &known);
+                (doc.name.clone(), coverage.known, coverage.total, coverage.ratio())
+            })
+            .collect())
+    }
+
+    /// Unknown lemmas that would raise coverage the most, as (lemma, count)
+    /// tuples
+    def get_top_unknown(&self, known: PyObject, limit: usize = 20)
+        -> PyResult<Vec<(String, usize)>>
+    {
+        let known = known_words(py, &known)?;
+        Ok(self.inner(py).borrow().get_top_unknown(&known, limit))
+    }
+});
+
+/// Reads known lemmas from any iterable of strings, such as a list or set
+fn known_words(py: Python, known: &PyObject) -> PyResult<KnownWords> {
+    let mut lemmas = Vec::new();
+    for lemma in known.iter(py)? {
+        lemmas.push(
+            lemma?
+                .cast_into::<PyString>(py)?
+                .to_string(py)?
+                .into_owned(),
+        );
+    }
+    Ok(lemmas.iter().map(String::as_str).collect())
+}
+
+fn to_py_err(py: Python, source: &str, e: LemmatizerError) -> PyErr {
+    let message = format!("{}: {}", source, e);
+    match e {
+        LemmatizerError::FileIOFailed(_) => PyErr::new::<exc::OSError, _>(py, message),
+        LemmatizerError::JSONParseFailed(_)
+        | LemmatizerError::MalformedFile(_)
+        | LemmatizerError::UnknownFormat(_) => PyErr::new::<exc::ValueError, _>(py, message),
+        LemmatizerError::AnalyzerFailed(_) | LemmatizerError::JSONSerializeFailed(_) => {
+            VocrabError::new(py, message)
+        }
+    }
+}
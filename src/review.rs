@@ -0,0 +1,289 @@
+use crate::lemmatizer::{save_json, LemmatizerError};
+use serde::{Deserialize, Serialize};
+use std::fs::File;
+use std::io::{BufReader, ErrorKind};
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+const SECONDS_PER_DAY: u64 = 24 * 60 * 60;
+const INITIAL_EASE: f32 = 2.5;
+const MINIMUM_EASE: f32 = 1.3;
+
+/// Source of the current time, so scheduling can be driven by a fake clock
+pub trait Clock {
+    /// Seconds since the unix epoch
+    fn now(&self) -> u64;
+
+    /// Days since the unix epoch, the unit cards are scheduled in
+    fn today(&self) -> u64 {
+        self.now() / SECONDS_PER_DAY
+    }
+}
+
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> u64 {
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |d| d.as_secs())
+    }
+}
+
+/// How well the learner remembered a card
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Grade {
+    Again,
+    Hard,
+    Good,
+    Easy,
+}
+
+impl Grade {
+    /// The 0-5 response quality of the SM-2 algorithm
+    fn quality(self) -> f32 {
+        match self {
+            Grade::Again => 1.0,
+            Grade::Hard => 3.0,
+            Grade::Good => 4.0,
+            Grade::Easy => 5.0,
+        }
+    }
+}
+
+/// Scheduling state of one lemma being learnt
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ReviewCard {
+    pub lemma: String,
+    /// Successful reviews in a row
+    pub repetitions: u32,
+    /// Days between the last review and the next
+    pub interval: u64,
+    pub ease: f32,
+    /// Day the card is next shown on
+    pub due: u64,
+}
+
+impl ReviewCard {
+    pub fn new(lemma: &str, today: u64) -> ReviewCard {
+        ReviewCard {
+            lemma: lemma.to_string(),
+            repetitions: 0,
+            interval: 0,
+            ease: INITIAL_EASE,
+            due: today,
+        }
+    }
+
+    /// Reschedules the card with SM-2. Failed cards come back the same day.
+    pub fn schedule(&mut self, grade: Grade, today: u64) {
+        let q = grade.quality();
+        if grade == Grade::Again {
+            self.repetitions = 0;
+            self.interval = 0;
+        } else {
+            self.interval = match self.repetitions {
+                0 => 1,
+                1 => 6,
+                _ => (self.interval as f32 * self.ease).round() as u64,
+            };
+            self.repetitions += 1;
+        }
+        self.ease = (self.ease + 0.1 - (5.0 - q) * (0.08 + (5.0 - q) * 0.02)).max(MINIMUM_EASE);
+        self.due = today + self.interval;
+    }
+}
+
+/// One answered review, kept so progress can be looked back on
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ReviewLog {
+    pub lemma: String,
+    /// Seconds since the unix epoch
+    pub time: u64,
+    pub grade: Grade,
+    /// Interval the grade scheduled, in days
+    pub interval: u64,
+}
+
+/// The lemmas marked as learning and their review history
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct ReviewDeck {
+    cards: Vec<ReviewCard>,
+    history: Vec<ReviewLog>,
+}
+
+impl ReviewDeck {
+    pub fn new() -> ReviewDeck {
+        ReviewDeck::default()
+    }
+
+    /// Reads a deck saved with `save_file`. A missing file is an empty deck.
+    pub fn load_file<P: AsRef<Path>>(path: P) -> Result<ReviewDeck, LemmatizerError> {
+        let file = match File::open(path) {
+            Ok(file) => file,
+            Err(e) if e.kind() == ErrorKind::NotFound => return Ok(ReviewDeck::new()),
+            Err(e) => return Err(LemmatizerError::FileIOFailed(e)),
+        };
+        serde_json::from_reader(BufReader::new(file)).map_err(LemmatizerError::JSONParseFailed)
+    }
+
+    pub fn save_file<P: AsRef<Path>>(&self, path: P) -> Result<(), LemmatizerError> {
+        save_json(self, path)
+    }
+
+    pub fn cards(&self) -> &[ReviewCard] {
+        &self.cards
+    }
+
+    pub fn history(&self) -> &[ReviewLog] {
+        &self.history
+    }
+
+    pub fn contains(&self, lemma: &str) -> bool {
+        self.cards.iter().any(|card| card.lemma == lemma)
+    }
+
+    /// Starts learning a lemma; it is due right away
+    pub fn add(&mut self, lemma: &str, clock: &dyn Clock) -> bool {
+        if self.contains(lemma) {
+            return false;
+        }
+        self.cards.push(ReviewCard::new(lemma, clock.today()));
+        true
+    }
+
+    pub fn remove(&mut self, lemma: &str) -> bool {
+        let len = self.cards.len();
+        self.cards.retain(|card| card.lemma != lemma);
+        self.cards.len() != len
+    }
+
+    /// Cards due today or earlier, most overdue first
+    pub fn due(&self, clock: &dyn Clock) -> Vec<&ReviewCard> {
+        let today = clock.today();
+        let mut due: Vec<&ReviewCard> = self.cards.iter().filter(|c| c.due <= today).collect();
+        due.sort_by(|a, b| a.due.cmp(&b.due).then_with(|| a.lemma.cmp(&b.lemma)));
+        due
+    }
+
+    /// Records an answer and reschedules the card. Returns the new due day,
+    /// or `None` if the lemma isn't in the deck.
+    pub fn grade(&mut self, lemma: &str, grade: Grade, clock: &dyn Clock) -> Option<u64> {
+        let card = self.cards.iter_mut().find(|card| card.lemma == lemma)?;
+        card.schedule(grade, clock.today());
+        self.history.push(ReviewLog {
+            lemma: lemma.to_string(),
+            time: clock.now(),
+            grade,
+            interval: card.interval,
+        });
+        Some(card.due)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A clock stopped at the start of a given day
+    struct FakeClock(u64);
+
+    impl Clock for FakeClock {
+        fn now(&self) -> u64 {
+            self.0 * SECONDS_PER_DAY
+        }
+    }
+
+    fn assert_ease(card: &ReviewCard, ease: f32) {
+        assert!(
+            (card.ease - ease).abs() < 1e-5,
+            "ease {} isn't {}",
+            card.ease,
+            ease
+        );
+    }
+
+    #[test]
+    fn intervals_and_ease_follow_each_grade() {
+        let mut card = ReviewCard::new("gato", 100);
+        card.schedule(Grade::Good, 100);
+        assert_eq!((card.repetitions, card.interval, card.due), (1, 1, 101));
+        assert_ease(&card, 2.5);
+
+        card.schedule(Grade::Good, 101);
+        assert_eq!((card.repetitions, card.interval, card.due), (2, 6, 107));
+        assert_ease(&card, 2.5);
+
+        card.schedule(Grade::Easy, 107);
+        assert_eq!((card.interval, card.due), (15, 122));
+        assert_ease(&card, 2.6);
+
+        card.schedule(Grade::Hard, 122);
+        assert_eq!((card.interval, card.due), (39, 161));
+        assert_ease(&card, 2.46);
+    }
+
+    #[test]
+    fn ease_never_drops_below_minimum() {
+        let mut card = ReviewCard::new("gato", 0);
+        card.schedule(Grade::Again, 0);
+        assert_ease(&card, 1.96);
+        card.schedule(Grade::Again, 0);
+        assert_ease(&card, 1.42);
+        for _ in 0..5 {
+            card.schedule(Grade::Again, 0);
+            assert_ease(&card, MINIMUM_EASE);
+        }
+    }
+
+    #[test]
+    fn failed_grade_starts_over() {
+        let mut card = ReviewCard::new("gato", 0);
+        for day in [0, 1, 7] {
+            card.schedule(Grade::Good, day);
+        }
+        assert_eq!((card.repetitions, card.interval), (3, 15));
+
+        card.schedule(Grade::Again, 22);
+        assert_eq!((card.repetitions, card.interval, card.due), (0, 0, 22));
+        card.schedule(Grade::Good, 22);
+        assert_eq!((card.repetitions, card.interval, card.due), (1, 1, 23));
+    }
+
+    #[test]
+    fn due_cards_come_most_overdue_first() {
+        let mut deck = ReviewDeck::new();
+        deck.add("perro", &FakeClock(10));
+        deck.add("gato", &FakeClock(12));
+        deck.add("casa", &FakeClock(12));
+        deck.add("luna", &FakeClock(12));
+        assert!(!deck.add("gato", &FakeClock(12)));
+        deck.grade("luna", Grade::Good, &FakeClock(12));
+
+        let due: Vec<&str> = deck
+            .due(&FakeClock(12))
+            .iter()
+            .map(|card| card.lemma.as_str())
+            .collect();
+        assert_eq!(due, ["perro", "casa", "gato"]);
+        assert!(deck.due(&FakeClock(9)).is_empty());
+        assert_eq!(deck.due(&FakeClock(13)).len(), 4);
+    }
+
+    #[test]
+    fn grades_are_logged_with_the_clock_time() {
+        let mut deck = ReviewDeck::new();
+        deck.add("gato", &FakeClock(5));
+        assert_eq!(deck.grade("gato", Grade::Good, &FakeClock(5)), Some(6));
+        assert_eq!(deck.grade("perro", Grade::Good, &FakeClock(5)), None);
+        assert_eq!(
+            deck.history(),
+            [ReviewLog {
+                lemma: "gato".to_string(),
+                time: 5 * SECONDS_PER_DAY,
+                grade: Grade::Good,
+                interval: 1,
+            }]
+        );
+    }
+}
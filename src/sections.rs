@@ -0,0 +1,104 @@
+use crate::lemmatizer::{Lemmatizer, Section};
+use std::collections::{HashMap, HashSet};
+use std::ops::Range;
+
+impl Lemmatizer {
+    /// The chapter or other section a sentence is in
+    pub fn section_of(&self, sentence_i: usize) -> Option<&Section> {
+        let (document, _) = self.locate_sentence(sentence_i)?;
+        document
+            .sections
+            .iter()
+            .find(|section| section.sentences.contains(&sentence_i))
+    }
+
+    /// Lemmas used in `sentences` but not in the sentences from `since` up
+    /// to them, with how often they're used in `sentences`, most used first
+    pub fn get_new_lemmas(&self, sentences: Range<usize>, since: usize) -> Vec<(String, usize)> {
+        let seen: HashSet<String> = self
+            .counted_sentences(since.min(sentences.start)..sentences.start)
+            .flat_map(|(_, sentence)| sentence)
+            .filter(|t| !t.is_punct())
+            .map(|t| t.lemma_key())
+            .collect();
+        let mut counts: HashMap<String, usize> = HashMap::new();
+        for token in self
+            .counted_sentences(sentences)
+            .flat_map(|(_, sentence)| sentence)
+            .filter(|t| !t.is_punct())
+        {
+            let lemma = token.lemma_key();
+            if !seen.contains(&lemma) {
+                *counts.entry(lemma).or_insert(0) += 1;
+            }
+        }
+        let mut counts: Vec<(String, usize)> = counts.into_iter().collect();
+        counts.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        counts
+    }
+
+    /// Lemmas section `section_i` of the document at `document_i` brings
+    /// in, first used in it rather than in an earlier section, or `None` if
+    /// there is no such section
+    pub fn get_section_new_lemmas(
+        &self,
+        document_i: usize,
+        section_i: usize,
+    ) -> Option<Vec<(String, usize)>> {
+        let document = self.documents().get(document_i)?;
+        let section = document.sections.get(section_i)?;
+        Some(self.get_new_lemmas(section.sentences.clone(), document.sentences.start))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn word(text: &str) -> String {
+        format!(r#"{{"text": "{0}", "lemma": "{0}", "pos": "X"}}"#, text)
+    }
+
+    #[test]
+    fn sections_bring_in_lemmas_not_seen_before_them() {
+        let sentence = |words: &[&str]| {
+            let words: Vec<String> = words.iter().map(|w| word(w)).collect();
+            format!(
+                "[{}, {}]",
+                words.join(", "),
+                r#"{"text": ".", "lemma": ".", "pos": "PUNCT"}"#
+            )
+        };
+        let mut lemmatizer = Lemmatizer::new();
+        lemmatizer
+            .load_json(
+                "uno",
+                &format!(
+                    r#"{{"sentences": [{}, {}, {}],
+                        "sections": [{{"title": "I", "start": 0}}, {{"title": "II", "start": 1}}]}}"#,
+                    sentence(&["el", "gato"]),
+                    sentence(&["el", "perro", "y", "el", "loro"]),
+                    sentence(&["el", "perro"]),
+                ),
+            )
+            .unwrap();
+        assert_eq!(
+            lemmatizer.section_of(2).and_then(|s| s.title.as_deref()),
+            Some("II")
+        );
+        let new = |lemmas: &[(&str, usize)]| {
+            let lemmas = lemmas.iter().map(|(l, n)| (l.to_string(), *n)).collect();
+            Some(lemmas)
+        };
+        assert_eq!(
+            lemmatizer.get_section_new_lemmas(0, 0),
+            new(&[("el", 1), ("gato", 1)])
+        );
+        assert_eq!(
+            lemmatizer.get_section_new_lemmas(0, 1),
+            new(&[("perro", 2), ("loro", 1), ("y", 1)])
+        );
+        assert_eq!(lemmatizer.get_section_new_lemmas(0, 2), None);
+        assert_eq!(lemmatizer.get_section_new_lemmas(1, 0), None);
+    }
+}
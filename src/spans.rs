@@ -0,0 +1,55 @@
+use crate::coverage::TRANSPARENT_POS;
+use crate::known::KnownWords;
+use crate::lemmatizer::Lemmatizer;
+use std::ops::Range;
+
+/// A word token of a document, located in the text it was analyzed from
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct WordSpan {
+    /// Byte offsets into the source text
+    pub range: Range<usize>,
+    /// The form as written, lowercased like the `FormMap` keys
+    pub form: String,
+    /// Key of the lemma in the `LemmaMap`
+    pub lemma: String,
+    pub pos: String,
+    pub sentence: usize,
+}
+
+impl WordSpan {
+    pub fn is_known(&self, known: &KnownWords) -> bool {
+        TRANSPARENT_POS.contains(&self.pos.as_str()) || known.contains(&self.lemma)
+    }
+}
+
+impl Lemmatizer {
+    /// Finds the word tokens of the document at `document_i` in `text`, the
+    /// text it was analyzed from. Tokens are matched in order, so words the
+    /// analyzer rewrote are left out rather than misplaced. `None` if there
+    /// is no such document.
+    pub fn get_word_spans(&self, document_i: usize, text: &str) -> Option<Vec<WordSpan>> {
+        let document = self.documents().get(document_i)?;
+        let mut spans = Vec::new();
+        let mut cursor = 0;
+        for sentence_i in document.sentences.clone() {
+            for token in self.tokens[sentence_i].iter() {
+                if token.is_punct() || token.text.is_empty() {
+                    continue;
+                }
+                let start = match text[cursor..].find(&token.text) {
+                    Some(offset) => cursor + offset,
+                    None => continue,
+                };
+                cursor = start + token.text.len();
+                spans.push(WordSpan {
+                    range: start..cursor,
+                    form: token.text.to_lowercase(),
+                    lemma: token.lemma_key(),
+                    pos: token.pos.clone(),
+                    sentence: sentence_i,
+                });
+            }
+        }
+        Some(spans)
+    }
+}
@@ -0,0 +1,219 @@
+use crate::coverage::Coverage;
+use crate::lemmatizer::Lemmatizer;
+use std::collections::HashMap;
+use std::ops::Range;
+
+/// Size of the library's text and vocabulary. Punctuation isn't counted.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct CorpusStats {
+    pub documents: usize,
+    pub sentences: usize,
+    pub tokens: usize,
+    /// Distinct lemmas
+    pub types: usize,
+    /// Distinct forms
+    pub forms: usize,
+    /// Lemmas used exactly once
+    pub hapax: usize,
+}
+
+impl CorpusStats {
+    /// Distinct lemmas per token
+    pub fn type_token_ratio(&self) -> f32 {
+        match self.tokens {
+            0 => 0.0,
+            tokens => self.types as f32 / tokens as f32,
+        }
+    }
+}
+
+impl Lemmatizer {
+    pub fn get_stats(&self) -> CorpusStats {
+        self.get_range_stats(0..self.tokens.len())
+    }
+
+    /// Size of the text and vocabulary of a run of sentences, counting the
+    /// documents that lie wholly inside it
+    pub fn get_range_stats(&self, sentences: Range<usize>) -> CorpusStats {
+        // Uses of each form of each lemma
+        let mut lemmas: HashMap<String, HashMap<String, usize>> = HashMap::new();
+        let tokens = self
+            .counted_sentences(sentences.clone())
+            .flat_map(|(_, sentence)| sentence);
+        for token in tokens.filter(|t| !t.is_punct()) {
+            *lemmas
+                .entry(token.lemma_key())
+                .or_default()
+                .entry(token.text.to_lowercase())
+                .or_insert(0) += 1;
+        }
+        let counts: Vec<usize> = lemmas.values().map(|forms| forms.values().sum()).collect();
+        CorpusStats {
+            documents: self
+                .documents()
+                .iter()
+                .filter(|document| {
+                    sentences.start <= document.sentences.start
+                        && document.sentences.end <= sentences.end
+                })
+                .count(),
+            sentences: sentences.len(),
+            tokens: counts.iter().sum(),
+            types: counts.len(),
+            forms: lemmas.values().map(|forms| forms.len()).sum(),
+            hapax: counts.iter().filter(|count| **count == 1).count(),
+        }
+    }
+
+    /// Coverage of the library after learning the most frequent lemma, the
+    /// two most frequent, and so on through every lemma, in the order of
+    /// `get_lemmas`
+    pub fn get_coverage_curve(&self) -> Vec<Coverage> {
+        let total = self.get_stats().tokens;
+        let mut known = 0;
+        self.get_lemmas()
+            .iter()
+            .map(|lemma| {
+                known += self.get_lemma_count(lemma);
+                Coverage { known, total }
+            })
+            .collect()
+    }
+
+    /// Number of tokens of each part of speech, most common first
+    pub fn get_pos_counts(&self) -> Vec<(String, usize)> {
+        let mut counts: HashMap<&str, usize> = HashMap::new();
+        let tokens = self
+            .counted_sentences(0..self.tokens.len())
+            .flat_map(|(_, sentence)| sentence);
+        for token in tokens.filter(|t| !t.is_punct()) {
+            *counts.entry(&token.pos).or_insert(0) += 1;
+        }
+        let mut counts: Vec<(String, usize)> = counts
+            .into_iter()
+            .map(|(pos, count)| (pos.to_string(), count))
+            .collect();
+        counts.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        counts
+    }
+
+    /// Uses of a lemma in each of `bins` equal runs of sentences, from the
+    /// start of the library to the end
+    pub fn get_lemma_distribution(&self, lemma: &str, bins: usize) -> Vec<u64> {
+        let mut distribution = vec![0; bins];
+        let sentences = self.sentence_count();
+        if bins == 0 || sentences == 0 {
+            return distribution;
+        }
+        if let Some(map) = self.lemma_map.get(lemma) {
+            for (sentence_i, _) in map.values().flatten() {
+                distribution[sentence_i * bins / sentences] += 1;
+            }
+        }
+        distribution
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// "El gato come. El perro come pan."
+    const DOCUMENT: &str = r#"{"sentences": [
+        [
+            {"text": "El", "lemma": "el", "pos": "DET"},
+            {"text": "gato", "lemma": "gato", "pos": "NOUN"},
+            {"text": "come", "lemma": "comer", "pos": "VERB"},
+            {"text": ".", "lemma": ".", "pos": "PUNCT"}
+        ],
+        [
+            {"text": "El", "lemma": "el", "pos": "DET"},
+            {"text": "perro", "lemma": "perro", "pos": "NOUN"},
+            {"text": "come", "lemma": "comer", "pos": "VERB"},
+            {"text": "pan", "lemma": "pan", "pos": "NOUN"},
+            {"text": ".", "lemma": ".", "pos": "PUNCT"}
+        ]
+    ]}"#;
+
+    fn lemmatizer() -> Lemmatizer {
+        let mut lemmatizer = Lemmatizer::new();
+        lemmatizer.load_json("gato", DOCUMENT).unwrap();
+        lemmatizer
+    }
+
+    #[test]
+    fn stats_leave_out_punctuation() {
+        let stats = lemmatizer().get_stats();
+        assert_eq!(
+            stats,
+            CorpusStats {
+                documents: 1,
+                sentences: 2,
+                tokens: 7,
+                types: 5,
+                forms: 5,
+                hapax: 3,
+            }
+        );
+        assert!((stats.type_token_ratio() - 5.0 / 7.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn range_stats_count_only_its_sentences() {
+        let stats = lemmatizer().get_range_stats(1..2);
+        assert_eq!(
+            stats,
+            CorpusStats {
+                documents: 0,
+                sentences: 1,
+                tokens: 4,
+                types: 4,
+                forms: 4,
+                hapax: 4,
+            }
+        );
+    }
+
+    #[test]
+    fn empty_library_has_no_ratio() {
+        let stats = Lemmatizer::new().get_stats();
+        assert_eq!(stats, CorpusStats::default());
+        assert_eq!(stats.type_token_ratio(), 0.0);
+    }
+
+    #[test]
+    fn coverage_curve_adds_lemmas_by_frequency() {
+        let curve = lemmatizer().get_coverage_curve();
+        let known: Vec<usize> = curve.iter().map(|coverage| coverage.known).collect();
+        assert_eq!(known, vec![2, 4, 5, 6, 7]);
+        assert!(curve.iter().all(|coverage| coverage.total == 7));
+    }
+
+    #[test]
+    fn pos_counts_are_most_common_first() {
+        assert_eq!(
+            lemmatizer().get_pos_counts(),
+            vec![
+                ("NOUN".to_string(), 3),
+                ("DET".to_string(), 2),
+                ("VERB".to_string(), 2),
+            ]
+        );
+    }
+
+    #[test]
+    fn lemma_distribution_bins_sentences() {
+        let lemmatizer = lemmatizer();
+        assert_eq!(lemmatizer.get_lemma_distribution("gato", 2), vec![1, 0]);
+        assert_eq!(lemmatizer.get_lemma_distribution("el", 2), vec![1, 1]);
+        assert_eq!(
+            lemmatizer.get_lemma_distribution("pan", 4),
+            vec![0, 0, 1, 0]
+        );
+        assert_eq!(
+            lemmatizer.get_lemma_distribution("perro", 0),
+            Vec::<u64>::new()
+        );
+        assert_eq!(lemmatizer.get_lemma_distribution("caballo", 2), vec![0, 0]);
+    }
+}
@@ -0,0 +1,383 @@
+use crate::analyzer::{token_offsets, Analyzer};
+use crate::lemmatizer::{DocumentFile, LemmatizerError};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::ops::Range;
+use std::path::Path;
+use std::time::Duration;
+
+/// Silence between cues long enough to start a new paragraph
+pub const PARAGRAPH_PAUSE: Duration = Duration::from_secs(2);
+
+/// When a sentence is said in the video it was subtitled from. Stored in
+/// document JSON as `[start, end]` in milliseconds.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(from = "[u64; 2]", into = "[u64; 2]")]
+pub struct Timing {
+    pub start: Duration,
+    pub end: Duration,
+}
+
+impl From<[u64; 2]> for Timing {
+    fn from([start, end]: [u64; 2]) -> Timing {
+        Timing {
+            start: Duration::from_millis(start),
+            end: Duration::from_millis(end),
+        }
+    }
+}
+
+impl From<Timing> for [u64; 2] {
+    fn from(timing: Timing) -> [u64; 2] {
+        [
+            timing.start.as_millis() as u64,
+            timing.end.as_millis() as u64,
+        ]
+    }
+}
+
+/// A time as `HH:MM:SS.mmm`, the way players and WebVTT write it
+pub fn format_timestamp(time: Duration) -> String {
+    let millis = time.as_millis();
+    format!(
+        "{:02}:{:02}:{:02}.{:03}",
+        millis / 3_600_000,
+        millis / 60_000 % 60,
+        millis / 1000 % 60,
+        millis % 1000
+    )
+}
+
+/// Text shown on screen between two times
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Cue {
+    pub start: Duration,
+    pub end: Duration,
+    /// Lines of the cue, without markup
+    pub lines: Vec<String>,
+    /// Speaker from a WebVTT voice tag, `<v Name>`
+    pub speaker: Option<String>,
+}
+
+/// Reads an SRT or WebVTT file, by its extension
+pub fn read_subtitles<P: AsRef<Path>>(path: P) -> Result<Vec<Cue>, LemmatizerError> {
+    let path = path.as_ref();
+    let text = fs::read_to_string(path).map_err(LemmatizerError::FileIOFailed)?;
+    let extension = path.extension().map(|e| e.to_string_lossy().to_lowercase());
+    match extension.as_deref() {
+        Some("srt") => parse_srt(&text),
+        Some("vtt") => parse_vtt(&text),
+        _ => Err(LemmatizerError::UnknownFormat(path.display().to_string())),
+    }
+}
+
+/// Cues of a SubRip file: numbered blocks of a timing line and text,
+/// separated by blank lines
+pub fn parse_srt(text: &str) -> Result<Vec<Cue>, LemmatizerError> {
+    blocks(text)
+        .into_iter()
+        .filter_map(|block| {
+            // The cue number is optional in practice
+            let timing_i = block.iter().position(|line| line.contains("-->"))?;
+            Some(parse_cue(&block[timing_i..]))
+        })
+        .collect()
+}
+
+/// Cues of a WebVTT file. Comments, styles and regions are skipped.
+pub fn parse_vtt(text: &str) -> Result<Vec<Cue>, LemmatizerError> {
+    let text = text.trim_start_matches('\u{feff}');
+    if !text.starts_with("WEBVTT") {
+        return Err(LemmatizerError::MalformedFile(
+            "WebVTT files start with WEBVTT".to_string(),
+        ));
+    }
+    blocks(text)
+        .into_iter()
+        .skip(1)
+        .filter(|block| {
+            !["NOTE", "STYLE", "REGION"]
+                .iter()
+                .any(|k| block[0].starts_with(k))
+        })
+        .filter_map(|block| {
+            let timing_i = block.iter().position(|line| line.contains("-->"))?;
+            Some(parse_cue(&block[timing_i..]))
+        })
+        .collect()
+}
+
+/// Runs of non-blank lines
+fn blocks(text: &str) -> Vec<Vec<&str>> {
+    let lines: Vec<&str> = text
+        .trim_start_matches('\u{feff}')
+        .lines()
+        .map(str::trim_end)
+        .collect();
+    lines
+        .split(|line| line.is_empty())
+        .filter(|block| !block.is_empty())
+        .map(<[&str]>::to_vec)
+        .collect()
+}
+
+/// A timing line, `start --> end` followed by optional WebVTT settings, and
+/// the lines of text under it
+fn parse_cue(block: &[&str]) -> Result<Cue, LemmatizerError> {
+    let malformed = || LemmatizerError::MalformedFile(format!("bad cue timing \"{}\"", block[0]));
+    let (start, rest) = block[0].split_once("-->").ok_or_else(malformed)?;
+    let end = rest.split_whitespace().next().ok_or_else(malformed)?;
+    let start = parse_timestamp(start.trim()).ok_or_else(malformed)?;
+    let end = parse_timestamp(end).ok_or_else(malformed)?;
+
+    let mut speaker = None;
+    let lines = block[1..]
+        .iter()
+        .map(|line| {
+            let (voice, line) = strip_markup(line);
+            speaker = speaker.take().or(voice);
+            line
+        })
+        .filter(|line| !line.is_empty())
+        .collect();
+    Ok(Cue {
+        start,
+        end,
+        lines,
+        speaker,
+    })
+}
+
+/// `HH:MM:SS,mmm` in SRT, `HH:MM:SS.mmm` or `MM:SS.mmm` in WebVTT
+fn parse_timestamp(timestamp: &str) -> Option<Duration> {
+    let (clock, millis) = timestamp.split_once([',', '.'])?;
+    let mut seconds = 0;
+    for part in clock.split(':') {
+        seconds = seconds * 60 + part.parse::<u64>().ok()?;
+    }
+    let millis: u64 = format!("{:0<3}", millis).get(..3)?.parse().ok()?;
+    Some(Duration::from_secs(seconds) + Duration::from_millis(millis))
+}
+
+/// A cue line without tags like `<i>`, `<c.yellow>` or `{\an8}`, entities
+/// decoded, and the name of a `<v Name>` voice tag if it has one
+fn strip_markup(line: &str) -> (Option<String>, String) {
+    let mut voice = None;
+    let mut text = String::new();
+    let mut rest = line;
+    while let Some(i) = rest.find(['<', '{']) {
+        text.push_str(&rest[..i]);
+        let close = if rest[i..].starts_with('<') { '>' } else { '}' };
+        match rest[i..].find(close) {
+            Some(j) => {
+                let tag = &rest[i + 1..i + j];
+                // Classes come before the name, as in <v.loud Name>
+                let name = match tag.strip_prefix('v') {
+                    Some(rest) if rest.starts_with(' ') => Some(rest),
+                    Some(rest) if rest.starts_with('.') => rest.split_once(' ').map(|(_, n)| n),
+                    _ => None,
+                };
+                if let Some(name) = name {
+                    voice = Some(name.trim().to_string());
+                }
+                rest = &rest[i + j + 1..];
+            }
+            None => {
+                text.push_str(&rest[i..]);
+                rest = "";
+            }
+        }
+    }
+    text.push_str(rest);
+    let text = text
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&nbsp;", " ")
+        .replace("&amp;", "&");
+    // Dashes that open each line of a two-person cue
+    let text = text.trim().trim_start_matches(['-', '‐']);
+    (voice, text.trim().to_string())
+}
+
+/// Cue text joined into running text to split into sentences, with the
+/// byte range each cue ends up at. Pauses of `PARAGRAPH_PAUSE` start a
+/// paragraph, voice tags become transcript speaker tags, and lines a
+/// rolling caption repeats from the cue before are left out.
+pub fn subtitle_text(cues: &[Cue]) -> (String, Vec<Range<usize>>) {
+    let mut text = String::new();
+    let mut spans = Vec::with_capacity(cues.len());
+    let mut previous: Option<&Cue> = None;
+    for cue in cues {
+        let mut lines = &cue.lines[..];
+        if let Some(previous) = previous {
+            if !lines.is_empty() && lines.first() == previous.lines.last() {
+                lines = &lines[1..];
+            }
+            if cue.start.saturating_sub(previous.end) >= PARAGRAPH_PAUSE {
+                text.push_str("\n\n");
+            }
+        }
+        if let Some(speaker) = &cue.speaker {
+            if previous.is_none_or(|previous| previous.speaker.as_ref() != Some(speaker)) {
+                text.push_str(&format!("\n[{}]: ", speaker));
+            }
+        }
+        if !text.is_empty() && !text.ends_with(char::is_whitespace) {
+            text.push(' ');
+        }
+        let start = text.len();
+        text.push_str(&lines.join(" "));
+        spans.push(start..text.len());
+        previous = Some(cue);
+    }
+    (text, spans)
+}
+
+/// Analyzes the text of subtitle cues into document JSON, with the timing
+/// of the cues each sentence was said in
+pub fn analyze_subtitles<A: Analyzer>(
+    cues: &[Cue],
+    analyzer: &A,
+    language: &str,
+) -> Result<String, LemmatizerError> {
+    let (text, spans) = subtitle_text(cues);
+    let json = analyzer.analyze(&text, language)?;
+    let mut document: DocumentFile =
+        serde_json::from_str(&json).map_err(LemmatizerError::JSONParseFailed)?;
+
+    let cue_at = |offset: usize| {
+        spans
+            .partition_point(|span| span.start <= offset)
+            .checked_sub(1)
+    };
+    document.timings = token_offsets(&text, &document.sentences)
+        .into_iter()
+        .map(|offsets| {
+            let cues_said: Vec<usize> = offsets.into_iter().flatten().filter_map(cue_at).collect();
+            let first = &cues[*cues_said.first()?];
+            let last = &cues[*cues_said.last()?];
+            Some(Timing {
+                start: first.start,
+                end: last.end.max(first.start),
+            })
+        })
+        .collect();
+    serde_json::to_string(&document).map_err(LemmatizerError::JSONSerializeFailed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analyzer::tests::SplitAnalyzer;
+
+    fn seconds(seconds: f64) -> Duration {
+        Duration::from_secs_f64(seconds)
+    }
+
+    fn cue(start: f64, end: f64, lines: &[&str], speaker: Option<&str>) -> Cue {
+        Cue {
+            start: seconds(start),
+            end: seconds(end),
+            lines: lines.iter().map(|line| line.to_string()).collect(),
+            speaker: speaker.map(str::to_string),
+        }
+    }
+
+    #[test]
+    fn timestamps_with_and_without_hours() {
+        assert_eq!(parse_timestamp("01:02:03,456"), Some(seconds(3723.456)));
+        assert_eq!(parse_timestamp("02:03.4"), Some(seconds(123.4)));
+        assert_eq!(parse_timestamp("00:00:01"), None);
+        assert_eq!(parse_timestamp("00:aa:01.000"), None);
+        assert_eq!(parse_timestamp("00:01.x"), None);
+        assert_eq!(format_timestamp(seconds(3723.456)), "01:02:03.456");
+    }
+
+    #[test]
+    fn srt_cues_lose_their_markup() {
+        let srt = "\u{feff}1\n00:00:01,000 --> 00:00:02,500\n{\\an8}<i>Hola</i>\n- ¿qué tal?\n\n\
+                   2\n00:00:03,000 --> 00:00:04,000\nBien\n";
+        assert_eq!(
+            parse_srt(srt).unwrap(),
+            vec![
+                cue(1.0, 2.5, &["Hola", "¿qué tal?"], None),
+                cue(3.0, 4.0, &["Bien"], None),
+            ]
+        );
+        let bad = "1\n00:00:01 --> 00:00:02,000\nHola\n";
+        assert!(matches!(
+            parse_srt(bad),
+            Err(LemmatizerError::MalformedFile(_))
+        ));
+    }
+
+    #[test]
+    fn vtt_skips_headers_notes_and_styles() {
+        let vtt = "WEBVTT - Entrevista\nKind: captions\n\n\
+                   NOTE grabado en\ndos partes\n\n\
+                   STYLE\n::cue { color: yellow }\n\n\
+                   intro\n00:01.000 --> 00:03.500 align:start position:10%\n\
+                   <v Ana>Hola, <c.yellow>¿qué</c> tal?\n\n\
+                   00:00:04.000 --> 00:00:05.000\n<v.loud Beto>Bien &amp; tú\n";
+        assert_eq!(
+            parse_vtt(vtt).unwrap(),
+            vec![
+                cue(1.0, 3.5, &["Hola, ¿qué tal?"], Some("Ana")),
+                cue(4.0, 5.0, &["Bien & tú"], Some("Beto")),
+            ]
+        );
+        assert!(matches!(
+            parse_vtt("00:01.000 --> 00:02.000\nHola\n"),
+            Err(LemmatizerError::MalformedFile(_))
+        ));
+    }
+
+    #[test]
+    fn rolling_captions_are_joined_once() {
+        let cues = [
+            cue(0.0, 1.0, &["uno dos"], None),
+            cue(1.0, 2.0, &["uno dos", "tres cuatro"], None),
+            cue(2.0, 3.0, &["tres cuatro", "cinco"], None),
+        ];
+        let (text, spans) = subtitle_text(&cues);
+        assert_eq!(text, "uno dos tres cuatro cinco");
+        assert_eq!(spans, vec![0..7, 8..19, 20..25]);
+    }
+
+    #[test]
+    fn pauses_and_voices_start_paragraphs_and_speakers() {
+        let cues = [
+            cue(0.0, 1.0, &["Hola."], Some("Ana")),
+            cue(1.5, 2.0, &["¿Sí?"], Some("Ana")),
+            cue(5.0, 6.0, &["Sí."], Some("Beto")),
+        ];
+        let (text, _) = subtitle_text(&cues);
+        assert_eq!(text, "\n[Ana]: Hola. ¿Sí?\n\n\n[Beto]: Sí.");
+    }
+
+    #[test]
+    fn sentences_span_the_cues_they_are_said_in() {
+        let cues = [
+            cue(0.0, 2.0, &["Hola a todos,"], None),
+            cue(2.5, 4.0, &["bienvenidos al programa."], None),
+            cue(4.0, 6.5, &["Hoy hablamos de emos."], None),
+        ];
+        let json = analyze_subtitles(&cues, &SplitAnalyzer, "es").unwrap();
+        let document: DocumentFile = serde_json::from_str(&json).unwrap();
+        assert_eq!(document.sentences.len(), 2);
+        assert_eq!(
+            document.timings,
+            vec![
+                Some(Timing {
+                    start: seconds(0.0),
+                    end: seconds(4.0),
+                }),
+                Some(Timing {
+                    start: seconds(4.0),
+                    end: seconds(6.5),
+                }),
+            ]
+        );
+        assert!(json.contains(r#""timings":[[0,4000],[4000,6500]]"#));
+    }
+}
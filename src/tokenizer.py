@@ -2,65 +2,145 @@ import spacy
 import re
 import textwrap
 import argparse
+import json
+import sys
 
 if __name__ == "__main__":
     
     parser = argparse.ArgumentParser()
-    parser.add_argument('file', help='the text file to read from')
-    parser.add_argument('lang', help='the language of the text')
+    parser.add_argument('file', help='the text file to read from, or - for standard input')
+    parser.add_argument('lang', help='the language of the text, by name or ISO code')
+    parser.add_argument('--stdout', action='store_true',
+                        help='write the JSON to standard output instead of a file')
     args = parser.parse_args()
 
+    # With --stdout the JSON is the only thing on standard output
+    log = sys.stderr if args.stdout else sys.stdout
+
     packages = {
         "english": "en_core_web_sm",
         "spanish": "es_core_news_sm",
-        "french":  "fr_core_news_sm"
+        "french":  "fr_core_news_sm",
+        "japanese": "ja_core_news_sm",
+        "chinese": "zh_core_web_sm",
+        # spaCy has no trained Thai pipeline, only a word segmenter built
+        # on PyThaiNLP, so Thai gets a blank one
+        "thai": None,
+    }
+    codes = {
+        "en": "english",
+        "es": "spanish",
+        "fr": "french",
+        "ja": "japanese",
+        "zh": "chinese",
+        "th": "thai",
     }
-    nlp = spacy.load(packages[args.lang])
+    codes_by_name = {name: code for (code, name) in codes.items()}
+    lang = codes.get(args.lang.lower(), args.lang.lower())
+    if lang not in packages:
+        sys.exit("Unsupported language: {}".format(args.lang))
+    if packages[lang]:
+        nlp = spacy.load(packages[lang])
+    else:
+        # Blank pipelines only split words; sentences end at punctuation
+        # marks, and words are their own lemmas
+        nlp = spacy.blank(codes_by_name[lang])
+        nlp.add_pipe("sentencizer")
 
     file_path = args.file
 
     replace_subs = [
-        ("\[[^\[\]]+\]: ", ""),
-        ("\(SOUNDBITE ARCHIVO\)", ""),
         ("\s+", " "),
         ("…", "."),
     ]
 
+    # Transcripts name the speaker at the start of a line, as in
+    # "[Daniel Alarcón]: ...", and mark sounds and music with stage
+    # directions like "(SOUNDBITE ARCHIVO)", "(risas)" or "[música]"
+    speaker_tag = re.compile(r"^[ \t]*\[([^\[\]\n]+)\]:[ \t]*", re.MULTILINE)
+    bracketed_note = re.compile(r"\[[^\[\]]*\]")
+    parenthesized = re.compile(r"\([^()]*\)")
+
+    def is_stage_direction(note, is_transcript):
+        # Other texts use parentheses for asides, so only capitals mark a
+        # direction there
+        inner = note[1:-1]
+        return is_transcript or (
+            any(c.isalpha() for c in inner) and inner == inner.upper())
+
+    def clean(text, is_transcript):
+        text = parenthesized.sub(
+            lambda m: " " if is_stage_direction(m.group(0), is_transcript)
+            else m.group(0),
+            text)
+        text = bracketed_note.sub(" ", text)
+        for (old, new) in replace_subs:
+            text = re.sub(old, new, text)
+        return text.strip()
+
     punctuation_marks = ["-", "»", "“", "”", "¡", "&"]
 
-    print("Reading from {}.".format(file_path))
+    print("Reading from {}.".format(file_path), file=log)
     text = ""
-    with open(file_path, "rt") as file:
-        text = file.read()
+    if file_path == "-":
+        text = sys.stdin.read()
+    else:
+        with open(file_path, "rt") as file:
+            text = file.read()
+
+    # Blank lines separate paragraphs. Each paragraph is a list of turns,
+    # (speaker, text), and a speaker keeps talking across paragraphs until
+    # the next tag.
+    is_transcript = speaker_tag.search(text) is not None
+    paragraphs = []
+    speaker = None
+    for paragraph in re.split("\n\s*\n", text):
+        turns = []
+        start = 0
+        for tag in speaker_tag.finditer(paragraph):
+            turns.append((speaker, paragraph[start:tag.start()]))
+            speaker = tag.group(1).strip()
+            start = tag.end()
+        turns.append((speaker, paragraph[start:]))
+        turns = [(who, clean(turn, is_transcript)) for (who, turn) in turns]
+        turns = [(who, turn) for (who, turn) in turns if turn]
+        if turns:
+            paragraphs.append(turns)
 
-    for (old, new) in replace_subs:
-        text = re.sub(old, new, text)
+    sentences = []
+    speakers = []
+    paragraph_starts = []
+    for paragraph in paragraphs:
+        paragraph_starts.append(len(sentences))
+        for (who, turn) in paragraph:
+            turn_sentences = list(nlp(turn).sents)
+            sentences.extend(turn_sentences)
+            speakers.extend([who] * len(turn_sentences))
 
-    text = text.strip()
-    doc = nlp(text)
+    document = {
+        "lang": nlp.lang,
+        "paragraphs": paragraph_starts,
+        # Who says each sentence, left out of texts that aren't transcripts
+        **({"speakers": speakers} if any(speakers) else {}),
+        "sentences": [
+            [
+                {
+                    "text": token.text,
+                    "lemma": (token.lemma_ or token.text).lower(),
+                    "pos": "PUNCT" if token.text in punctuation_marks else token.pos_,
+                }
+                for token in sentence
+            ]
+            for sentence in sentences
+        ],
+    }
 
-    outfile_name = args.file.split('.')[0]
-    outfile_path = "{}.json".format(outfile_name)
-    with open(outfile_path, "w") as outfile:
-        outfile.write("""{\n""")
-        outfile.write("""  "sentences": [\n""")
-        outfile.write("""    [\n""");
-        for sentence_i, sentence in enumerate(doc.sents):
-            if sentence_i > 0:
-                outfile.write(""",\n    [\n""")
-            tok_end=","
-            for token_i, token in enumerate(sentence):
-                if token_i > 0:
-                    outfile.write(""",\n""")
-                pos = "PUNCT" if token.text in punctuation_marks else token.pos_
-                outfile.write("""      {}\n""".format("{"))
-                outfile.write("""        "text":"{}",\n""".format(token.text))
-                outfile.write("""        "lemma":"{}",\n""".format(token.lemma_.lower()))
-                outfile.write("""        "pos":"{}"\n""".format(pos))
-                outfile.write("""      {}""".format("}"))
-            outfile.write("""\n""")
-            outfile.write("""    ]""")
-        outfile.write("""\n""")
-        outfile.write("""  ]\n""")
-        outfile.write("""}""")
-    print("Results written into {}.".format(outfile_path))
+    if args.stdout:
+        json.dump(document, sys.stdout, ensure_ascii=False)
+        sys.stdout.write("\n")
+    else:
+        outfile_name = args.file.split('.')[0]
+        outfile_path = "{}.json".format(outfile_name)
+        with open(outfile_path, "w") as outfile:
+            json.dump(document, outfile, ensure_ascii=False, indent=2)
+        print("Results written into {}.".format(outfile_path), file=log)
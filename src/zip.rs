@@ -0,0 +1,277 @@
+use crate::lemmatizer::LemmatizerError;
+use flate2::read::DeflateDecoder;
+use flate2::write::DeflateEncoder;
+use flate2::{Compression, Crc};
+use std::convert::TryFrom;
+use std::io::{self, Read, Write};
+
+const END_OF_CENTRAL_DIRECTORY: u32 = 0x0605_4b50;
+const CENTRAL_DIRECTORY_ENTRY: u32 = 0x0201_4b50;
+const LOCAL_FILE_HEADER: u32 = 0x0403_4b50;
+
+/// January 1st 1980, the earliest date zip archives can hold
+const DOS_EPOCH: u16 = 0x21;
+
+/// Files in a zip archive held in memory, enough to read EPUBs: stored or
+/// deflated entries, checked against their CRC-32, without encryption or
+/// ZIP64
+pub(crate) struct ZipArchive {
+    data: Vec<u8>,
+    entries: Vec<Entry>,
+}
+
+struct Entry {
+    name: String,
+    method: u16,
+    crc: u32,
+    compressed_size: usize,
+    size: usize,
+    header_offset: usize,
+}
+
+fn malformed(reason: &str) -> LemmatizerError {
+    LemmatizerError::MalformedFile(format!("zip archive: {}", reason))
+}
+
+impl ZipArchive {
+    pub(crate) fn new(data: Vec<u8>) -> Result<ZipArchive, LemmatizerError> {
+        // The end record is last, followed by a comment of up to 64 KiB
+        let search_start = data.len().saturating_sub(22 + 0xFFFF);
+        let end = (search_start..data.len().saturating_sub(21))
+            .rev()
+            .find(|i| read_u32(&data, *i) == Some(END_OF_CENTRAL_DIRECTORY))
+            .ok_or_else(|| malformed("no central directory"))?;
+        let count = read_u16(&data, end + 10).ok_or_else(|| malformed("truncated"))?;
+        let mut offset = read_u32(&data, end + 16).ok_or_else(|| malformed("truncated"))? as usize;
+
+        let mut entries = Vec::with_capacity(count as usize);
+        for _ in 0..count {
+            if read_u32(&data, offset) != Some(CENTRAL_DIRECTORY_ENTRY) {
+                return Err(malformed("bad central directory entry"));
+            }
+            let field =
+                |at: usize| read_u16(&data, offset + at).ok_or_else(|| malformed("truncated"));
+            let wide =
+                |at: usize| read_u32(&data, offset + at).ok_or_else(|| malformed("truncated"));
+            if field(8)? & 1 != 0 {
+                return Err(malformed("encrypted entries aren't supported"));
+            }
+            let (compressed_size, size, header_offset) = (wide(20)?, wide(24)?, wide(42)?);
+            if [compressed_size, size, header_offset].contains(&u32::MAX) {
+                return Err(malformed("ZIP64 archives aren't supported"));
+            }
+            let name_len = field(28)? as usize;
+            let name = data
+                .get(offset + 46..offset + 46 + name_len)
+                .ok_or_else(|| malformed("truncated"))?;
+            entries.push(Entry {
+                name: String::from_utf8_lossy(name).to_string(),
+                method: field(10)?,
+                crc: wide(16)?,
+                compressed_size: compressed_size as usize,
+                size: size as usize,
+                header_offset: header_offset as usize,
+            });
+            offset += 46 + name_len + field(30)? as usize + field(32)? as usize;
+        }
+        Ok(ZipArchive { data, entries })
+    }
+
+    /// Contents of the file at `name`, a path within the archive
+    pub(crate) fn read(&self, name: &str) -> Result<Vec<u8>, LemmatizerError> {
+        let entry = self
+            .entries
+            .iter()
+            .find(|entry| entry.name == name)
+            .ok_or_else(|| malformed(&format!("no file {}", name)))?;
+        let header = entry.header_offset;
+        if read_u32(&self.data, header) != Some(LOCAL_FILE_HEADER) {
+            return Err(malformed("bad local file header"));
+        }
+        let name_len = read_u16(&self.data, header + 26).ok_or_else(|| malformed("truncated"))?;
+        let extra_len = read_u16(&self.data, header + 28).ok_or_else(|| malformed("truncated"))?;
+        let start = header + 30 + name_len as usize + extra_len as usize;
+        let compressed = self
+            .data
+            .get(start..start + entry.compressed_size)
+            .ok_or_else(|| malformed("truncated"))?;
+
+        // The sizes in the directory can't be trusted to allocate by, and
+        // a small entry can inflate without limit, so at most one byte past
+        // the expected size is read, enough to fail the size check below
+        let mut contents = Vec::new();
+        match entry.method {
+            0 => contents.extend_from_slice(compressed),
+            8 => {
+                DeflateDecoder::new(compressed)
+                    .take(entry.size as u64 + 1)
+                    .read_to_end(&mut contents)
+                    .map_err(LemmatizerError::FileIOFailed)?;
+            }
+            method => return Err(malformed(&format!("compression method {}", method))),
+        }
+        if contents.len() != entry.size {
+            return Err(malformed(&format!("{} has the wrong size", name)));
+        }
+        let mut crc = Crc::new();
+        crc.update(&contents);
+        if crc.sum() != entry.crc {
+            return Err(malformed(&format!("{} is corrupt", name)));
+        }
+        Ok(contents)
+    }
+}
+
+/// A zip archive of `files`, each a path and its contents, deflated
+pub(crate) fn write_zip(files: &[(&str, &[u8])]) -> io::Result<Vec<u8>> {
+    let too_large = || io::Error::new(io::ErrorKind::InvalidInput, "too large for a zip archive");
+    let mut archive = Vec::new();
+    let mut directory = Vec::new();
+    for (name, contents) in files {
+        let mut encoder = DeflateEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(contents)?;
+        let compressed = encoder.finish()?;
+        let mut crc = Crc::new();
+        crc.update(contents);
+        let size = u32::try_from(contents.len()).map_err(|_| too_large())?;
+        let compressed_size = u32::try_from(compressed.len()).map_err(|_| too_large())?;
+        let header_offset = u32::try_from(archive.len()).map_err(|_| too_large())?;
+        let name_len = u16::try_from(name.len()).map_err(|_| too_large())?;
+
+        // Version needed, flags, method, time, date, CRC and sizes, shared
+        // by both headers
+        let mut common = Vec::with_capacity(26);
+        for field in [20, 0, 8, 0, DOS_EPOCH] {
+            common.extend_from_slice(&u16::to_le_bytes(field));
+        }
+        for field in [crc.sum(), compressed_size, size] {
+            common.extend_from_slice(&u32::to_le_bytes(field));
+        }
+        common.extend_from_slice(&name_len.to_le_bytes());
+        // No extra field
+        common.extend_from_slice(&[0, 0]);
+
+        archive.extend_from_slice(&LOCAL_FILE_HEADER.to_le_bytes());
+        archive.extend_from_slice(&common);
+        archive.extend_from_slice(name.as_bytes());
+        archive.extend_from_slice(&compressed);
+
+        directory.extend_from_slice(&CENTRAL_DIRECTORY_ENTRY.to_le_bytes());
+        // Made by version 2.0
+        directory.extend_from_slice(&20u16.to_le_bytes());
+        directory.extend_from_slice(&common);
+        // No comment, disk 0, no attributes
+        directory.extend_from_slice(&[0; 10]);
+        directory.extend_from_slice(&header_offset.to_le_bytes());
+        directory.extend_from_slice(name.as_bytes());
+    }
+
+    let count = u16::try_from(files.len()).map_err(|_| too_large())?;
+    let directory_offset = u32::try_from(archive.len()).map_err(|_| too_large())?;
+    archive.extend_from_slice(&directory);
+    archive.extend_from_slice(&END_OF_CENTRAL_DIRECTORY.to_le_bytes());
+    // Disk numbers
+    archive.extend_from_slice(&[0; 4]);
+    archive.extend_from_slice(&count.to_le_bytes());
+    archive.extend_from_slice(&count.to_le_bytes());
+    archive.extend_from_slice(&(directory.len() as u32).to_le_bytes());
+    archive.extend_from_slice(&directory_offset.to_le_bytes());
+    // No comment
+    archive.extend_from_slice(&[0, 0]);
+    Ok(archive)
+}
+
+fn read_u16(data: &[u8], at: usize) -> Option<u16> {
+    let bytes = data.get(at..at + 2)?;
+    Some(u16::from_le_bytes([bytes[0], bytes[1]]))
+}
+
+fn read_u32(data: &[u8], at: usize) -> Option<u32> {
+    let bytes = data.get(at..at + 4)?;
+    Some(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Offset of the central directory entry of the `i`th file
+    fn directory_entry(archive: &[u8], i: usize) -> usize {
+        (0..archive.len())
+            .filter(|at| read_u32(archive, *at) == Some(CENTRAL_DIRECTORY_ENTRY))
+            .nth(i)
+            .unwrap()
+    }
+
+    fn sample() -> Vec<u8> {
+        write_zip(&[
+            ("mimetype", b"application/epub+zip"),
+            ("OEBPS/texto.txt", "¿Qué tal? ".repeat(50).as_bytes()),
+            ("vacío", b""),
+        ])
+        .unwrap()
+    }
+
+    #[test]
+    fn written_archives_read_back() {
+        let archive = ZipArchive::new(sample()).unwrap();
+        assert_eq!(archive.read("mimetype").unwrap(), b"application/epub+zip");
+        assert_eq!(
+            archive.read("OEBPS/texto.txt").unwrap(),
+            "¿Qué tal? ".repeat(50).as_bytes()
+        );
+        assert_eq!(archive.read("vacío").unwrap(), b"");
+        assert!(matches!(
+            archive.read("texto.txt"),
+            Err(LemmatizerError::MalformedFile(_))
+        ));
+    }
+
+    #[test]
+    fn truncated_archives_fail() {
+        let mut data = sample();
+        // Without its end record
+        data.truncate(data.len() - 10);
+        assert!(matches!(
+            ZipArchive::new(data),
+            Err(LemmatizerError::MalformedFile(_))
+        ));
+        assert!(ZipArchive::new(Vec::new()).is_err());
+
+        // With an entry running past the end of the data
+        let mut data = sample();
+        let entry = directory_entry(&data, 1);
+        data[entry + 20..entry + 24].copy_from_slice(&0x7F00_0000u32.to_le_bytes());
+        let archive = ZipArchive::new(data).unwrap();
+        assert!(matches!(
+            archive.read("OEBPS/texto.txt"),
+            Err(LemmatizerError::MalformedFile(_))
+        ));
+    }
+
+    #[test]
+    fn entries_larger_than_listed_fail() {
+        let mut data = sample();
+        let entry = directory_entry(&data, 1);
+        data[entry + 24..entry + 28].copy_from_slice(&10u32.to_le_bytes());
+        let archive = ZipArchive::new(data).unwrap();
+        assert!(matches!(
+            archive.read("OEBPS/texto.txt"),
+            Err(LemmatizerError::MalformedFile(_))
+        ));
+        assert!(archive.read("mimetype").is_ok());
+    }
+
+    #[test]
+    fn corrupt_entries_fail() {
+        let mut data = sample();
+        let entry = directory_entry(&data, 0);
+        data[entry + 16] ^= 1;
+        let archive = ZipArchive::new(data).unwrap();
+        assert!(matches!(
+            archive.read("mimetype"),
+            Err(LemmatizerError::MalformedFile(_))
+        ));
+        assert!(archive.read("OEBPS/texto.txt").is_ok());
+    }
+}
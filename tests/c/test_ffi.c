@@ -0,0 +1,123 @@
+/* Exercises the C API. Built and run by tests/ffi.rs, under valgrind or
+ * AddressSanitizer, with the path of a tokenized document as argument. */
+
+#include "vocrab.h"
+
+#include <stdio.h>
+#include <string.h>
+
+static int failures = 0;
+
+#define CHECK(cond)                                                        \
+  do {                                                                     \
+    if (!(cond)) {                                                         \
+      fprintf(stderr, "%s:%d: check failed: %s\n", __FILE__, __LINE__,     \
+              #cond);                                                      \
+      failures++;                                                          \
+    }                                                                      \
+  } while (0)
+
+static int contains(const VocrabStrings *strings, const char *wanted) {
+  for (size_t i = 0; i < vocrab_strings_len(strings); i++) {
+    if (strcmp(vocrab_strings_get(strings, i), wanted) == 0) {
+      return 1;
+    }
+  }
+  return 0;
+}
+
+static void test_file(const char *path) {
+  VocrabLemmatizer *lemmatizer = vocrab_lemmatizer_new();
+  CHECK(vocrab_load_file(lemmatizer, path) == VOCRAB_STATUS_OK);
+  CHECK(vocrab_last_error_message() == NULL);
+  CHECK(vocrab_document_count(lemmatizer) == 1);
+
+  VocrabStrings *lemmas = vocrab_get_lemmas(lemmatizer);
+  CHECK(lemmas != NULL);
+  CHECK(vocrab_strings_len(lemmas) > 100);
+  size_t previous = (size_t)-1;
+  for (size_t i = 0; i < vocrab_strings_len(lemmas); i++) {
+    size_t count = vocrab_get_lemma_count(lemmatizer, vocrab_strings_get(lemmas, i));
+    CHECK(count > 0 && count <= previous);
+    previous = count;
+  }
+  CHECK(vocrab_strings_get(lemmas, vocrab_strings_len(lemmas)) == NULL);
+  vocrab_strings_free(lemmas);
+
+  VocrabStrings *forms = vocrab_get_forms(lemmatizer, "droit");
+  CHECK(contains(forms, "droit") && contains(forms, "droits"));
+  vocrab_strings_free(forms);
+
+  VocrabUsages *usages = vocrab_get_usages(lemmatizer, "droit", "droits");
+  CHECK(vocrab_usages_len(usages) > 0);
+  VocrabUsage usage;
+  CHECK(vocrab_usages_get(usages, 0, &usage));
+  CHECK(strcmp(usage.word, "droits") == 0);
+  CHECK(!vocrab_usages_get(usages, vocrab_usages_len(usages), &usage));
+  size_t form_usages = vocrab_usages_len(usages);
+  vocrab_usages_free(usages);
+
+  VocrabUsages *all = vocrab_get_usages(lemmatizer, "droit", NULL);
+  CHECK(vocrab_usages_len(all) == vocrab_get_lemma_count(lemmatizer, "droit"));
+  CHECK(vocrab_usages_len(all) > form_usages);
+  vocrab_usages_free(all);
+
+  vocrab_lemmatizer_free(lemmatizer);
+}
+
+static void test_buffer(void) {
+  /* Not NUL-terminated where the length ends */
+  const char json[] = "{\"lang\": \"es\", \"sentences\": [[{\"text\": \"Gatos\", "
+                      "\"lemma\": \"gato\", \"pos\": \"NOUN\"}]]}trailing";
+  VocrabLemmatizer *lemmatizer = vocrab_lemmatizer_new();
+  CHECK(vocrab_load_buffer(lemmatizer, "gatos", json, strlen(json) - strlen("trailing")) ==
+        VOCRAB_STATUS_OK);
+  CHECK(vocrab_get_lemma_count(lemmatizer, "gato") == 1);
+
+  VocrabStrings *forms = vocrab_get_forms(lemmatizer, "nothing");
+  CHECK(forms != NULL && vocrab_strings_len(forms) == 0);
+  vocrab_strings_free(forms);
+
+  CHECK(vocrab_load_buffer(lemmatizer, "bad", "{", 1) == VOCRAB_STATUS_JSON_PARSE_FAILED);
+  CHECK(vocrab_last_error_message() != NULL);
+  CHECK(vocrab_load_buffer(lemmatizer, "bad", "\xff", 1) == VOCRAB_STATUS_INVALID_UTF8);
+  CHECK(vocrab_document_count(lemmatizer) == 1);
+  vocrab_lemmatizer_free(lemmatizer);
+}
+
+static void test_errors(void) {
+  VocrabLemmatizer *lemmatizer = vocrab_lemmatizer_new();
+  CHECK(vocrab_load_file(lemmatizer, "/nonexistent/document.json") ==
+        VOCRAB_STATUS_FILE_IO_FAILED);
+  const char *message = vocrab_last_error_message();
+  CHECK(message != NULL && strstr(message, "/nonexistent/document.json") != NULL);
+
+  CHECK(vocrab_load_file(NULL, "x.json") == VOCRAB_STATUS_NULL_ARGUMENT);
+  CHECK(vocrab_load_file(lemmatizer, NULL) == VOCRAB_STATUS_NULL_ARGUMENT);
+  CHECK(vocrab_get_lemmas(NULL) == NULL);
+  CHECK(vocrab_get_forms(lemmatizer, NULL) == NULL);
+  CHECK(vocrab_get_usages(lemmatizer, NULL, NULL) == NULL);
+  CHECK(vocrab_strings_len(NULL) == 0);
+  CHECK(vocrab_usages_len(NULL) == 0);
+
+  vocrab_strings_free(NULL);
+  vocrab_usages_free(NULL);
+  vocrab_lemmatizer_free(lemmatizer);
+  vocrab_lemmatizer_free(NULL);
+}
+
+int main(int argc, char **argv) {
+  if (argc != 2) {
+    fprintf(stderr, "usage: %s DOCUMENT.json\n", argv[0]);
+    return 2;
+  }
+  test_file(argv[1]);
+  test_buffer();
+  test_errors();
+  if (failures > 0) {
+    fprintf(stderr, "%d checks failed\n", failures);
+    return 1;
+  }
+  printf("ok\n");
+  return 0;
+}
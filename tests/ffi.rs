@@ -0,0 +1,80 @@
+//! Builds tests/c/test_ffi.c against the cdylib and runs it under a memory
+//! checker: valgrind when it's installed, AddressSanitizer otherwise
+#![cfg(unix)]
+
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output};
+
+fn root() -> &'static Path {
+    Path::new(env!("CARGO_MANIFEST_DIR"))
+}
+
+/// Directory the cdylib is built into, next to the binaries
+fn lib_dir() -> PathBuf {
+    Path::new(env!("CARGO_BIN_EXE_vocrab"))
+        .parent()
+        .unwrap()
+        .to_path_buf()
+}
+
+fn found(program: &str) -> bool {
+    Command::new(program)
+        .arg("--version")
+        .output()
+        .is_ok_and(|output| output.status.success())
+}
+
+fn compile(output: &Path, sanitize: bool) -> Output {
+    let lib_dir = lib_dir();
+    let mut cc = Command::new("cc");
+    cc.args(["-std=c99", "-Wall", "-Wextra", "-Werror", "-g"])
+        .arg("-I")
+        .arg(root().join("include"))
+        .arg(root().join("tests/c/test_ffi.c"))
+        .arg("-o")
+        .arg(output)
+        .arg(format!("-L{}", lib_dir.display()))
+        .arg(format!("-Wl,-rpath,{}", lib_dir.display()))
+        .arg("-lvocrab");
+    if sanitize {
+        cc.args(["-fsanitize=address,undefined", "-fno-omit-frame-pointer"]);
+    }
+    cc.output().expect("cc should run")
+}
+
+#[test]
+fn c_api_under_memory_checker() {
+    if !found("cc") {
+        eprintln!("skipping: no C compiler");
+        return;
+    }
+    let valgrind = found("valgrind");
+    let program = lib_dir().join(format!("test_ffi-{}", std::process::id()));
+    let compiled = compile(&program, !valgrind);
+    assert!(
+        compiled.status.success(),
+        "{}",
+        String::from_utf8_lossy(&compiled.stderr)
+    );
+
+    let document = root().join("data/rights-fr.json");
+    let mut run = if valgrind {
+        let mut valgrind = Command::new("valgrind");
+        valgrind.args(["--error-exitcode=99", "--leak-check=full", "--quiet"]);
+        valgrind.arg(&program);
+        valgrind
+    } else {
+        let mut run = Command::new(&program);
+        run.env("ASAN_OPTIONS", "detect_leaks=1:halt_on_error=1");
+        run
+    };
+    let output = run.arg(&document).output().unwrap();
+    let _ = std::fs::remove_file(&program);
+    assert!(
+        output.status.success(),
+        "{}{}",
+        String::from_utf8_lossy(&output.stdout),
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "ok\n");
+}
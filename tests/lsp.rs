@@ -0,0 +1,229 @@
+//! Drives `vocrab lsp` over its stdio JSON-RPC transport
+#![cfg(unix)]
+
+use serde_json::{json, Value};
+use std::fs;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::path::PathBuf;
+use std::process::{Child, ChildStdin, ChildStdout, Command, Stdio};
+
+const URI: &str = "file:///notes/gatos.md";
+const TEXT: &str = "Los gatos comen.\nEl perro duerme.";
+
+/// What the analyzer script prints for `TEXT`, whatever it's given
+const ANALYZED: &str = r#"{"lang": "es", "sentences": [
+  [{"text": "Los", "lemma": "el", "pos": "DET"},
+   {"text": "gatos", "lemma": "gato", "pos": "NOUN"},
+   {"text": "comen", "lemma": "comer", "pos": "VERB"},
+   {"text": ".", "lemma": ".", "pos": "PUNCT"}],
+  [{"text": "El", "lemma": "el", "pos": "DET"},
+   {"text": "perro", "lemma": "perro", "pos": "NOUN"},
+   {"text": "duerme", "lemma": "dormir", "pos": "VERB"},
+   {"text": ".", "lemma": ".", "pos": "PUNCT"}]]}"#;
+
+struct Client {
+    child: Child,
+    stdin: ChildStdin,
+    stdout: BufReader<ChildStdout>,
+    next_id: u64,
+    dir: PathBuf,
+}
+
+impl Client {
+    fn start(name: &str, known: &str) -> Client {
+        let dir = std::env::temp_dir().join(format!("vocrab-lsp-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        let script = dir.join("analyze.sh");
+        fs::write(
+            &script,
+            format!("cat > /dev/null\ncat <<'EOF'\n{}\nEOF\n", ANALYZED),
+        )
+        .unwrap();
+        fs::write(dir.join("known.txt"), known).unwrap();
+
+        let mut child = Command::new(env!("CARGO_BIN_EXE_vocrab"))
+            .args(["lsp", "--lang", "es", "--known"])
+            .arg(dir.join("known.txt"))
+            .arg("--analyzer")
+            .arg(format!("sh {}", script.display()))
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()
+            .expect("vocrab lsp should start");
+        let stdin = child.stdin.take().unwrap();
+        let stdout = BufReader::new(child.stdout.take().unwrap());
+        let mut client = Client {
+            child,
+            stdin,
+            stdout,
+            next_id: 1,
+            dir,
+        };
+        let capabilities = client.request("initialize", json!({ "capabilities": {} }));
+        assert_eq!(capabilities["capabilities"]["hoverProvider"], true);
+        client.notify("initialized", json!({}));
+        client
+    }
+
+    fn send(&mut self, message: Value) {
+        let body = message.to_string();
+        write!(self.stdin, "Content-Length: {}\r\n\r\n{}", body.len(), body).unwrap();
+        self.stdin.flush().unwrap();
+    }
+
+    fn receive(&mut self) -> Value {
+        let mut length = 0;
+        loop {
+            let mut line = String::new();
+            assert!(
+                self.stdout.read_line(&mut line).unwrap() > 0,
+                "server closed"
+            );
+            let line = line.trim_end();
+            if line.is_empty() {
+                break;
+            }
+            if let Some(value) = line.strip_prefix("Content-Length: ") {
+                length = value.parse().unwrap();
+            }
+        }
+        let mut body = vec![0; length];
+        self.stdout.read_exact(&mut body).unwrap();
+        serde_json::from_slice(&body).unwrap()
+    }
+
+    fn notify(&mut self, method: &str, params: Value) {
+        self.send(json!({ "jsonrpc": "2.0", "method": method, "params": params }));
+    }
+
+    /// Sends a request and returns its result, keeping notifications that
+    /// arrive first
+    fn request_with_notifications(&mut self, method: &str, params: Value) -> (Value, Vec<Value>) {
+        let id = self.next_id;
+        self.next_id += 1;
+        self.send(json!({ "jsonrpc": "2.0", "id": id, "method": method, "params": params }));
+        let mut notifications = Vec::new();
+        loop {
+            let message = self.receive();
+            if message["id"] == id {
+                assert!(message.get("error").is_none(), "{}", message);
+                return (message["result"].clone(), notifications);
+            }
+            notifications.push(message);
+        }
+    }
+
+    fn request(&mut self, method: &str, params: Value) -> Value {
+        self.request_with_notifications(method, params).0
+    }
+
+    fn open(&mut self) -> Value {
+        self.notify(
+            "textDocument/didOpen",
+            json!({ "textDocument": {
+                "uri": URI, "languageId": "markdown", "version": 1, "text": TEXT,
+            } }),
+        );
+        let published = self.receive();
+        assert_eq!(published["method"], "textDocument/publishDiagnostics");
+        published["params"]["diagnostics"].clone()
+    }
+}
+
+impl Drop for Client {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+        let _ = fs::remove_dir_all(&self.dir);
+    }
+}
+
+fn position(line: u64, character: u64) -> Value {
+    json!({ "line": line, "character": character })
+}
+
+fn range(start: (u64, u64), end: (u64, u64)) -> Value {
+    json!({ "start": position(start.0, start.1), "end": position(end.0, end.1) })
+}
+
+#[test]
+fn unknown_words_are_published_as_diagnostics() {
+    let mut client = Client::start("diagnostics", "el\ncomer\n");
+    let diagnostics = client.open();
+    let ranges: Vec<&Value> = diagnostics
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|d| &d["range"])
+        .collect();
+    assert_eq!(
+        ranges,
+        vec![
+            &range((0, 4), (0, 9)),
+            &range((1, 3), (1, 8)),
+            &range((1, 9), (1, 15))
+        ]
+    );
+    assert_eq!(diagnostics[0]["message"], "Unknown word: gatos (gato)");
+    assert_eq!(diagnostics[0]["source"], "vocrab");
+}
+
+#[test]
+fn hover_shows_lemma_and_frequency() {
+    let mut client = Client::start("hover", "");
+    client.open();
+    let hover = client.request(
+        "textDocument/hover",
+        json!({ "textDocument": { "uri": URI }, "position": position(1, 12) }),
+    );
+    let text = hover["contents"]["value"].as_str().unwrap();
+    assert!(text.contains("**dormir** · VERB"), "{}", text);
+    assert!(text.contains("form *duerme*"), "{}", text);
+    assert!(text.contains("1× in this document"), "{}", text);
+    assert_eq!(hover["range"], range((1, 9), (1, 15)));
+
+    let nothing = client.request(
+        "textDocument/hover",
+        json!({ "textDocument": { "uri": URI }, "position": position(0, 15) }),
+    );
+    assert_eq!(nothing, Value::Null);
+}
+
+#[test]
+fn code_action_marks_a_word_known() {
+    let mut client = Client::start("action", "el\n");
+    client.open();
+    let actions = client.request(
+        "textDocument/codeAction",
+        json!({
+            "textDocument": { "uri": URI },
+            "range": range((0, 5), (0, 5)),
+            "context": { "diagnostics": [] },
+        }),
+    );
+    assert_eq!(actions.as_array().unwrap().len(), 1);
+    assert_eq!(actions[0]["title"], "Mark \"gato\" as known");
+
+    let command = actions[0]["command"].clone();
+    let (_, notifications) = client.request_with_notifications(
+        "workspace/executeCommand",
+        json!({ "command": command["command"], "arguments": command["arguments"] }),
+    );
+    let diagnostics = &notifications[0]["params"]["diagnostics"];
+    assert!(diagnostics
+        .as_array()
+        .unwrap()
+        .iter()
+        .all(|d| d["data"]["lemma"] != "gato"));
+    let known = fs::read_to_string(client.dir.join("known.txt")).unwrap();
+    assert_eq!(known, "el\ngato\n");
+}
+
+#[test]
+fn shutdown_then_exit_ends_cleanly() {
+    let mut client = Client::start("exit", "");
+    assert_eq!(client.request("shutdown", Value::Null), Value::Null);
+    client.notify("exit", Value::Null);
+    assert!(client.child.wait().unwrap().success());
+}
@@ -0,0 +1,225 @@
+//! Runs `vocrab serve` on an ephemeral port and talks to it over HTTP
+
+use serde_json::{json, Value};
+use std::fs;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::TcpStream;
+use std::path::PathBuf;
+use std::process::{Child, Command, Stdio};
+
+/// A running server, stopped when dropped
+struct Server {
+    child: Child,
+    addr: String,
+}
+
+impl Server {
+    fn start(args: &[&str]) -> Server {
+        let mut child = Command::new(env!("CARGO_BIN_EXE_vocrab"))
+            .arg("serve")
+            .args(args)
+            .args(["--port", "0"])
+            .current_dir(env!("CARGO_MANIFEST_DIR"))
+            .stdout(Stdio::piped())
+            .spawn()
+            .expect("vocrab serve should start");
+        let mut line = String::new();
+        BufReader::new(child.stdout.take().unwrap())
+            .read_line(&mut line)
+            .unwrap();
+        let addr = line
+            .trim()
+            .strip_prefix("Listening on http://")
+            .unwrap_or_else(|| panic!("unexpected first line {:?}", line))
+            .to_string();
+        Server { child, addr }
+    }
+
+    /// Sends a request and returns the status and the parsed JSON body
+    fn request(&self, method: &str, path: &str, body: Option<(&str, &str)>) -> (u16, Value) {
+        let mut stream = TcpStream::connect(&self.addr).unwrap();
+        let mut request = format!("{} {} HTTP/1.1\r\nHost: {}\r\n", method, path, self.addr);
+        if let Some((content_type, body)) = body {
+            request.push_str(&format!(
+                "Content-Type: {}\r\nContent-Length: {}\r\n\r\n{}",
+                content_type,
+                body.len(),
+                body
+            ));
+        } else {
+            request.push_str("\r\n");
+        }
+        stream.write_all(request.as_bytes()).unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+        let (head, body) = response.split_once("\r\n\r\n").unwrap();
+        let status = head.split_whitespace().nth(1).unwrap().parse().unwrap();
+        let body = if body.is_empty() {
+            Value::Null
+        } else {
+            serde_json::from_str(body).unwrap()
+        };
+        (status, body)
+    }
+
+    fn get(&self, path: &str) -> (u16, Value) {
+        self.request("GET", path, None)
+    }
+}
+
+impl Drop for Server {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+/// A fresh directory for files a test writes
+fn temp_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("vocrab-{}-{}", name, std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+#[test]
+fn lemmas_are_ranked_by_count() {
+    let server = Server::start(&["data/rights-fr.json"]);
+    let (status, lemmas) = server.get("/lemmas?limit=3");
+    assert_eq!(status, 200);
+    let lemmas = lemmas.as_array().unwrap();
+    assert_eq!(lemmas.len(), 3);
+    assert_eq!(lemmas[0]["rank"], 1);
+    assert!(lemmas[0]["count"].as_u64() >= lemmas[1]["count"].as_u64());
+
+    let (_, nouns) = server.get("/lemmas?pos=NOUN&limit=5");
+    assert!(nouns
+        .as_array()
+        .unwrap()
+        .iter()
+        .all(|row| row["pos"] == "NOUN"));
+}
+
+#[test]
+fn forms_and_usages_of_a_lemma() {
+    let server = Server::start(&["data/rights-fr.json"]);
+    let (status, forms) = server.get("/lemmas/droit/forms");
+    assert_eq!(status, 200);
+    let forms: Vec<&str> = forms
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|row| row["form"].as_str().unwrap())
+        .collect();
+    assert!(forms.contains(&"droit") && forms.contains(&"droits"));
+
+    let (status, usages) = server.get("/lemmas/droit/usages?form=droits&limit=2");
+    assert_eq!(status, 200);
+    let usages = usages.as_array().unwrap();
+    assert_eq!(usages.len(), 2);
+    assert_eq!(usages[0]["document"], "rights-fr");
+    assert_eq!(usages[0]["word"], "droits");
+
+    assert_eq!(server.get("/lemmas/zzzz/forms").0, 404);
+    assert_eq!(server.get("/lemmas/zzzz/usages").0, 404);
+}
+
+#[test]
+fn known_words_change_coverage_and_are_saved() {
+    let dir = temp_dir("known");
+    let known_path = dir.join("known.txt");
+    let server = Server::start(&[
+        "data/rights-fr.json",
+        "--known",
+        known_path.to_str().unwrap(),
+    ]);
+    let (_, before) = server.get("/coverage");
+    assert_eq!(before["documents"][0]["name"], "rights-fr");
+
+    let (status, record) = server.request("PUT", "/known/Droit", None);
+    assert_eq!(status, 200);
+    assert_eq!(record, json!({ "lemma": "droit", "known": true }));
+    assert_eq!(fs::read_to_string(&known_path).unwrap(), "droit\n");
+    assert_eq!(server.get("/known").1, json!(["droit"]));
+
+    let (_, after) = server.get("/coverage");
+    assert!(after["library"]["known"].as_u64() > before["library"]["known"].as_u64());
+
+    let (_, record) = server.request("DELETE", "/known/droit", None);
+    assert_eq!(record["known"], false);
+    assert_eq!(fs::read_to_string(&known_path).unwrap(), "");
+    let _ = fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn analyzed_documents_can_be_added() {
+    let server = Server::start(&[]);
+    assert_eq!(server.get("/documents").1, json!([]));
+
+    let document = json!({
+        "lang": "es",
+        "sentences": [[
+            { "text": "Los", "lemma": "el", "pos": "DET" },
+            { "text": "gatos", "lemma": "gato", "pos": "NOUN" },
+            { "text": ".", "lemma": ".", "pos": "PUNCT" }
+        ]]
+    });
+    let (status, added) = server.request(
+        "POST",
+        "/documents?name=gatos",
+        Some(("application/json", &document.to_string())),
+    );
+    assert_eq!(status, 201);
+    assert_eq!(added["name"], "gatos");
+    assert_eq!(added["language"], "es");
+    assert_eq!(server.get("/lemmas/gato/forms").1[0]["form"], "gatos");
+
+    let (status, _) = server.request(
+        "POST",
+        "/documents",
+        Some(("application/json", "{\"sentences\": 3}")),
+    );
+    assert_eq!(status, 422);
+}
+
+#[cfg(unix)]
+#[test]
+fn raw_text_goes_through_the_analyzer() {
+    let dir = temp_dir("analyzer");
+    let script = dir.join("analyze.sh");
+    fs::write(
+        &script,
+        "cat > /dev/null\n\
+         echo '{\"lang\": \"'\"$1\"'\", \"sentences\": [[{\"text\": \"Hola\", \"lemma\": \"hola\", \"pos\": \"INTJ\"}]]}'\n",
+    )
+    .unwrap();
+    let analyzer = format!("sh {}", script.display());
+    let server = Server::start(&["--analyzer", &analyzer]);
+
+    let (status, added) = server.request(
+        "POST",
+        "/documents?name=saludo&lang=es",
+        Some(("text/plain; charset=utf-8", "Hola.")),
+    );
+    assert_eq!(status, 201, "{}", added);
+    assert_eq!(added["language"], "es");
+    assert_eq!(server.get("/lemmas").1[0]["lemma"], "hola");
+
+    let (status, _) = server.request("POST", "/documents", Some(("text/plain", "Hola.")));
+    assert_eq!(status, 400);
+    let _ = fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn unknown_routes_and_methods_are_rejected() {
+    let server = Server::start(&[]);
+    assert_eq!(server.get("/nothing").0, 404);
+    assert_eq!(server.request("DELETE", "/lemmas", None).0, 405);
+    assert_eq!(server.get("/lemmas?limit=many").0, 400);
+    assert_eq!(
+        server
+            .request("POST", "/documents", Some(("text/html", "<p>")))
+            .0,
+        415
+    );
+}